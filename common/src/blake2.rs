@@ -0,0 +1,243 @@
+//! In-circuit Blake2b hashing.
+//!
+//! Substrate derives storage keys with Blake2b, so proving that a storage proof belongs to a given
+//! key requires hashing inside the circuit. These are only the hashing gadgets: nothing here ties
+//! a hashed key to a storage proof. Words are handled as 64 little-endian bits, which
+//! makes rotations free and XOR a single arithmetic operation per bit. Additions modulo 2^64 are
+//! done on two 32-bit limbs, as a full 64-bit word doesn't fit in a Goldilocks field element.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use plonky2::{
+    field::extension::Extendable,
+    hash::hash_types::RichField,
+    iop::target::{BoolTarget, Target},
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::gadgets::xor;
+
+/// The size of a Blake2b block in bytes.
+pub const BLAKE2B_BLOCK_BYTES: usize = 128;
+/// The output size of Blake2_128 in bytes.
+pub const BLAKE2_128_OUTPUT_BYTES: usize = 16;
+/// The output size of Blake2_256 in bytes, the hasher of Substrate state roots.
+pub const BLAKE2_256_OUTPUT_BYTES: usize = 32;
+
+const BLAKE2B_ROUNDS: usize = 12;
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// A 64-bit word as little-endian bits.
+type U64Target = [BoolTarget; 64];
+
+/// Computes the Blake2b hash of `data` with a 16-byte output, i.e. Substrate's `Blake2_128`.
+///
+/// Each target in `data` is a byte, and is range checked to 8 bits. The length of the input is
+/// fixed by the number of targets.
+///
+/// # Returns
+/// - `Vec<Target>`: The 16 bytes of the hash.
+pub fn blake2_128<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    data: &[Target],
+) -> Vec<Target> {
+    blake2b(builder, data, BLAKE2_128_OUTPUT_BYTES)
+}
+
+/// Computes the Blake2b hash of `data` with a 32-byte output, i.e. Substrate's `Blake2_256`.
+///
+/// Each target in `data` is a byte, and is range checked to 8 bits. The length of the input is
+/// fixed by the number of targets.
+///
+/// # Returns
+/// - `Vec<Target>`: The 32 bytes of the hash.
+pub fn blake2_256<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    data: &[Target],
+) -> Vec<Target> {
+    blake2b(builder, data, BLAKE2_256_OUTPUT_BYTES)
+}
+
+/// Computes the unkeyed Blake2b hash of `data` with an `output_len` byte output.
+///
+/// # Returns
+/// - `Vec<Target>`: The bytes of the hash.
+pub fn blake2b<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    data: &[Target],
+    output_len: usize,
+) -> Vec<Target> {
+    assert!(
+        (1..=64).contains(&output_len),
+        "blake2b output length must be between 1 and 64 bytes"
+    );
+
+    // The parameter block only sets the output length and a fanout and depth of one.
+    let mut h: Vec<U64Target> = BLAKE2B_IV
+        .iter()
+        .map(|&iv| constant_word(builder, iv))
+        .collect();
+    h[0] = constant_word(builder, BLAKE2B_IV[0] ^ 0x01010000 ^ output_len as u64);
+
+    let bytes: Vec<Vec<BoolTarget>> = data.iter().map(|&b| builder.split_le(b, 8)).collect();
+    let num_blocks = data.len().div_ceil(BLAKE2B_BLOCK_BYTES).max(1);
+    for block in 0..num_blocks {
+        let start = block * BLAKE2B_BLOCK_BYTES;
+        let m: Vec<U64Target> = (0..16)
+            .map(|i| {
+                let mut word = [builder._false(); 64];
+                for j in 0..8 {
+                    if let Some(byte) = bytes.get(start + 8 * i + j) {
+                        word[8 * j..8 * (j + 1)].copy_from_slice(byte);
+                    }
+                }
+                word
+            })
+            .collect();
+
+        let is_last = block == num_blocks - 1;
+        let counter = if is_last {
+            data.len()
+        } else {
+            start + BLAKE2B_BLOCK_BYTES
+        };
+        compress(builder, &mut h, &m, counter as u128, is_last);
+    }
+
+    h.iter()
+        .flat_map(|word| word.chunks(8))
+        .take(output_len)
+        .map(|bits| builder.le_sum(bits.iter()))
+        .collect()
+}
+
+/// The Blake2b compression function `F`.
+fn compress<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    h: &mut [U64Target],
+    m: &[U64Target],
+    counter: u128,
+    is_last: bool,
+) {
+    // The counter and finalization flag are known when building the circuit, so they are folded
+    // into the initial state as constants.
+    let mut iv = BLAKE2B_IV;
+    iv[4] ^= counter as u64;
+    iv[5] ^= (counter >> 64) as u64;
+    if is_last {
+        iv[6] = !iv[6];
+    }
+
+    let mut v: Vec<U64Target> = h.to_vec();
+    v.extend(iv.iter().map(|&word| constant_word(builder, word)));
+
+    for round in 0..BLAKE2B_ROUNDS {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        g(builder, &mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        g(builder, &mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        g(builder, &mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        g(builder, &mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        g(builder, &mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        g(builder, &mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        g(builder, &mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        g(builder, &mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        let mixed = xor_words(builder, &v[i], &v[i + 8]);
+        h[i] = xor_words(builder, &h[i], &mixed);
+    }
+}
+
+/// The Blake2b mixing function `G`.
+fn g<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    v: &mut [U64Target],
+    [a, b, c, d]: [usize; 4],
+    x: U64Target,
+    y: U64Target,
+) {
+    v[a] = add_words(builder, &[v[a], v[b], x]);
+    v[d] = rotate_right(&xor_words(builder, &v[d], &v[a]), 32);
+    v[c] = add_words(builder, &[v[c], v[d]]);
+    v[b] = rotate_right(&xor_words(builder, &v[b], &v[c]), 24);
+    v[a] = add_words(builder, &[v[a], v[b], y]);
+    v[d] = rotate_right(&xor_words(builder, &v[d], &v[a]), 16);
+    v[c] = add_words(builder, &[v[c], v[d]]);
+    v[b] = rotate_right(&xor_words(builder, &v[b], &v[c]), 63);
+}
+
+fn constant_word<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    value: u64,
+) -> U64Target {
+    core::array::from_fn(|i| builder.constant_bool((value >> i) & 1 == 1))
+}
+
+fn xor_words<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &U64Target,
+    b: &U64Target,
+) -> U64Target {
+    core::array::from_fn(|i| xor(builder, a[i], b[i]))
+}
+
+fn rotate_right(word: &U64Target, n: usize) -> U64Target {
+    core::array::from_fn(|i| word[(i + n) % 64])
+}
+
+/// Adds up to three words modulo 2^64, limb by limb, propagating the carry of the low limb.
+fn add_words<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    words: &[U64Target],
+) -> U64Target {
+    debug_assert!(words.len() <= 3);
+    // Three 32-bit limbs sum to at most 34 bits.
+    const LIMB_SUM_BITS: usize = 34;
+
+    let low_limbs: Vec<Target> = words
+        .iter()
+        .map(|word| builder.le_sum(word[..32].iter()))
+        .collect();
+    let low_sum = builder.add_many(low_limbs);
+    let low_bits = builder.split_le(low_sum, LIMB_SUM_BITS);
+    let carry = builder.le_sum(low_bits[32..].iter());
+
+    let mut high_limbs: Vec<Target> = words
+        .iter()
+        .map(|word| builder.le_sum(word[32..].iter()))
+        .collect();
+    high_limbs.push(carry);
+    let high_sum = builder.add_many(high_limbs);
+    let high_bits = builder.split_le(high_sum, LIMB_SUM_BITS);
+
+    let mut result = [builder._false(); 64];
+    result[..32].copy_from_slice(&low_bits[..32]);
+    result[32..].copy_from_slice(&high_bits[..32]);
+    result
+}
@@ -1,7 +1,19 @@
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use std::{collections::BTreeMap, vec::Vec};
+
 use plonky2::{
-    field::goldilocks_field::GoldilocksField,
-    iop::witness::PartialWitness,
-    plonk::{circuit_builder::CircuitBuilder, config::PoseidonGoldilocksConfig},
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    iop::{
+        generator::GeneratedValues,
+        target::Target,
+        witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite},
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder, circuit_data::CircuitData,
+        config::PoseidonGoldilocksConfig,
+    },
 };
 
 // Plonky2 setup parameters.
@@ -9,6 +21,11 @@ pub const D: usize = 2; // D=2 provides 100-bits of security
 pub type C = PoseidonGoldilocksConfig;
 pub type F = GoldilocksField;
 
+/// A part of a circuit over the Goldilocks field [`F`], with its targets and their witness.
+///
+/// The trait stays concrete over [`F`] and [`D`], since every fragment fills its targets with
+/// Goldilocks witness values. Fragments whose constraints are needed over other fields expose
+/// them as a generic function as well, e.g. `storage_proof_circuit` of the wormhole circuit.
 pub trait CircuitFragment {
     /// The targets that the circuit operates on. These are constrained in the circuit definition
     /// and filled with [`Self::fill_targets`].
@@ -24,3 +41,70 @@ pub trait CircuitFragment {
         targets: Self::Targets,
     ) -> anyhow::Result<()>;
 }
+
+/// Lists the targets of `circuit_data` that still need a value in `pw`, i.e. the inputs of the
+/// circuit that no [`CircuitFragment::fill_targets`] has set yet. Proving with such a witness
+/// fails, so this is meant for debugging the wiring of fragments.
+///
+/// Targets computed by the circuit itself, such as constants or the outputs of gates, are not
+/// listed. Where possible, the virtual target that was created for an input is returned rather
+/// than a wire it is copied to.
+///
+/// # Errors
+///
+/// Returns an error if `pw` sets a target twice with different values.
+pub fn unassigned_targets(
+    pw: &PartialWitness<F>,
+    circuit_data: &CircuitData<F, C, D>,
+) -> anyhow::Result<Vec<Target>> {
+    let num_wires = circuit_data.common.config.num_wires;
+    let degree = circuit_data.common.degree();
+    let representative_map = &circuit_data.prover_only.representative_map;
+    let representative = |target: Target| representative_map[target.index(num_wires, degree)];
+
+    let mut witness = PartitionWitness::new(num_wires, degree, representative_map);
+    for (&target, &value) in &pw.target_values {
+        witness.set_target(target, value)?;
+    }
+
+    // The inputs are the targets that generators read, but that no generator writes. A
+    // generator's outputs are found by running it with all of its dependencies set.
+    let mut dependencies = BTreeMap::new();
+    let mut outputs = BTreeMap::new();
+    for generator in &circuit_data.prover_only.generators {
+        let watch_list = generator.0.watch_list();
+        let mut probe = PartitionWitness::new(num_wires, degree, representative_map);
+        for &target in &watch_list {
+            probe.set_target(target, F::ONE)?;
+            dependencies.entry(representative(target)).or_insert(target);
+        }
+
+        let mut buffer = GeneratedValues::empty();
+        if generator.0.run(&probe, &mut buffer).is_ok() {
+            for (target, _) in buffer.target_values {
+                outputs.insert(representative(target), target);
+            }
+        }
+    }
+
+    let missing: Vec<usize> = dependencies
+        .iter()
+        .filter(|&(rep, &target)| {
+            !outputs.contains_key(rep) && witness.try_get_target(target).is_none()
+        })
+        .map(|(&rep, _)| rep)
+        .collect();
+
+    // Prefer the first virtual target of each missing input.
+    let num_virtual_targets = representative_map.len() - num_wires * degree;
+    let mut named = BTreeMap::new();
+    for index in 0..num_virtual_targets {
+        let target = Target::VirtualTarget { index };
+        named.entry(representative(target)).or_insert(target);
+    }
+
+    Ok(missing
+        .into_iter()
+        .map(|rep| named.get(&rep).copied().unwrap_or(dependencies[&rep]))
+        .collect())
+}
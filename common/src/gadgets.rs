@@ -5,9 +5,9 @@ use std::vec::Vec;
 
 use plonky2::{
     field::extension::Extendable,
-    hash::hash_types::RichField,
+    hash::hash_types::{HashOut, HashOutTarget, RichField},
     iop::target::{BoolTarget, Target},
-    plonk::circuit_builder::CircuitBuilder,
+    plonk::{circuit_builder::CircuitBuilder, config::AlgebraicHasher},
 };
 
 /// Compares a constant integer `left` with a variable `right` in a circuit, and returns whether
@@ -67,3 +67,193 @@ pub fn xor<F: RichField + Extendable<D>, const D: usize>(
     let xor = builder.sub(a_plus_b, two_ab);
     BoolTarget::new_unsafe(xor)
 }
+
+/// The maximum number of bytes, excluding the prefix byte, that [`decode_compact`] reads in the
+/// big-integer mode. Larger integers cannot be represented by a single field element.
+pub const COMPACT_MAX_BIG_INT_BYTES: usize = 8;
+
+/// Decodes a SCALE compact-encoded integer from a little-endian byte sequence in a circuit.
+///
+/// The two least significant bits of the first byte select the mode:
+///
+/// ```text
+/// 0b00: single-byte mode, value = b0 >> 2
+/// 0b01: two-byte mode,    value = (b0 | b1 << 8) >> 2
+/// 0b10: four-byte mode,   value = (b0 | b1 << 8 | b2 << 16 | b3 << 24) >> 2
+/// 0b11: big-integer mode, value = b1 | b2 << 8 | ... over the next (b0 >> 2) + 4 bytes
+/// ```
+///
+/// Each byte target is range checked to 8 bits. Missing trailing bytes are treated as zero, and
+/// the big-integer mode is constrained to at most [`COMPACT_MAX_BIG_INT_BYTES`] bytes. Note that
+/// 8-byte values are reduced modulo the field order.
+///
+/// # Returns
+/// - `(Target, Target)`: The decoded value and the number of bytes consumed.
+pub fn decode_compact<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Target],
+) -> (Target, Target) {
+    let zero = builder.zero();
+    let bytes: Vec<Target> = (0..=COMPACT_MAX_BIG_INT_BYTES)
+        .map(|i| bytes.get(i).copied().unwrap_or(zero))
+        .collect();
+
+    // Splitting the prefix into bits also range checks it.
+    let prefix_bits = builder.split_le(bytes[0], 8);
+    for &byte in &bytes[1..] {
+        builder.range_check(byte, 8);
+    }
+
+    let (m0, m1) = (prefix_bits[0], prefix_bits[1]);
+    let not_m0 = builder.not(m0);
+    let not_m1 = builder.not(m1);
+    let is_single = builder.and(not_m0, not_m1);
+    let is_two = builder.and(m0, not_m1);
+    let is_four = builder.and(not_m0, m1);
+    let is_big = builder.and(m0, m1);
+
+    // The upper six bits of the prefix byte, i.e. `b0 >> 2`.
+    let upper = builder.le_sum(prefix_bits[2..].iter());
+
+    let single_value = upper;
+    let two_value = builder.mul_const_add(F::from_canonical_u32(1 << 6), bytes[1], upper);
+    let mut four_value = two_value;
+    for (i, &byte) in bytes[2..4].iter().enumerate() {
+        let shift = 6 + 8 * (i + 1);
+        four_value = builder.mul_const_add(F::from_canonical_u64(1u64 << shift), byte, four_value);
+    }
+
+    // In big-integer mode the upper bits encode the number of following bytes minus four.
+    let too_many_bytes = is_const_less_than(builder, COMPACT_MAX_BIG_INT_BYTES - 4, upper, 6);
+    let invalid_big = builder.and(is_big, too_many_bytes);
+    builder.assert_zero(invalid_big.target);
+
+    let mut big_value = zero;
+    for (j, &byte) in bytes[1..].iter().enumerate() {
+        let shifted = builder.mul_const(F::from_canonical_u64(1u64 << (8 * j)), byte);
+        let term = if j < 4 {
+            shifted
+        } else {
+            let is_included = is_const_less_than(builder, j - 4, upper, 6);
+            builder.mul(shifted, is_included.target)
+        };
+        big_value = builder.add(big_value, term);
+    }
+
+    let mut value = builder.mul(is_single.target, single_value);
+    value = builder.mul_add(is_two.target, two_value, value);
+    value = builder.mul_add(is_four.target, four_value, value);
+    value = builder.mul_add(is_big.target, big_value, value);
+
+    let big_len = builder.add_const(upper, F::from_canonical_u32(5));
+    let mut len = is_single.target;
+    len = builder.mul_const_add(F::TWO, is_two.target, len);
+    len = builder.mul_const_add(F::from_canonical_u32(4), is_four.target, len);
+    len = builder.mul_add(is_big.target, big_len, len);
+
+    (value, len)
+}
+
+/// Checks whether every element of a hash is zero in a circuit.
+///
+/// # Returns
+/// - `BoolTarget`: True if `hash` is the all-zero hash, false otherwise.
+pub fn is_zero_hash<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    hash: HashOutTarget,
+) -> BoolTarget {
+    let zero = builder.zero();
+    let mut is_zero = builder._true();
+    for element in hash.elements {
+        let element_is_zero = builder.is_equal(element, zero);
+        is_zero = builder.and(is_zero, element_is_zero);
+    }
+
+    is_zero
+}
+
+/// Checks whether two hashes are equal in a circuit, element by element. Unlike
+/// [`CircuitBuilder::connect_hashes`], this doesn't constrain anything, so the result can be used
+/// to branch.
+///
+/// # Returns
+/// - `BoolTarget`: True if every element of `a` equals the matching element of `b`.
+pub fn is_equal_hash<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: HashOutTarget,
+    b: HashOutTarget,
+) -> BoolTarget {
+    let mut is_equal = builder._true();
+    for (x, y) in a.elements.into_iter().zip(b.elements) {
+        let element_is_equal = builder.is_equal(x, y);
+        is_equal = builder.and(is_equal, element_is_equal);
+    }
+
+    is_equal
+}
+
+/// Constrains every element of `hash` to equal the matching element of `constant`, e.g. to pin a
+/// root that is known when building the circuit instead of exposing it as a public input.
+pub fn connect_hash_constant<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    hash: HashOutTarget,
+    constant: [F; 4],
+) {
+    let constant = builder.constant_hash(HashOut { elements: constant });
+    builder.connect_hashes(hash, constant);
+}
+
+/// Hashes the first `len` elements of `data` in a circuit, binding the length into the sponge.
+///
+/// Elements at or past `len` are masked to zero before hashing, and `len` itself is absorbed
+/// first, so the result equals hashing `[len, data[0], .., data[len - 1], 0, .., 0]` without
+/// padding. Unlike hashing the padded array directly, two inputs that only differ in length can
+/// never share a digest.
+///
+/// `len` is range checked to the number of bits needed to represent `data.len()`, but is not
+/// otherwise constrained to be at most `data.len()`.
+///
+/// # Returns
+/// - `HashOutTarget`: The hash of the length-prefixed, masked input.
+pub fn hash_var_len<F: RichField + Extendable<D>, const D: usize, H: AlgebraicHasher<F>>(
+    builder: &mut CircuitBuilder<F, D>,
+    data: &[Target],
+    len: Target,
+) -> HashOutTarget {
+    let n_log = ((usize::BITS - data.len().leading_zeros()) as usize).max(1);
+
+    let mut inputs = Vec::with_capacity(data.len() + 1);
+    inputs.push(len);
+    for (i, &element) in data.iter().enumerate() {
+        let is_included = is_const_less_than(builder, i, len, n_log);
+        inputs.push(builder.mul(element, is_included.target));
+    }
+
+    builder.hash_n_to_hash_no_pad::<H>(inputs)
+}
+
+/// Decomposes `felt` into its 8 canonical little-endian bytes in a circuit, i.e. the bytes of the
+/// unique 64-bit value below the field order that equals `felt`. With `p = 2^64 - 2^32 + 1`, a
+/// 64-bit value is non-canonical exactly when its high 32 bits are all ones and its low 32 bits
+/// are not all zeros.
+///
+/// # Returns
+/// - `[Target; 8]`: The bytes of `felt`, each in the range `0..256`.
+pub fn canonical_le_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    felt: Target,
+) -> [Target; 8] {
+    let bits = builder.split_le(felt, 64);
+    let bytes = core::array::from_fn(|i| builder.le_sum(bits[8 * i..8 * (i + 1)].iter()));
+
+    // The bits pack to `felt` modulo the field order, so they must also encode a value below it:
+    // the high 32 bits can only be all ones if the low 32 bits are all zeros.
+    let high_is_max = bits[32..]
+        .iter()
+        .fold(builder._true(), |acc, &bit| builder.and(acc, bit));
+    let low = builder.le_sum(bits[..32].iter());
+    let low_if_high_is_max = builder.mul(high_is_max.target, low);
+    builder.assert_zero(low_if_high_is_max);
+
+    bytes
+}
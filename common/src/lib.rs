@@ -1,3 +1,4 @@
+pub mod blake2;
 pub mod circuit;
 pub mod gadgets;
 pub mod utils;
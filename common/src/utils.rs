@@ -1,7 +1,7 @@
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{format, string::String, vec::Vec};
 #[cfg(feature = "std")]
-use std::vec::Vec;
+use std::{string::String, vec::Vec};
 
 use crate::circuit::F;
 use plonky2::field::types::{Field, PrimeField64};
@@ -14,18 +14,73 @@ pub const DIGEST_NUM_FIELD_ELEMENTS: usize = 4;
 
 pub const ZERO_DIGEST: Digest = [F::ZERO; 4];
 
+/// A nonce, e.g. the funding nonce, as the single field element the circuits hold it in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Nonce(pub F);
+
+impl From<u32> for Nonce {
+    fn from(nonce: u32) -> Self {
+        Self(F::from_canonical_u32(nonce))
+    }
+}
+
+/// An amount, e.g. the funding amount, as the field elements of [`u128_to_felts`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Amount(pub [F; FELTS_PER_U128]);
+
+impl From<u128> for Amount {
+    fn from(amount: u128) -> Self {
+        Self(u128_to_felts(amount))
+    }
+}
+
+impl From<Amount> for u128 {
+    fn from(amount: Amount) -> Self {
+        felts_to_u128(amount.0)
+    }
+}
+
+/// Converts a u128 into two field elements holding its high and low 64 bits, in that order.
 pub fn u128_to_felts(num: u128) -> [F; FELTS_PER_U128] {
     let amount_high = F::from_noncanonical_u64((num >> 64) as u64);
     let amount_low = F::from_noncanonical_u64(num as u64);
     [amount_high, amount_low]
 }
 
+/// Inverse of [`u128_to_felts`].
 pub fn felts_to_u128(felts: [F; 2]) -> u128 {
     let amount_high: u128 = felts[0].0 as u128;
     let amount_low: u128 = felts[1].0 as u128;
     (amount_high << 64) | amount_low
 }
 
+/// Converts a u128 into field elements by packing its big-endian byte encoding, 8 bytes per
+/// element, in the same way as [`bytes_to_felts`].
+pub fn u128_to_felts_be(num: u128) -> [F; FELTS_PER_U128] {
+    let felts = bytes_to_felts(&num.to_be_bytes());
+    [felts[0], felts[1]]
+}
+
+/// Inverse of [`u128_to_felts_be`].
+pub fn felts_to_u128_be(felts: [F; FELTS_PER_U128]) -> u128 {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&felts_to_bytes(&felts));
+    u128::from_be_bytes(bytes)
+}
+
+/// Formats `amount`, given in the smallest unit of a token with `decimals` decimals, as a
+/// decimal string with all of its decimals, e.g. "1.250000000000" for 1.25 tokens with 12
+/// decimals. Decode the amount with [`felts_to_u128`] first when it comes from a proof.
+pub fn format_amount(amount: u128, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    if decimals == 0 {
+        return digits;
+    }
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    format!("{}.{}", whole, fraction)
+}
+
 // Encodes an 8-byte string into a single field element
 pub fn string_to_felt(input: &str) -> F {
     // Convert string to UTF-8 bytes
@@ -71,3 +126,35 @@ pub fn felts_to_bytes(input: &[F]) -> Vec<u8> {
 pub fn felts_to_hashout(felts: &[F; 4]) -> HashOut<F> {
     HashOut { elements: *felts }
 }
+
+/// Decodes a SCALE compact-encoded integer from the start of `bytes`. This is the off-circuit
+/// counterpart of [`crate::gadgets::decode_compact`], limited to integers that fit in a `u64`.
+///
+/// # Returns
+/// - `(u64, usize)`: The decoded value and the number of bytes consumed.
+pub fn decode_compact(bytes: &[u8]) -> anyhow::Result<(u64, usize)> {
+    let Some(&first) = bytes.first() else {
+        anyhow::bail!("SCALE compact integer is empty")
+    };
+    // In the big-integer mode, the value starts after the prefix byte.
+    let (start, len) = match first & 0b11 {
+        0b00 => (0, 1),
+        0b01 => (0, 2),
+        0b10 => (0, 4),
+        _ => (1, (first >> 2) as usize + 4),
+    };
+    anyhow::ensure!(
+        len <= 8,
+        "SCALE compact integer of {} bytes does not fit in a u64",
+        len
+    );
+    let Some(value_bytes) = bytes.get(start..start + len) else {
+        anyhow::bail!("SCALE compact integer is truncated")
+    };
+
+    let mut buf = [0u8; 8];
+    buf[..len].copy_from_slice(value_bytes);
+    let value = u64::from_le_bytes(buf);
+    let value = if start == 0 { value >> 2 } else { value };
+    Ok((value, start + len))
+}
@@ -2,7 +2,10 @@ use std::time::Duration;
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
-use wormhole_aggregator::{aggregator::WormholeProofAggregator, DEFAULT_NUM_PROOFS_TO_AGGREGATE};
+use wormhole_aggregator::{
+    aggregator::WormholeProofAggregator, circuit::WormholeProofAggregatorInner,
+    DEFAULT_NUM_PROOFS_TO_AGGREGATE,
+};
 use wormhole_verifier::ProofWithPublicInputs;
 use zk_circuits_common::circuit::{C, D, F};
 
@@ -36,6 +39,35 @@ fn aggregate_proofs_benchmark(c: &mut Criterion) {
     });
 }
 
+/// Compares `set_proofs` on a reused aggregator before and after the dummy proof was cached.
+/// The `uncached` case parses the dummy proof on every call, as `set_proofs` did before, so the
+/// two cases of the group report the before and after times side by side.
+fn set_proofs_benchmark(c: &mut Criterion) {
+    let config = CircuitConfig::standard_recursion_zk_config();
+    let mut aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(config);
+    let common_data = aggregator.inner_verifier.circuit_data.common.clone();
+    let [proof, ..] = deserialize_proofs(&common_data);
+
+    let mut group = c.benchmark_group("aggregator_set_proofs");
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            ProofWithPublicInputs::from_bytes(DUMMY_PROOF_BYTES.to_vec(), &common_data).unwrap();
+            aggregator.set_proofs(vec![proof.clone()]).unwrap()
+        });
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| aggregator.set_proofs(vec![proof.clone()]).unwrap());
+    });
+    group.finish();
+}
+
+criterion_group!(
+    name = set_proofs_benches;
+    config = Criterion::default();
+    targets = set_proofs_benchmark
+);
+
 criterion_group!(
     name = benches;
     config = Criterion::default()
@@ -43,4 +75,4 @@ criterion_group!(
         .sample_size(10);
     targets = aggregate_proofs_benchmark
 );
-criterion_main!(benches);
+criterion_main!(benches, set_proofs_benches);
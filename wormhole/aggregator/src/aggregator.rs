@@ -3,7 +3,7 @@ use plonky2::{
     iop::witness::PartialWitness,
     plonk::{
         circuit_builder::CircuitBuilder,
-        circuit_data::{CircuitConfig, CircuitData},
+        circuit_data::{CircuitConfig, CircuitData, VerifierCircuitData},
     },
 };
 use wormhole_verifier::ProofWithPublicInputs;
@@ -11,6 +11,9 @@ use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
 
 use crate::circuit::{WormholeProofAggregatorInner, WormholeProofAggregatorTargets};
 
+/// The name of the counter of aggregated batches proven, incremented with the `metrics` feature.
+pub const AGGREGATION_BATCHES_COUNTER: &str = "wormhole_aggregation_batches_total";
+
 /// A circuit that aggregates proofs from the Wormhole circuit.
 pub struct WormholeProofAggregator<const N: usize> {
     pub inner: WormholeProofAggregatorInner<N>,
@@ -28,12 +31,25 @@ impl<const N: usize> Default for WormholeProofAggregator<N> {
 }
 
 impl<const N: usize> WormholeProofAggregator<N> {
+    /// Creates a new [`WormholeProofAggregator`].
     pub fn new(config: CircuitConfig) -> Self {
-        let inner = WormholeProofAggregatorInner::new(config.clone());
-        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        Self::from_inner(WormholeProofAggregatorInner::new(config))
+    }
+
+    /// Creates a new [`WormholeProofAggregator`] that exposes the chain id of each proof. See
+    /// [`WormholeProofAggregatorInner::with_chain_ids`].
+    pub fn with_chain_ids(config: CircuitConfig) -> Self {
+        Self::from_inner(WormholeProofAggregatorInner::with_chain_ids(config))
+    }
+
+    fn from_inner(inner: WormholeProofAggregatorInner<N>) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("build_circuit", num_proofs = N).entered();
+
+        let mut builder = CircuitBuilder::<F, D>::new(inner.config().clone());
 
         // Setup targets.
-        let targets = WormholeProofAggregatorTargets::new(&mut builder, config);
+        let targets = WormholeProofAggregatorTargets::new(&mut builder, &inner);
 
         // Setup circuits.
         WormholeProofAggregatorInner::circuit(&targets, &mut builder);
@@ -50,6 +66,13 @@ impl<const N: usize> WormholeProofAggregator<N> {
         }
     }
 
+    /// Rejects batches containing the same proof twice. See
+    /// [`WormholeProofAggregatorInner::with_unique_proofs`].
+    pub fn with_unique_proofs(mut self) -> Self {
+        self.inner = self.inner.with_unique_proofs();
+        self
+    }
+
     pub fn push_proof(&mut self, proof: ProofWithPublicInputs<F, C, D>) -> anyhow::Result<()> {
         if let Some(proofs_buffer) = self.proofs_buffer.as_mut() {
             if proofs_buffer.len() >= N {
@@ -68,6 +91,9 @@ impl<const N: usize> WormholeProofAggregator<N> {
             bail!("there are no proofs to aggregate")
         };
 
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("fill_witness", num_proofs = proofs.len()).entered();
+
         self.inner.set_proofs(proofs)?;
         self.inner
             .fill_targets(&mut self.partial_witness, self.targets.clone())?;
@@ -75,6 +101,35 @@ impl<const N: usize> WormholeProofAggregator<N> {
         Ok(())
     }
 
+    /// Aggregates and proves `proofs` in one go, with a fresh witness. Unlike
+    /// [`WormholeProofAggregator::prove`] the aggregator isn't consumed, so the circuit can be
+    /// reused for any number of batches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are more than `N` proofs, or if proving fails.
+    pub fn prove_batch(
+        &mut self,
+        proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+    ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        let mut partial_witness = PartialWitness::new();
+        self.inner.set_proofs(proofs)?;
+        self.inner
+            .fill_targets(&mut partial_witness, self.targets.clone())?;
+        let proof = self.circuit_data.prove(partial_witness)?;
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!(AGGREGATION_BATCHES_COUNTER).increment(1);
+
+        Ok(proof)
+    }
+
+    /// Returns the verifier data of the aggregation circuit, used to verify aggregated proofs
+    /// with [`wormhole_verifier::WormholeVerifier::verify_aggregate`].
+    pub fn verifier_data(&self) -> VerifierCircuitData<F, C, D> {
+        self.circuit_data.verifier_data()
+    }
+
     /// Prove the circuit with commited values. It's necessary to call [`WormholeProofAggregator::aggregate`]
     /// before running this function.
     ///
@@ -82,6 +137,14 @@ impl<const N: usize> WormholeProofAggregator<N> {
     ///
     /// Returns an error if the prover has not commited to any inputs.
     pub fn prove(self) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
-        self.circuit_data.prove(self.partial_witness)
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("prove").entered();
+
+        let proof = self.circuit_data.prove(self.partial_witness)?;
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!(AGGREGATION_BATCHES_COUNTER).increment(1);
+
+        Ok(proof)
     }
 }
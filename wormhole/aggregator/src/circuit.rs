@@ -1,12 +1,27 @@
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
 use anyhow::bail;
 use plonky2::{
-    iop::witness::{PartialWitness, WitnessWrite},
+    field::types::PrimeField64,
+    hash::{
+        hash_types::{HashOut, HashOutTarget, NUM_HASH_OUT_ELTS},
+        poseidon::PoseidonHash,
+    },
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
     plonk::{
         circuit_builder::CircuitBuilder,
         circuit_data::{CircuitConfig, CommonCircuitData, VerifierCircuitTarget},
+        config::Hasher,
         proof::ProofWithPublicInputsTarget,
     },
 };
+use serde_json::json;
+use wormhole_circuit::inputs::CHAIN_ID_INDEX;
 use wormhole_verifier::{ProofWithPublicInputs, WormholeVerifier};
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
 
@@ -15,26 +30,82 @@ const DUMMY_PROOF_BYTES: &[u8] = include_bytes!("../data/dummy_proof_zk.bin");
 #[cfg(feature = "no_zk")]
 const DUMMY_PROOF_BYTES: &[u8] = include_bytes!("../data/dummy_proof.bin");
 
+/// The virtual target indices of a single proof in the aggregation circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofTargetsLayout {
+    /// The contiguous range of targets of the proof itself, in the order plonky2 allocates them.
+    pub proof: Range<usize>,
+    /// The targets of the proof's public inputs, in order.
+    pub public_inputs: Vec<usize>,
+}
+
+/// A description of the aggregation circuit's targets, for provers that fill the witness
+/// themselves. All indices are virtual target indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatorTargetsLayout {
+    /// The targets of the inner circuit's constants and sigmas merkle cap, 4 per hash.
+    pub constants_sigmas_cap: Vec<usize>,
+    /// The targets of the inner circuit's digest.
+    pub circuit_digest: Vec<usize>,
+    /// The targets of each aggregated proof, in the order they are verified.
+    pub proofs: Vec<ProofTargetsLayout>,
+}
+
+impl AggregatorTargetsLayout {
+    /// Serializes the layout as a JSON object of the form:
+    ///
+    /// ```json
+    /// {
+    ///   "verifier_data": { "constants_sigmas_cap": [0, ...], "circuit_digest": [16, ...] },
+    ///   "proofs": [{ "proof": { "start": 20, "end": 5000 }, "public_inputs": [5000, ...] }]
+    /// }
+    /// ```
+    pub fn to_json(&self) -> String {
+        let proofs: Vec<_> = self
+            .proofs
+            .iter()
+            .map(|proof| {
+                json!({
+                    "proof": { "start": proof.proof.start, "end": proof.proof.end },
+                    "public_inputs": proof.public_inputs,
+                })
+            })
+            .collect();
+        json!({
+            "verifier_data": {
+                "constants_sigmas_cap": self.constants_sigmas_cap,
+                "circuit_digest": self.circuit_digest,
+            },
+            "proofs": proofs,
+        })
+        .to_string()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WormholeProofAggregatorTargets<const N: usize> {
     verifier_data: VerifierCircuitTarget,
     proofs: [ProofWithPublicInputsTarget<D>; N],
     // HACK: This allows us to only create `circuit_data` once.
     circuit_data: CommonCircuitData<F, D>,
+    /// Whether the chain id of each proof is exposed, see
+    /// [`WormholeProofAggregatorInner::with_chain_ids`].
+    chain_ids: bool,
 }
 
 impl<const N: usize> WormholeProofAggregatorTargets<N> {
-    pub fn new(builder: &mut CircuitBuilder<F, D>, config: CircuitConfig) -> Self {
-        let circuit_data = WormholeVerifier::new(config, None).circuit_data.common;
+    /// Creates the aggregation targets for the inner circuit described by `aggregator`. The
+    /// circuit data is taken from the aggregator's own verifier so the two can never be built
+    /// from different configs.
+    pub fn new(
+        builder: &mut CircuitBuilder<F, D>,
+        aggregator: &WormholeProofAggregatorInner<N>,
+    ) -> Self {
+        let circuit_data = aggregator.inner_verifier.circuit_data.common.clone();
         let verifier_data =
             builder.add_virtual_verifier_data(circuit_data.fri_params.config.cap_height);
 
         // Setup targets for proofs.
-        let mut proofs = Vec::with_capacity(N);
-        for _ in 0..N {
-            proofs.push(builder.add_virtual_proof_with_pis(&circuit_data));
-        }
-
         let proofs: [ProofWithPublicInputsTarget<D>; N] =
             std::array::from_fn(|_| builder.add_virtual_proof_with_pis(&circuit_data));
 
@@ -42,26 +113,195 @@ impl<const N: usize> WormholeProofAggregatorTargets<N> {
             verifier_data,
             proofs,
             circuit_data,
+            chain_ids: aggregator.chain_ids,
         }
     }
+
+    /// The merkle cap height of the inner circuit's verifier data.
+    pub fn cap_height(&self) -> usize {
+        self.circuit_data.fri_params.config.cap_height
+    }
+
+    /// The common data of the inner circuit whose proofs are being aggregated.
+    pub fn common_data(&self) -> &CommonCircuitData<F, D> {
+        &self.circuit_data
+    }
+
+    /// Describes where the verifier data and each proof live among the circuit's targets.
+    ///
+    /// A proof's targets are allocated in one go, immediately followed by its public inputs, so
+    /// the proof spans from the first target of its wires cap to its first public input.
+    pub fn layout(&self) -> AggregatorTargetsLayout {
+        let hash_indices = |hashes: &[HashOutTarget]| -> Vec<usize> {
+            hashes
+                .iter()
+                .flat_map(|hash| hash.elements.map(virtual_index))
+                .collect()
+        };
+
+        let proofs = self
+            .proofs
+            .iter()
+            .map(|proof| {
+                let public_inputs: Vec<usize> = proof
+                    .public_inputs
+                    .iter()
+                    .copied()
+                    .map(virtual_index)
+                    .collect();
+                let start = virtual_index(proof.proof.wires_cap.0[0].elements[0]);
+                let end = public_inputs.first().copied().unwrap_or(start);
+                ProofTargetsLayout {
+                    proof: start..end,
+                    public_inputs,
+                }
+            })
+            .collect();
+
+        AggregatorTargetsLayout {
+            constants_sigmas_cap: hash_indices(&self.verifier_data.constants_sigmas_cap.0),
+            circuit_digest: hash_indices(&[self.verifier_data.circuit_digest]),
+            proofs,
+        }
+    }
+}
+
+/// The commitment to the public inputs of `proofs` exposed by the aggregation circuit: the
+/// Poseidon hash of all their public inputs, concatenated in batch order.
+pub fn batch_commitment(proofs: &[ProofWithPublicInputs<F, C, D>]) -> HashOut<F> {
+    let public_inputs: Vec<F> = proofs
+        .iter()
+        .flat_map(|proof| proof.public_inputs.iter().copied())
+        .collect();
+    PoseidonHash::hash_no_pad(&public_inputs)
+}
+
+/// The chain ids exposed by an aggregate proof of an aggregator built with
+/// [`WormholeProofAggregatorInner::with_chain_ids`], one per aggregated proof in batch order. This
+/// only reads the public inputs, and doesn't verify the proof.
+///
+/// # Errors
+///
+/// Returns an error if the proof doesn't expose `N` chain ids after its batch commitment, or if
+/// one doesn't fit in a `u32`.
+pub fn aggregate_chain_ids<const N: usize>(
+    proof: &ProofWithPublicInputs<F, C, D>,
+) -> anyhow::Result<[u32; N]> {
+    let Some(chain_ids) = proof.public_inputs.get(NUM_HASH_OUT_ELTS..) else {
+        bail!("aggregate proof has no batch commitment")
+    };
+    if chain_ids.len() != N {
+        bail!(
+            "aggregate proof should expose {} chain ids, got: {}",
+            N,
+            chain_ids.len()
+        )
+    }
+    let mut ids = [0; N];
+    for (id, felt) in ids.iter_mut().zip(chain_ids) {
+        let Ok(chain_id) = u32::try_from(felt.to_canonical_u64()) else {
+            bail!("chain id doesn't fit in a u32")
+        };
+        *id = chain_id;
+    }
+    Ok(ids)
+}
+
+/// The index of a target allocated with one of the builder's `add_virtual_*` methods.
+fn virtual_index(target: Target) -> usize {
+    match target {
+        Target::VirtualTarget { index } => index,
+        Target::Wire(_) => panic!("aggregation targets are all virtual"),
+    }
 }
 
 pub struct WormholeProofAggregatorInner<const N: usize> {
     pub inner_verifier: WormholeVerifier,
     num_proofs: usize,
     proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+    /// The serialized proof used to pad batches with fewer than `N` proofs.
+    dummy_proof_bytes: Vec<u8>,
+    /// The dummy proof, parsed from `dummy_proof_bytes` the first time a batch is padded.
+    dummy_proof: Option<ProofWithPublicInputs<F, C, D>>,
+    /// Whether batches containing the same proof twice are rejected.
+    unique_proofs: bool,
+    /// Whether the chain id of each proof is exposed, see
+    /// [`WormholeProofAggregatorInner::with_chain_ids`].
+    chain_ids: bool,
 }
 
 impl<const N: usize> WormholeProofAggregatorInner<N> {
     pub fn new(config: CircuitConfig) -> Self {
-        let inner_verifier = WormholeVerifier::new(config, None);
+        Self::with_verifier(WormholeVerifier::new(config, None))
+    }
+
+    fn with_verifier(inner_verifier: WormholeVerifier) -> Self {
         Self {
             inner_verifier,
             num_proofs: 0,
             proofs: Vec::with_capacity(N),
+            dummy_proof_bytes: DUMMY_PROOF_BYTES.to_vec(),
+            dummy_proof: None,
+            unique_proofs: false,
+            chain_ids: false,
         }
     }
 
+    /// Creates a new aggregator of proofs of the circuit built with
+    /// [`wormhole_circuit::circuit::WormholeCircuit::with_chain_id`], which exposes the chain id
+    /// of each aggregated proof as a public input after the batch commitment, in batch order, see
+    /// [`aggregate_chain_ids`]. Each id is the one the proof commits to, so a verifier can check
+    /// each proven root against the state roots of its own chain.
+    ///
+    /// The embedded dummy proofs are proofs of the circuit without a chain id, so batches can't be
+    /// padded: they must hold exactly `N` proofs.
+    pub fn with_chain_ids(config: CircuitConfig) -> Self {
+        Self {
+            dummy_proof_bytes: Vec::new(),
+            chain_ids: true,
+            ..Self::with_verifier(WormholeVerifier::with_chain_id(config))
+        }
+    }
+
+    /// Rejects batches in which two proofs have the same public inputs, so that a proof can't be
+    /// replayed and counted twice within a batch. Proofs are compared by the Poseidon digest of
+    /// their public inputs.
+    pub fn with_unique_proofs(mut self) -> Self {
+        self.unique_proofs = true;
+        self
+    }
+
+    /// Creates a new aggregator which pads batches with the dummy proof stored at `path`, instead
+    /// of the one embedded in the binary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or if it does not contain a valid proof for
+    /// the inner circuit.
+    pub fn with_dummy_proof_path(
+        config: CircuitConfig,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<Self> {
+        let mut aggregator = Self::new(config);
+        let dummy_proof_bytes = fs::read(path)?;
+
+        let dummy_proof = ProofWithPublicInputs::from_bytes(
+            dummy_proof_bytes.clone(),
+            &aggregator.inner_verifier.circuit_data.common,
+        )?;
+        aggregator.inner_verifier.verify(dummy_proof.clone())?;
+
+        aggregator.dummy_proof_bytes = dummy_proof_bytes;
+        aggregator.dummy_proof = Some(dummy_proof);
+        Ok(aggregator)
+    }
+
+    /// The config used to build the inner circuit. This is the single source of truth for both
+    /// the inner verifier and the aggregation targets.
+    pub fn config(&self) -> &CircuitConfig {
+        &self.inner_verifier.circuit_data.common.config
+    }
+
     pub fn set_proofs(
         &mut self,
         proofs: Vec<ProofWithPublicInputs<F, C, D>>,
@@ -72,20 +312,78 @@ impl<const N: usize> WormholeProofAggregatorInner<N> {
             bail!("proofs to aggregate was more than the maximum allowed")
         }
 
+        if self.unique_proofs {
+            let mut digests = Vec::with_capacity(num_proofs);
+            for (i, proof) in proofs.iter().enumerate() {
+                let digest = PoseidonHash::hash_no_pad(&proof.public_inputs);
+                if let Some(original) = digests.iter().position(|d| *d == digest) {
+                    bail!("proof {} duplicates proof {} in the batch", i, original)
+                }
+                digests.push(digest);
+            }
+        }
+
         // Move proof data from the aggregater, to be used the circuit.
         self.num_proofs = num_proofs;
         self.proofs = proofs;
 
-        let dummy_proof = ProofWithPublicInputs::from_bytes(
-            DUMMY_PROOF_BYTES.to_vec(),
-            &self.inner_verifier.circuit_data.common,
-        )?;
-        for _ in 0..(N - num_proofs) {
-            self.proofs.push(dummy_proof.clone());
+        if num_proofs < N {
+            let dummy_proof = self.dummy_proof()?.clone();
+            for _ in 0..(N - num_proofs) {
+                self.proofs.push(dummy_proof.clone());
+            }
         }
 
         Ok(())
     }
+
+    /// The proof used to pad batches, parsed once and cached.
+    fn dummy_proof(&mut self) -> anyhow::Result<&ProofWithPublicInputs<F, C, D>> {
+        if self.dummy_proof_bytes.is_empty() {
+            bail!(
+                "there is no dummy proof to pad the batch with, it must hold {} proofs",
+                N
+            )
+        }
+        if self.dummy_proof.is_none() {
+            let dummy_proof = ProofWithPublicInputs::from_bytes(
+                self.dummy_proof_bytes.clone(),
+                &self.inner_verifier.circuit_data.common,
+            )?;
+            self.dummy_proof = Some(dummy_proof);
+        }
+        Ok(self.dummy_proof.as_ref().unwrap())
+    }
+
+    /// Verifies the proofs at `indices` of the batch off-circuit, e.g. to spot-check a large batch
+    /// before aggregating it. The other proofs, including padding, are not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns the indices that failed verification, in the order given. Indices outside the
+    /// batch are reported as failed.
+    pub fn validate_sample(&self, indices: &[usize]) -> Result<(), Vec<usize>> {
+        let failed: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&i| match self.proofs.get(i) {
+                Some(proof) => self.inner_verifier.verify(proof.clone()).is_err(),
+                None => true,
+            })
+            .collect();
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+
+    /// The [`batch_commitment`] of the current batch, including the padding proofs, i.e. the
+    /// public inputs of the aggregate proof.
+    pub fn batch_commitment(&self) -> HashOut<F> {
+        batch_commitment(&self.proofs)
+    }
 }
 
 impl<const N: usize> CircuitFragment for WormholeProofAggregatorInner<N> {
@@ -96,6 +394,7 @@ impl<const N: usize> CircuitFragment for WormholeProofAggregatorInner<N> {
             verifier_data,
             proofs,
             circuit_data,
+            chain_ids,
         }: &Self::Targets,
         builder: &mut CircuitBuilder<F, D>,
     ) {
@@ -103,6 +402,21 @@ impl<const N: usize> CircuitFragment for WormholeProofAggregatorInner<N> {
         for proof in proofs {
             builder.verify_proof::<C>(proof, verifier_data, circuit_data);
         }
+
+        // Rather than exposing every inner public input, the aggregate proof commits to all of
+        // them with a single hash.
+        let public_inputs: Vec<Target> = proofs
+            .iter()
+            .flat_map(|proof| proof.public_inputs.iter().copied())
+            .collect();
+        let commitment = builder.hash_n_to_hash_no_pad::<PoseidonHash>(public_inputs);
+        builder.register_public_inputs(&commitment.elements);
+
+        if *chain_ids {
+            for proof in proofs {
+                builder.register_public_input(proof.public_inputs[CHAIN_ID_INDEX]);
+            }
+        }
     }
 
     fn fill_targets(
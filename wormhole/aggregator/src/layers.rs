@@ -0,0 +1,141 @@
+//! Recursive aggregation over a fixed number of layers.
+//!
+//! The first layer aggregates wormhole proofs with a
+//! [`WormholeProofAggregator`](crate::aggregator::WormholeProofAggregator), and every following
+//! layer combines proofs of the previous one with a
+//! [`TopLevelAggregator`](crate::top_level::TopLevelAggregator). All circuits are built once, up
+//! front, so proving a layer never rebuilds its circuit.
+//!
+//! [`LayeredAggregator::prove_stream`] drives all the layers from a stream of wormhole proofs,
+//! combining aggregates as soon as a layer has enough of them, so only `M` pending proofs per
+//! layer are held in memory at any time.
+use anyhow::{anyhow, bail, ensure};
+use plonky2::plonk::circuit_data::{CircuitConfig, VerifierCircuitData};
+use wormhole_verifier::ProofWithPublicInputs;
+use zk_circuits_common::circuit::{C, D, F};
+
+use crate::aggregator::WormholeProofAggregator;
+use crate::top_level::TopLevelAggregator;
+
+/// The aggregation circuits of every layer, aggregating `N` wormhole proofs in the first layer
+/// and combining `M` proofs of the previous layer in each following one.
+pub struct LayeredAggregator<const N: usize, const M: usize> {
+    base: WormholeProofAggregator<N>,
+    top_levels: Vec<TopLevelAggregator<M>>,
+    layer_circuit_data: Vec<VerifierCircuitData<F, C, D>>,
+}
+
+impl<const N: usize, const M: usize> LayeredAggregator<N, M> {
+    /// Builds the circuits of `num_layers` aggregation layers, all with `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `num_layers` is zero.
+    pub fn new(config: CircuitConfig, num_layers: usize) -> anyhow::Result<Self> {
+        ensure!(num_layers > 0, "aggregation needs at least one layer");
+
+        let base = WormholeProofAggregator::<N>::new(config.clone());
+        let mut layer_circuit_data = vec![base.verifier_data()];
+        let mut top_levels = Vec::with_capacity(num_layers - 1);
+        for layer in 1..num_layers {
+            let top_level =
+                TopLevelAggregator::<M>::new(config.clone(), layer_circuit_data[layer - 1].clone());
+            layer_circuit_data.push(top_level.verifier_data());
+            top_levels.push(top_level);
+        }
+
+        Ok(Self {
+            base,
+            top_levels,
+            layer_circuit_data,
+        })
+    }
+
+    /// The number of aggregation layers.
+    pub fn num_layers(&self) -> usize {
+        self.layer_circuit_data.len()
+    }
+
+    /// The verifier data of layer `layer`, or `None` if there is no such layer.
+    pub fn layer_circuit_data(&self, layer: usize) -> Option<&VerifierCircuitData<F, C, D>> {
+        self.layer_circuit_data.get(layer)
+    }
+
+    /// Proves `proofs` with the circuit of layer `layer`. The first layer takes up to `N`
+    /// wormhole proofs, and every other layer exactly `M` proofs of the layer before it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such layer, if the number of proofs doesn't fit the layer,
+    /// or if proving fails.
+    pub fn prove_layer(
+        &mut self,
+        layer: usize,
+        proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+    ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        if layer == 0 {
+            return self.base.prove_batch(proofs);
+        }
+        let num_layers = self.num_layers();
+        let Some(top_level) = self.top_levels.get_mut(layer - 1) else {
+            bail!("no aggregation layer {}, only {} layers", layer, num_layers)
+        };
+        top_level.prove_batch(proofs)
+    }
+
+    /// Aggregates a stream of wormhole proofs through every layer into a single proof of the last
+    /// layer. Proofs are taken from `proofs` `N` at a time, padding the last batch, and each
+    /// layer's proofs are combined as soon as there are `M` of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `proofs` is empty, if the aggregates don't exactly fill `M` proofs in
+    /// each layer, if there are more proofs than the layers can combine into one, or if proving
+    /// fails.
+    pub fn prove_stream<I>(&mut self, proofs: I) -> anyhow::Result<ProofWithPublicInputs<F, C, D>>
+    where
+        I: IntoIterator<Item = ProofWithPublicInputs<F, C, D>>,
+    {
+        let num_layers = self.num_layers();
+        // The proofs of each layer waiting to be combined by the next one.
+        let mut pending: Vec<Vec<ProofWithPublicInputs<F, C, D>>> =
+            vec![Vec::with_capacity(M); num_layers - 1];
+        let mut final_proof = None;
+
+        let mut proofs = proofs.into_iter().peekable();
+        while proofs.peek().is_some() {
+            ensure!(
+                final_proof.is_none(),
+                "more proofs than {} layers can aggregate",
+                num_layers
+            );
+
+            let batch: Vec<_> = proofs.by_ref().take(N).collect();
+            let mut proof = self.prove_layer(0, batch)?;
+            let mut layer = 0;
+            loop {
+                if layer + 1 == num_layers {
+                    final_proof = Some(proof);
+                    break;
+                }
+                pending[layer].push(proof);
+                if pending[layer].len() < M {
+                    break;
+                }
+                let batch = std::mem::replace(&mut pending[layer], Vec::with_capacity(M));
+                layer += 1;
+                proof = self.prove_layer(layer, batch)?;
+            }
+        }
+
+        if let Some(layer) = pending.iter().position(|proofs| !proofs.is_empty()) {
+            bail!(
+                "proofs don't fill aggregation layer {}: {} of {} proofs",
+                layer + 1,
+                pending[layer].len(),
+                M
+            )
+        }
+        final_proof.ok_or_else(|| anyhow!("there are no proofs to aggregate"))
+    }
+}
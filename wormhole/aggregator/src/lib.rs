@@ -1,5 +1,7 @@
 pub mod aggregator;
 pub mod circuit;
+pub mod layers;
+pub mod top_level;
 
 /// The maximum numbers of proofs to aggregate into a composite proof.
 pub const DEFAULT_NUM_PROOFS_TO_AGGREGATE: usize = 10;
@@ -0,0 +1,179 @@
+//! Aggregation of aggregate proofs.
+//!
+//! A [`TopLevelAggregator`] takes proofs produced by a
+//! [`WormholeProofAggregator`](crate::aggregator::WormholeProofAggregator) and combines them into
+//! a single proof. Repeating this allows building a balanced aggregation tree.
+use anyhow::bail;
+use plonky2::{
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{
+            CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitData,
+            VerifierCircuitTarget,
+        },
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+    },
+};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+
+#[derive(Debug, Clone)]
+pub struct TopLevelAggregatorTargets<const M: usize> {
+    verifier_data: VerifierCircuitTarget,
+    proofs: [ProofWithPublicInputsTarget<D>; M],
+    circuit_data: CommonCircuitData<F, D>,
+}
+
+impl<const M: usize> TopLevelAggregatorTargets<M> {
+    pub fn new(
+        builder: &mut CircuitBuilder<F, D>,
+        aggregator: &TopLevelAggregatorInner<M>,
+    ) -> Self {
+        let circuit_data = aggregator.aggregate_circuit_data.common.clone();
+
+        // Aggregate proofs must always come from the aggregation circuit, so its verifier data is
+        // a constant of this circuit rather than a witness.
+        let verifier_data =
+            builder.constant_verifier_data(&aggregator.aggregate_circuit_data.verifier_only);
+
+        let proofs: [ProofWithPublicInputsTarget<D>; M] =
+            std::array::from_fn(|_| builder.add_virtual_proof_with_pis(&circuit_data));
+
+        Self {
+            verifier_data,
+            proofs,
+            circuit_data,
+        }
+    }
+}
+
+pub struct TopLevelAggregatorInner<const M: usize> {
+    /// The verifier data of the aggregation circuit that produced the proofs being combined.
+    pub aggregate_circuit_data: VerifierCircuitData<F, C, D>,
+    proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+}
+
+impl<const M: usize> TopLevelAggregatorInner<M> {
+    pub fn new(aggregate_circuit_data: VerifierCircuitData<F, C, D>) -> Self {
+        Self {
+            aggregate_circuit_data,
+            proofs: Vec::with_capacity(M),
+        }
+    }
+
+    pub fn set_proofs(
+        &mut self,
+        proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+    ) -> anyhow::Result<()> {
+        if proofs.len() != M {
+            bail!("expected {} aggregate proofs, got: {}", M, proofs.len())
+        }
+
+        self.proofs = proofs;
+        Ok(())
+    }
+}
+
+impl<const M: usize> CircuitFragment for TopLevelAggregatorInner<M> {
+    type Targets = TopLevelAggregatorTargets<M>;
+
+    fn circuit(
+        Self::Targets {
+            verifier_data,
+            proofs,
+            circuit_data,
+        }: &Self::Targets,
+        builder: &mut CircuitBuilder<F, D>,
+    ) {
+        // Verify each aggregate proof against the aggregation circuit.
+        for proof in proofs {
+            builder.verify_proof::<C>(proof, verifier_data, circuit_data);
+        }
+    }
+
+    fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: Self::Targets,
+    ) -> anyhow::Result<()> {
+        for (proof_target, proof) in targets.proofs.iter().zip(self.proofs.iter()) {
+            pw.set_proof_with_pis_target(proof_target, proof)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A circuit that combines `M` aggregate proofs into a single proof.
+pub struct TopLevelAggregator<const M: usize> {
+    pub inner: TopLevelAggregatorInner<M>,
+    pub circuit_data: CircuitData<F, C, D>,
+    partial_witness: PartialWitness<F>,
+    targets: TopLevelAggregatorTargets<M>,
+}
+
+impl<const M: usize> TopLevelAggregator<M> {
+    /// Creates a new [`TopLevelAggregator`] for proofs of the aggregation circuit described by
+    /// `aggregate_circuit_data`.
+    pub fn new(
+        config: CircuitConfig,
+        aggregate_circuit_data: VerifierCircuitData<F, C, D>,
+    ) -> Self {
+        let inner = TopLevelAggregatorInner::new(aggregate_circuit_data);
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Setup targets.
+        let targets = TopLevelAggregatorTargets::new(&mut builder, &inner);
+
+        // Setup circuits.
+        TopLevelAggregatorInner::circuit(&targets, &mut builder);
+        let circuit_data = builder.build();
+        let partial_witness = PartialWitness::new();
+
+        Self {
+            inner,
+            circuit_data,
+            partial_witness,
+            targets,
+        }
+    }
+
+    /// Commits exactly `M` aggregate proofs to the circuit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of proofs is not `M`.
+    pub fn aggregate(&mut self, proofs: Vec<ProofWithPublicInputs<F, C, D>>) -> anyhow::Result<()> {
+        self.inner.set_proofs(proofs)?;
+        self.inner
+            .fill_targets(&mut self.partial_witness, self.targets.clone())
+    }
+
+    /// Combines and proves exactly `M` aggregate proofs in one go, with a fresh witness. Unlike
+    /// [`TopLevelAggregator::prove`] the aggregator isn't consumed, so the circuit can be reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of proofs is not `M`, or if proving fails.
+    pub fn prove_batch(
+        &mut self,
+        proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+    ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        let mut partial_witness = PartialWitness::new();
+        self.inner.set_proofs(proofs)?;
+        self.inner
+            .fill_targets(&mut partial_witness, self.targets.clone())?;
+        self.circuit_data.prove(partial_witness)
+    }
+
+    /// Returns the verifier data of the top level circuit.
+    pub fn verifier_data(&self) -> VerifierCircuitData<F, C, D> {
+        self.circuit_data.verifier_data()
+    }
+
+    /// Prove the circuit with commited values. It's necessary to call [`TopLevelAggregator::aggregate`]
+    /// before running this function.
+    pub fn prove(self) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        self.circuit_data.prove(self.partial_witness)
+    }
+}
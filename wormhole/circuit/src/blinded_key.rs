@@ -0,0 +1,204 @@
+//! A commitment to a private storage key, as a building block for inclusion proofs that don't
+//! reveal which key is proven.
+//!
+//! The key is a private input, range checked to bytes, and only `Poseidon(key ++ salt)` is
+//! public. Built with [`BlindedKeyTargets::new`], the fragment only proves knowledge of a key
+//! opening the commitment. Built with [`BlindedKeyTargets::from_storage_proof`], the trailing bytes
+//! of the key must also be the end of the partial key of the leaf node that ends the storage
+//! proof, which ties the commitment to proven storage. A caller can further connect
+//! [`BlindedKeyTargets::key`] to an in-circuit key reconstruction, e.g.
+//! [`crate::system_account::system_account_key`], which ties the commitment to the account the key
+//! is derived from. The salt keeps keys with few candidates, such as the accounts of a known set,
+//! from being recovered by hashing each candidate.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use anyhow::bail;
+use plonky2::{
+    field::types::Field,
+    hash::{hash_types::HashOutTarget, poseidon::PoseidonHash},
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        config::{AlgebraicHasher, Hasher},
+    },
+};
+
+use crate::storage_proof::{read_last_node_bytes, StorageProof, StorageProofTargets};
+use zk_circuits_common::circuit::{CircuitFragment, D, F};
+use zk_circuits_common::utils::Digest;
+
+#[derive(Debug, Clone)]
+pub struct BlindedKeyTargets {
+    /// The bytes of the storage key, one per target.
+    pub key: Vec<Target>,
+    pub salt: HashOutTarget,
+    /// The public commitment to the key.
+    pub commitment: HashOutTarget,
+    /// The byte offset of the key suffix within the last storage proof node, for targets built
+    /// with [`BlindedKeyTargets::from_storage_proof`].
+    pub key_offset: Option<Target>,
+}
+
+impl BlindedKeyTargets {
+    /// Creates the targets for a key of `key_len` bytes, registering the commitment as a public
+    /// input.
+    pub fn new(builder: &mut CircuitBuilder<F, D>, key_len: usize) -> Self {
+        Self {
+            key: builder.add_virtual_targets(key_len),
+            salt: builder.add_virtual_hash(),
+            commitment: builder.add_virtual_hash_public_input(),
+            key_offset: None,
+        }
+    }
+
+    /// Same as [`BlindedKeyTargets::new`], but the last `suffix_len` bytes of the key are read
+    /// from the last node of `storage_proof`, at a byte offset the prover sets, see
+    /// [`BlindedKey::with_storage_proof`]. A leaf node holds the end of its key as its partial
+    /// key, so `suffix_len` must be at most the length of the partial key of the proven leaf, e.g.
+    /// [`crate::system_account::SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES`] for `System::Account`.
+    ///
+    /// Like [`crate::storage_proof::read_last_node_bytes`], this doesn't constrain where the
+    /// bytes sit within the node: the caller must bind the last node as a leaf, e.g. with
+    /// [`StorageProofTargets::with_leaf_value`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `suffix_len` is greater than `key_len`.
+    pub fn from_storage_proof(
+        builder: &mut CircuitBuilder<F, D>,
+        storage_proof: &StorageProofTargets,
+        key_len: usize,
+        suffix_len: usize,
+    ) -> Self {
+        assert!(
+            suffix_len <= key_len,
+            "a key suffix of {} bytes doesn't fit in a key of {} bytes",
+            suffix_len,
+            key_len
+        );
+        let targets = Self::new(builder, key_len);
+        let key_offset = builder.add_virtual_target();
+        let node_bytes = read_last_node_bytes(builder, storage_proof, key_offset, suffix_len);
+        for (&key_byte, &node_byte) in targets.key[key_len - suffix_len..].iter().zip(&node_bytes) {
+            builder.connect(key_byte, node_byte);
+        }
+        Self {
+            key_offset: Some(key_offset),
+            ..targets
+        }
+    }
+}
+
+/// A storage key blinded by a secret salt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlindedKey {
+    pub key: Vec<u8>,
+    pub salt: Digest,
+    /// The byte offset of the key suffix within the last storage proof node, see
+    /// [`BlindedKey::with_storage_proof`].
+    pub key_offset: Option<usize>,
+}
+
+impl BlindedKey {
+    pub fn new(key: &[u8], salt: Digest) -> Self {
+        Self {
+            key: key.to_vec(),
+            salt,
+            key_offset: None,
+        }
+    }
+
+    /// Locates the last `suffix_len` bytes of the key in the last node of `storage_proof`, for a
+    /// circuit built with [`BlindedKeyTargets::from_storage_proof`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `suffix_len` is greater than the key length, or if the last node
+    /// doesn't hold the key suffix.
+    pub fn with_storage_proof<H: AlgebraicHasher<F>>(
+        mut self,
+        storage_proof: &StorageProof<H>,
+        suffix_len: usize,
+    ) -> anyhow::Result<Self> {
+        let Some(suffix) = self
+            .key
+            .len()
+            .checked_sub(suffix_len)
+            .map(|i| &self.key[i..])
+        else {
+            bail!(
+                "a key suffix of {} bytes doesn't fit in a key of {} bytes",
+                suffix_len,
+                self.key.len()
+            );
+        };
+        let Some(offset) = storage_proof.last_node_offset(suffix) else {
+            bail!("last proof node does not hold the key suffix");
+        };
+        self.key_offset = Some(offset);
+        Ok(self)
+    }
+
+    /// Computes the public commitment to the key.
+    pub fn commitment(&self) -> Digest {
+        PoseidonHash::hash_no_pad(&self.preimage()).elements
+    }
+
+    fn preimage(&self) -> Vec<F> {
+        let mut preimage: Vec<F> = self.key.iter().map(|&b| F::from_canonical_u8(b)).collect();
+        preimage.extend(self.salt);
+        preimage
+    }
+}
+
+impl CircuitFragment for BlindedKey {
+    type Targets = BlindedKeyTargets;
+
+    /// Builds a circuit that asserts that the commitment is `H(key ++ salt)`, where every element
+    /// of the key is a byte.
+    fn circuit(
+        &Self::Targets {
+            ref key,
+            salt,
+            commitment,
+            ..
+        }: &Self::Targets,
+        builder: &mut CircuitBuilder<F, D>,
+    ) {
+        // Otherwise a field element other than a byte could open the commitment of a key that
+        // was never derived as bytes.
+        for &byte in key {
+            builder.range_check(byte, 8);
+        }
+        let mut preimage = key.clone();
+        preimage.extend(salt.elements);
+        let computed = builder.hash_n_to_hash_no_pad::<PoseidonHash>(preimage);
+        builder.connect_hashes(computed, commitment);
+    }
+
+    fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: Self::Targets,
+    ) -> anyhow::Result<()> {
+        match (targets.key_offset, self.key_offset) {
+            (Some(target), Some(offset)) => {
+                pw.set_target(target, F::from_canonical_usize(offset))?
+            }
+            (Some(_), None) => {
+                bail!("the circuit reads the key from the storage proof, but it wasn't located")
+            }
+            (None, _) => {}
+        }
+        let key: Vec<F> = self.key.iter().map(|&b| F::from_canonical_u8(b)).collect();
+        pw.set_target_arr(&targets.key, &key)?;
+        pw.set_hash_target(targets.salt, self.salt.into())?;
+        pw.set_hash_target(targets.commitment, self.commitment().into())
+    }
+}
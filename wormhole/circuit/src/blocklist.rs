@@ -0,0 +1,77 @@
+//! An in-circuit check that an account is not one of a fixed set of forbidden accounts, e.g. to
+//! refuse transfers to blocklisted to_accounts.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use plonky2::{
+    hash::hash_types::{HashOut, HashOutTarget},
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use zk_circuits_common::circuit::{CircuitFragment, D, F};
+use zk_circuits_common::gadgets::is_equal_hash;
+use zk_circuits_common::utils::Digest;
+
+#[derive(Debug, Clone)]
+pub struct BlocklistTargets {
+    /// The account checked against the blocklist, e.g. the to_account of the leaf.
+    pub account: HashOutTarget,
+    /// The forbidden accounts, which are constants of the circuit.
+    pub forbidden: Vec<HashOutTarget>,
+}
+
+impl BlocklistTargets {
+    /// Checks `account`, an account target of another fragment such as
+    /// [`crate::leaf::LeafInputsTargets::to_account`], against the `forbidden` account ids.
+    pub fn new(
+        builder: &mut CircuitBuilder<F, D>,
+        account: HashOutTarget,
+        forbidden: &[Digest],
+    ) -> Self {
+        Self {
+            account,
+            forbidden: forbidden
+                .iter()
+                .map(|&account| builder.constant_hash(HashOut::from(account)))
+                .collect(),
+        }
+    }
+}
+
+/// An account that must not be any of the forbidden accounts of [`BlocklistTargets`].
+#[derive(Debug, Clone, Copy)]
+pub struct Blocklist {
+    /// The account id, e.g. [`crate::unspendable_account::UnspendableAccount::account_id`].
+    pub account: Digest,
+}
+
+impl Blocklist {
+    pub fn new(account: Digest) -> Self {
+        Self { account }
+    }
+}
+
+impl CircuitFragment for Blocklist {
+    type Targets = BlocklistTargets;
+
+    fn circuit(
+        Self::Targets { account, forbidden }: &Self::Targets,
+        builder: &mut CircuitBuilder<F, D>,
+    ) {
+        for &forbidden_account in forbidden {
+            let is_forbidden = is_equal_hash(builder, *account, forbidden_account);
+            builder.assert_zero(is_forbidden.target);
+        }
+    }
+
+    fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: Self::Targets,
+    ) -> anyhow::Result<()> {
+        pw.set_hash_target(targets.account, HashOut::from(self.account))
+    }
+}
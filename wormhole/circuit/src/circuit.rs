@@ -1,15 +1,19 @@
 //! Wormhole Circuit.
 //!
 //! This module defines the zero-knowledge circuit for the Wormhole protocol.
+use crate::blocklist::{Blocklist, BlocklistTargets};
+use crate::leaf::LeafInputsTargets;
 use crate::nullifier::{Nullifier, NullifierTargets};
 use crate::storage_proof::{StorageProof, StorageProofTargets};
 use crate::substrate_account::{ExitAccountTargets, SubstrateAccount};
 use crate::unspendable_account::{UnspendableAccount, UnspendableAccountTargets};
+use plonky2::hash::poseidon::PoseidonHash;
 use plonky2::plonk::{
     circuit_builder::CircuitBuilder,
     circuit_data::{CircuitConfig, CircuitData, ProverCircuitData, VerifierCircuitData},
 };
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::Digest;
 
 #[derive(Debug, Clone)]
 pub struct CircuitTargets {
@@ -17,17 +21,48 @@ pub struct CircuitTargets {
     pub unspendable_account: UnspendableAccountTargets,
     pub storage_proof: StorageProofTargets,
     pub exit_account: ExitAccountTargets,
+    /// The blocklist of a circuit built with [`WormholeCircuit::with_blocklist`], checked against
+    /// the to_account of the leaf.
+    pub blocklist: Option<BlocklistTargets>,
 }
 
 impl CircuitTargets {
     pub fn new(builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self::with_storage_proof(builder, StorageProofTargets::with_leaf_inputs)
+    }
+
+    /// Same as [`CircuitTargets::new`], with the storage proof targets created by
+    /// `storage_proof`, e.g. [`StorageProofTargets::with_chain_id`]. The targets are created in
+    /// the same order, so the public inputs keep their indices.
+    fn with_storage_proof(
+        builder: &mut CircuitBuilder<F, D>,
+        storage_proof: fn(&mut CircuitBuilder<F, D>) -> StorageProofTargets,
+    ) -> Self {
         Self {
             nullifier: NullifierTargets::new(builder),
             unspendable_account: UnspendableAccountTargets::new(builder),
-            storage_proof: StorageProofTargets::new(builder),
+            storage_proof: storage_proof(builder),
             exit_account: ExitAccountTargets::new(builder),
+            blocklist: None,
         }
     }
+
+    /// The leaf input targets of the storage proof, which the wormhole circuit always binds.
+    pub fn leaf_inputs(&self) -> &LeafInputsTargets {
+        self.storage_proof
+            .leaf_inputs
+            .as_ref()
+            .expect("the wormhole storage proof binds its leaf inputs")
+    }
+}
+
+/// Returns [`CircuitConfig::standard_recursion_zk_config`] with `num_query_rounds` FRI query
+/// rounds. More rounds add soundness, at the cost of larger proofs and slower proving. The
+/// zero-knowledge blinding isn't configured separately: plonky2 derives it from the query rounds.
+pub fn zk_config_with_query_rounds(num_query_rounds: usize) -> CircuitConfig {
+    let mut config = CircuitConfig::standard_recursion_zk_config();
+    config.fri_config.num_query_rounds = num_query_rounds;
+    config
 }
 
 pub struct WormholeCircuit {
@@ -44,20 +79,89 @@ impl Default for WormholeCircuit {
 
 impl WormholeCircuit {
     pub fn new(config: CircuitConfig) -> Self {
+        Self::with_storage_proof(config, StorageProofTargets::with_leaf_inputs)
+    }
+
+    fn with_storage_proof(
+        config: CircuitConfig,
+        storage_proof: fn(&mut CircuitBuilder<F, D>) -> StorageProofTargets,
+    ) -> Self {
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
         // Setup targets
-        let targets = CircuitTargets::new(&mut builder);
+        let targets = CircuitTargets::with_storage_proof(&mut builder, storage_proof);
 
         // Setup circuits.
         Nullifier::circuit(&targets.nullifier, &mut builder);
         UnspendableAccount::circuit(&targets.unspendable_account, &mut builder);
-        StorageProof::circuit(&targets.storage_proof, &mut builder);
+        StorageProof::<PoseidonHash>::circuit(&targets.storage_proof, &mut builder);
         SubstrateAccount::circuit(&targets.exit_account, &mut builder);
 
+        // The proven leaf must be the transfer that the nullifier spends, to the unspendable
+        // account derived from the same secret.
+        let leaf_inputs = targets.leaf_inputs();
+        builder.connect(leaf_inputs.funding_nonce, targets.nullifier.funding_nonce);
+        for (&leaf_target, &nullifier_target) in leaf_inputs
+            .funding_account
+            .elements
+            .iter()
+            .zip(&targets.nullifier.funding_account)
+        {
+            builder.connect(leaf_target, nullifier_target);
+        }
+        builder.connect_hashes(
+            leaf_inputs.to_account,
+            targets.unspendable_account.account_id,
+        );
+
         Self { builder, targets }
     }
 
+    /// Same as [`WormholeCircuit::new`], but also registers the to_account of the leaf, i.e. the
+    /// account id of the unspendable account, as the last public input. This reveals which
+    /// unspendable account was funded, so that a verifier can check it, see
+    /// [`crate::inputs::extract_leaf_public`].
+    pub fn with_public_to_account(config: CircuitConfig) -> Self {
+        let mut circuit = Self::new(config);
+
+        let to_account = circuit.targets.leaf_inputs().to_account;
+        circuit.builder.register_public_inputs(&to_account.elements);
+
+        circuit
+    }
+
+    /// Same as [`WormholeCircuit::new`], but the leaf is hashed in the domain of the id of the
+    /// chain it belongs to, see [`StorageProofTargets::with_chain_id`], and the chain id is
+    /// registered as the last public input, at [`crate::inputs::CHAIN_ID_INDEX`]. This attributes
+    /// each proof to its chain, e.g. when aggregating proofs of several chains. Inputs must be
+    /// committed with the chain id, see [`crate::storage_proof::StorageProof::on_chain`].
+    pub fn with_chain_id(config: CircuitConfig) -> Self {
+        let mut circuit = Self::with_storage_proof(config, StorageProofTargets::with_chain_id);
+
+        let chain_id = circuit
+            .targets
+            .leaf_inputs()
+            .domain
+            .expect("the leaf inputs are hashed in the domain of the chain id");
+        circuit.builder.register_public_input(chain_id);
+
+        circuit
+    }
+
+    /// Same as [`WormholeCircuit::new`], but the circuit also refuses leaves whose to_account is
+    /// any of the `forbidden` account ids. The blocklist checks the to_account targets of the leaf
+    /// inputs, which the storage proof fills, so it needs no witness of its own.
+    pub fn with_blocklist(config: CircuitConfig, forbidden: &[Digest]) -> Self {
+        let mut circuit = Self::new(config);
+
+        let to_account = circuit.targets.leaf_inputs().to_account;
+        let blocklist = BlocklistTargets::new(&mut circuit.builder, to_account, forbidden);
+        Blocklist::circuit(&blocklist, &mut circuit.builder);
+        circuit.targets.blocklist = Some(blocklist);
+
+        circuit
+    }
+
     pub fn targets(&self) -> CircuitTargets {
         self.targets.clone()
     }
@@ -0,0 +1,89 @@
+//! A human-readable listing of the storage proof circuit, to review circuit changes by diffing
+//! the listings of two versions.
+use std::fmt::Write as _;
+use std::path::Path;
+
+use plonky2::{
+    field::types::PrimeField64,
+    plonk::circuit_data::{CircuitConfig, CircuitData},
+};
+
+use crate::storage_proof::{build_storage_proof_circuit, PublicInputLayout, FELTS_PER_AMOUNT};
+use zk_circuits_common::circuit::{C, D, F};
+
+/// Writes the gate types of the default storage proof circuit, how many rows each of them
+/// occupies, and which value each public input holds to `path`.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written.
+pub fn dump_circuit<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
+    let (data, _) = build_storage_proof_circuit(CircuitConfig::standard_recursion_config());
+    std::fs::write(path, circuit_listing(&data))?;
+    Ok(())
+}
+
+fn circuit_listing(data: &CircuitData<F, C, D>) -> String {
+    let common = &data.common;
+    let mut listing = String::new();
+
+    writeln!(listing, "degree_bits: {}", common.degree_bits()).unwrap();
+    writeln!(
+        listing,
+        "quotient_degree_factor: {}",
+        common.quotient_degree_factor
+    )
+    .unwrap();
+    writeln!(
+        listing,
+        "num_gate_constraints: {}",
+        common.num_gate_constraints
+    )
+    .unwrap();
+
+    writeln!(listing, "\ngates:").unwrap();
+    for (gate, count) in common.gates.iter().zip(gate_counts(data)) {
+        writeln!(listing, "  {} x {}", count, gate.0.id()).unwrap();
+    }
+
+    let amount: Vec<String> = (0..FELTS_PER_AMOUNT)
+        .map(|i| format!("funding_amount[{}]", i))
+        .collect();
+    let root: Vec<String> = (0..4).map(|i| format!("root_hash[{}]", i)).collect();
+    writeln!(listing, "\npublic inputs:").unwrap();
+    for (i, name) in PublicInputLayout::default()
+        .arrange(&amount, &root)
+        .iter()
+        .enumerate()
+    {
+        writeln!(listing, "  {}: {}", i, name).unwrap();
+    }
+
+    listing
+}
+
+/// The number of rows of each gate in `common.gates`, read back from the selector polynomials,
+/// since the circuit data doesn't keep the gate instances.
+fn gate_counts(data: &CircuitData<F, C, D>) -> Vec<usize> {
+    let selectors = &data.common.selectors_info;
+    let mut counts = vec![0; data.common.gates.len()];
+    let values: Vec<Vec<F>> = data.prover_only.constants_sigmas_commitment.polynomials
+        [..selectors.num_selectors()]
+        .iter()
+        .map(|poly| poly.clone().fft().values)
+        .collect();
+
+    for row in 0..data.common.degree() {
+        // Each row is selected by exactly one group, whose selector holds the gate index. The
+        // selectors of the other groups hold a value outside of their range.
+        for (group, range) in selectors.groups.iter().enumerate() {
+            let gate = values[group][row].to_canonical_u64() as usize;
+            if range.contains(&gate) {
+                counts[gate] += 1;
+                break;
+            }
+        }
+    }
+
+    counts
+}
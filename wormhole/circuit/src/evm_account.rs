@@ -0,0 +1,80 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::codec::{ByteCodec, FieldElementCodec};
+use zk_circuits_common::circuit::F;
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes};
+
+/// The size of an EVM address in bytes.
+pub const EVM_ACCOUNT_NUM_BYTES: usize = 20;
+
+/// A 20-byte EVM address, as used by accounts funded through the EVM bridge. Like
+/// [`crate::substrate_account::SubstrateAccount`], each 8 bytes are represented by a field
+/// element, so the last field element only holds the final 4 bytes.
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+pub struct EvmAccount([u8; EVM_ACCOUNT_NUM_BYTES]);
+
+impl EvmAccount {
+    /// The number of field elements returned by [`FieldElementCodec::to_field_elements`].
+    pub const NUM_FELTS: usize = EVM_ACCOUNT_NUM_BYTES.div_ceil(8);
+
+    pub fn new(address: &[u8]) -> anyhow::Result<Self> {
+        Self::from_bytes(address)
+    }
+}
+
+impl From<[u8; EVM_ACCOUNT_NUM_BYTES]> for EvmAccount {
+    fn from(address: [u8; EVM_ACCOUNT_NUM_BYTES]) -> Self {
+        Self(address)
+    }
+}
+
+impl AsRef<[u8]> for EvmAccount {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl ByteCodec for EvmAccount {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_bytes(slice: &[u8]) -> anyhow::Result<Self> {
+        let address: [u8; EVM_ACCOUNT_NUM_BYTES] = slice.try_into().map_err(|_| {
+            anyhow::anyhow!(
+                "Expected {} bytes for EvmAccount, got: {}",
+                EVM_ACCOUNT_NUM_BYTES,
+                slice.len()
+            )
+        })?;
+        Ok(Self(address))
+    }
+}
+
+impl FieldElementCodec for EvmAccount {
+    fn to_field_elements(&self) -> Vec<F> {
+        bytes_to_felts(&self.0)
+    }
+
+    fn from_field_elements(elements: &[F]) -> anyhow::Result<Self> {
+        if elements.len() != Self::NUM_FELTS {
+            return Err(anyhow::anyhow!(
+                "Expected {} field elements for EvmAccount, got: {}",
+                Self::NUM_FELTS,
+                elements.len()
+            ));
+        }
+        let bytes = felts_to_bytes(elements);
+        let (address, padding) = bytes.split_at(EVM_ACCOUNT_NUM_BYTES);
+        if padding.iter().any(|&byte| byte != 0) {
+            return Err(anyhow::anyhow!(
+                "Field elements for EvmAccount exceed {} bytes",
+                EVM_ACCOUNT_NUM_BYTES
+            ));
+        }
+        Self::from_bytes(address)
+    }
+}
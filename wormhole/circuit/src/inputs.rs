@@ -1,29 +1,41 @@
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
 #[cfg(feature = "std")]
-use std::vec::Vec;
+use std::{string::String, vec::Vec};
 
 use crate::codec::FieldElementCodec;
 use crate::nullifier::Nullifier;
 use crate::substrate_account::SubstrateAccount;
 use crate::unspendable_account::UnspendableAccount;
 use anyhow::bail;
-use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::{field::types::PrimeField64, plonk::proof::ProofWithPublicInputs};
 use zk_circuits_common::circuit::{C, D, F};
-use zk_circuits_common::utils::{felts_to_bytes, felts_to_u128};
+use zk_circuits_common::utils::{
+    bytes_to_felts, felts_to_bytes, felts_to_u128, Amount, Digest, DIGEST_NUM_FIELD_ELEMENTS,
+};
 
 /// The total size of the public inputs field element vector.
-const PUBLIC_INPUTS_FELTS_LEN: usize = 14;
-#[allow(dead_code)]
+pub const PUBLIC_INPUTS_FELTS_LEN: usize = EXIT_ACCOUNT_END_INDEX;
 const NULLIFIER_START_INDEX: usize = 0;
-#[allow(dead_code)]
 const NULLIFIER_END_INDEX: usize = 4;
 const FUNDING_AMOUNT_START_INDEX: usize = 4;
 const FUNDING_AMOUNT_END_INDEX: usize = 6;
-const ROOT_HASH_START_INDEX: usize = 6;
-const ROOT_HASH_END_INDEX: usize = 10;
+pub const ROOT_HASH_START_INDEX: usize = 6;
+pub const ROOT_HASH_END_INDEX: usize = 10;
 const EXIT_ACCOUNT_START_INDEX: usize = 10;
-const EXIT_ACCOUNT_END_INDEX: usize = 14;
+const EXIT_ACCOUNT_END_INDEX: usize = EXIT_ACCOUNT_START_INDEX + SubstrateAccount::NUM_FELTS;
+/// The size of the public inputs field element vector of a circuit built with
+/// [`crate::circuit::WormholeCircuit::with_public_to_account`].
+pub const PUBLIC_INPUTS_WITH_TO_ACCOUNT_FELTS_LEN: usize = TO_ACCOUNT_END_INDEX;
+const TO_ACCOUNT_START_INDEX: usize = EXIT_ACCOUNT_END_INDEX;
+const TO_ACCOUNT_END_INDEX: usize = TO_ACCOUNT_START_INDEX + DIGEST_NUM_FIELD_ELEMENTS;
+/// The index of the chain id among the public inputs of a circuit built with
+/// [`crate::circuit::WormholeCircuit::with_chain_id`].
+pub const CHAIN_ID_INDEX: usize = EXIT_ACCOUNT_END_INDEX;
+/// The size of the public inputs field element vector of a circuit built with
+/// [`crate::circuit::WormholeCircuit::with_chain_id`].
+pub const PUBLIC_INPUTS_WITH_CHAIN_ID_FELTS_LEN: usize = CHAIN_ID_INDEX + 1;
 pub const DEFAULT_SECRET: &str = "9aa84f99ef2de22e3070394176868df41d6a148117a36132d010529e19b018b7";
 pub const DEFAULT_FUNDING_NONCE: u32 = 0;
 pub const DEFAULT_FUNDING_ACCOUNT: &[u8] = &[10u8; 32];
@@ -48,6 +60,38 @@ pub struct PublicCircuitInputs {
     pub exit_account: SubstrateAccount,
 }
 
+impl CircuitInputs {
+    /// The public part of the inputs, which is all a verifier needs.
+    pub fn public_view(&self) -> &PublicCircuitInputs {
+        &self.public
+    }
+}
+
+impl PublicCircuitInputs {
+    /// Returns the public inputs as the exact field element vector registered by the circuit, so
+    /// that a verifier can compare it against a proof's public inputs.
+    pub fn to_field_elements(&self) -> Vec<F> {
+        let mut elements = Vec::with_capacity(PUBLIC_INPUTS_FELTS_LEN);
+        elements.extend(self.nullifier.hash);
+        elements.extend(Amount::from(self.funding_amount).0);
+        elements.extend(bytes_to_felts(&self.root_hash));
+        elements.extend(self.exit_account.to_field_elements());
+        elements
+    }
+}
+
+/// The leaf fields and exit account as revealed in the public inputs of a proof.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PublicLeaf {
+    /// The amount transferred to the unspendable account, as committed to by the proven leaf.
+    pub funding_amount: u128,
+    /// The address of the account to pay out to.
+    pub exit_account: SubstrateAccount,
+    /// The account id of the unspendable account the funds were transferred to, if the circuit
+    /// was built with [`crate::circuit::WormholeCircuit::with_public_to_account`].
+    pub to_account: Option<Digest>,
+}
+
 /// All of the private inputs required for the circuit.
 #[derive(Debug)]
 pub struct PrivateCircuitInputs {
@@ -60,6 +104,9 @@ pub struct PrivateCircuitInputs {
     pub storage_proof: Vec<(Vec<u8>, Vec<u8>)>,
     pub funding_nonce: u32,
     pub funding_account: SubstrateAccount,
+    /// The accounts that jointly funded the leaf along with `funding_account`, see
+    /// [`crate::leaf::LeafInputs::funding_accounts`]. Empty for a single funding account.
+    pub funding_accounts: Vec<SubstrateAccount>,
     /// The unspendable account hash.
     pub unspendable_account: UnspendableAccount,
 }
@@ -112,3 +159,100 @@ impl TryFrom<ProofWithPublicInputs<F, C, D>> for PublicCircuitInputs {
         })
     }
 }
+
+/// Decodes the funding amount, the exit account and, if it is public, the to_account from the
+/// public inputs of `proof`. The circuit binds the funding amount and to_account to the proven
+/// storage leaf, so once the proof is verified they describe the transfer that was made. This
+/// only reads the public inputs, and doesn't verify the proof.
+pub fn extract_leaf_public(proof: &ProofWithPublicInputs<F, C, D>) -> anyhow::Result<PublicLeaf> {
+    let public_inputs = &proof.public_inputs;
+    let to_account = match public_inputs.len() {
+        PUBLIC_INPUTS_FELTS_LEN => None,
+        PUBLIC_INPUTS_WITH_TO_ACCOUNT_FELTS_LEN => Some(
+            public_inputs[TO_ACCOUNT_START_INDEX..TO_ACCOUNT_END_INDEX]
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("failed to deserialize to_account"))?,
+        ),
+        len => bail!(
+            "public inputs should contain: {} or {} field elements, got: {}",
+            PUBLIC_INPUTS_FELTS_LEN,
+            PUBLIC_INPUTS_WITH_TO_ACCOUNT_FELTS_LEN,
+            len
+        ),
+    };
+
+    let funding_amount = felts_to_u128(<[F; 2]>::try_from(
+        &public_inputs[FUNDING_AMOUNT_START_INDEX..FUNDING_AMOUNT_END_INDEX],
+    )?);
+    let exit_account = SubstrateAccount::from_field_elements(
+        &public_inputs[EXIT_ACCOUNT_START_INDEX..EXIT_ACCOUNT_END_INDEX],
+    )?;
+
+    Ok(PublicLeaf {
+        funding_amount,
+        exit_account,
+        to_account,
+    })
+}
+
+/// Decodes the chain id from the public inputs of `proof`, for a circuit built with
+/// [`crate::circuit::WormholeCircuit::with_chain_id`]. This only reads the public inputs, and
+/// doesn't verify the proof.
+///
+/// # Errors
+///
+/// Returns an error if the proof doesn't have [`PUBLIC_INPUTS_WITH_CHAIN_ID_FELTS_LEN`] public
+/// inputs, or if the chain id doesn't fit in a `u32`.
+pub fn extract_chain_id(proof: &ProofWithPublicInputs<F, C, D>) -> anyhow::Result<u32> {
+    let public_inputs = &proof.public_inputs;
+    if public_inputs.len() != PUBLIC_INPUTS_WITH_CHAIN_ID_FELTS_LEN {
+        bail!(
+            "public inputs should contain: {} field elements, got: {}",
+            PUBLIC_INPUTS_WITH_CHAIN_ID_FELTS_LEN,
+            public_inputs.len()
+        )
+    }
+    let Ok(chain_id) = u32::try_from(public_inputs[CHAIN_ID_INDEX].to_canonical_u64()) else {
+        bail!("chain id doesn't fit in a u32")
+    };
+    Ok(chain_id)
+}
+
+/// The name and index range of each public input of the wormhole circuit, in order.
+pub fn public_input_layout() -> [(&'static str, Range<usize>); 4] {
+    [
+        ("nullifier", NULLIFIER_START_INDEX..NULLIFIER_END_INDEX),
+        (
+            "funding_amount",
+            FUNDING_AMOUNT_START_INDEX..FUNDING_AMOUNT_END_INDEX,
+        ),
+        ("root_hash", ROOT_HASH_START_INDEX..ROOT_HASH_END_INDEX),
+        (
+            "exit_account",
+            EXIT_ACCOUNT_START_INDEX..EXIT_ACCOUNT_END_INDEX,
+        ),
+    ]
+}
+
+/// Groups the public inputs of `proof` by name, following [`public_input_layout`].
+///
+/// # Errors
+///
+/// Returns an error if the proof doesn't have [`PUBLIC_INPUTS_FELTS_LEN`] public inputs.
+pub fn decode_public_inputs(
+    proof: &ProofWithPublicInputs<F, C, D>,
+) -> anyhow::Result<Vec<(String, Vec<F>)>> {
+    let public_inputs = &proof.public_inputs;
+    if public_inputs.len() != PUBLIC_INPUTS_FELTS_LEN {
+        bail!(
+            "public inputs should contain: {} field elements, got: {}",
+            PUBLIC_INPUTS_FELTS_LEN,
+            public_inputs.len()
+        )
+    }
+
+    Ok(public_input_layout()
+        .into_iter()
+        .map(|(name, range)| (String::from(name), public_inputs[range].to_vec()))
+        .collect())
+}
@@ -0,0 +1,137 @@
+//! Reading [`CircuitInputs`] from JSON, with the public and private inputs kept in separate
+//! documents, e.g. files with different access control.
+//!
+//! The public document holds the values revealed by a proof:
+//!
+//! ```json
+//! {
+//!     "funding_amount": "1000000000000",
+//!     "root_hash": "0x...",
+//!     "exit_account": "0x...",
+//!     "nullifier": "0x..."
+//! }
+//! ```
+//!
+//! The amount is a decimal string, as it may not fit in a JSON number, and the nullifier is
+//! optional: it is derived from the private inputs, and only checked against them when present.
+//! The private document holds everything else, with the storage proof nodes split at their child
+//! hash:
+//!
+//! ```json
+//! {
+//!     "secret": "0x...",
+//!     "funding_nonce": 0,
+//!     "funding_account": "0x...",
+//!     "funding_accounts": ["0x..."],
+//!     "storage_proof": [["0x...", "0x..."]]
+//! }
+//! ```
+//!
+//! `funding_accounts` lists the accounts that jointly funded the leaf along with
+//! `funding_account`, and can be omitted when there are none.
+use anyhow::{anyhow, bail, Context};
+use serde_json::Value;
+
+use crate::inputs::{CircuitInputs, PrivateCircuitInputs, PublicCircuitInputs};
+use crate::leaf::MAX_FUNDING_ACCOUNTS;
+use crate::nullifier::Nullifier;
+use crate::substrate_account::SubstrateAccount;
+use crate::unspendable_account::UnspendableAccount;
+use zk_circuits_common::utils::felts_to_bytes;
+
+impl CircuitInputs {
+    /// Combines the public and private inputs, each given as a JSON document in the format
+    /// described in [`crate::json`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either document is malformed, or if the public nullifier doesn't match
+    /// the one derived from the private inputs.
+    pub fn from_parts(public_json: &str, private_json: &str) -> anyhow::Result<CircuitInputs> {
+        let public: Value = serde_json::from_str(public_json).context("invalid public inputs")?;
+        let private: Value =
+            serde_json::from_str(private_json).context("invalid private inputs")?;
+
+        let secret = hex_field(&private, "secret")?;
+        let funding_nonce = private
+            .get("funding_nonce")
+            .and_then(Value::as_u64)
+            .and_then(|nonce| u32::try_from(nonce).ok())
+            .ok_or_else(|| anyhow!("funding_nonce must be a u32"))?;
+        let funding_account = SubstrateAccount::new(&hex_field(&private, "funding_account")?)?;
+        let funding_accounts = match private.get("funding_accounts") {
+            None => Vec::new(),
+            Some(accounts) => accounts
+                .as_array()
+                .ok_or_else(|| anyhow!("funding_accounts must be an array of hex strings"))?
+                .iter()
+                .map(|account| SubstrateAccount::new(&hex_value(account)?))
+                .collect::<anyhow::Result<_>>()?,
+        };
+        if funding_accounts.len() > MAX_FUNDING_ACCOUNTS {
+            bail!(
+                "funding_accounts holds at most {} accounts, got: {}",
+                MAX_FUNDING_ACCOUNTS,
+                funding_accounts.len()
+            )
+        }
+        let storage_proof = private
+            .get("storage_proof")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("storage_proof must be an array of nodes"))?
+            .iter()
+            .map(|node| match node.as_array().map(Vec::as_slice) {
+                Some([left, right]) => Ok((hex_value(left)?, hex_value(right)?)),
+                _ => bail!("storage proof nodes must be pairs of hex strings"),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let funding_amount = public
+            .get("funding_amount")
+            .and_then(Value::as_str)
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(|| anyhow!("funding_amount must be a decimal string"))?;
+        let root_hash = hex_field(&public, "root_hash")?
+            .try_into()
+            .map_err(|_| anyhow!("root_hash must be 32 bytes"))?;
+        let exit_account = SubstrateAccount::new(&hex_field(&public, "exit_account")?)?;
+
+        let nullifier = Nullifier::new(&secret, funding_nonce, funding_account.as_ref());
+        if public.get("nullifier").is_some()
+            && hex_field(&public, "nullifier")? != felts_to_bytes(&nullifier.hash)
+        {
+            bail!("nullifier doesn't match the private inputs")
+        }
+
+        Ok(CircuitInputs {
+            public: PublicCircuitInputs {
+                funding_amount,
+                nullifier,
+                root_hash,
+                exit_account,
+            },
+            private: PrivateCircuitInputs {
+                unspendable_account: UnspendableAccount::new(&secret),
+                secret,
+                storage_proof,
+                funding_nonce,
+                funding_account,
+                funding_accounts,
+            },
+        })
+    }
+}
+
+fn hex_field(document: &Value, field: &str) -> anyhow::Result<Vec<u8>> {
+    let value = document
+        .get(field)
+        .ok_or_else(|| anyhow!("missing field {}", field))?;
+    hex_value(value).with_context(|| format!("invalid field {}", field))
+}
+
+fn hex_value(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let Some(hex) = value.as_str() else {
+        bail!("expected a hex string")
+    };
+    Ok(hex::decode(hex.trim_start_matches("0x"))?)
+}
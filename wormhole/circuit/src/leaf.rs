@@ -0,0 +1,255 @@
+//! The inputs of the storage leaf proven by the wormhole circuit. The last node of the storage
+//! proof embeds the Poseidon hash of these inputs, so that the proof commits to the transfer they
+//! describe rather than to an arbitrary node of the trie.
+//!
+//! A leaf can be jointly funded by several accounts, in which case the accounts other than
+//! `funding_account` are appended to the leaf inputs, see [`LeafInputs::funding_accounts`].
+//!
+//! A deployment can also mix a domain constant into the leaf hash, see
+//! [`LeafInputs::hash_with_domain`], so that proofs of one deployment, e.g. testnet, don't verify
+//! against the circuit of another, e.g. mainnet.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use anyhow::bail;
+use plonky2::{
+    field::{extension::Extendable, types::PrimeField64},
+    hash::{
+        hash_types::{HashOut, HashOutTarget, RichField},
+        poseidon::PoseidonHash,
+    },
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{circuit_builder::CircuitBuilder, config::Hasher},
+};
+
+use crate::codec::FieldElementCodec;
+use crate::inputs::CircuitInputs;
+use crate::nullifier::NONCE_NUM_TARGETS;
+use crate::storage_proof::FELTS_PER_AMOUNT;
+use crate::substrate_account::SubstrateAccount;
+use crate::unspendable_account::{UnspendableAccount, SECRET_NUM_TARGETS};
+use zk_circuits_common::circuit::F;
+use zk_circuits_common::utils::{felts_to_u128, Amount, Digest, Nonce};
+
+/// The number of field elements returned by [`FieldElementCodec::to_field_elements`] for
+/// [`LeafInputs`] without additional funding accounts: the funding nonce, the funding account, the
+/// account id of the unspendable account, i.e. its field elements without the secret, and the
+/// funding amount. Each additional funding account adds [`SubstrateAccount::NUM_FELTS`].
+pub const LEAF_INPUTS_NUM_FELTS: usize = NONCE_NUM_TARGETS
+    + SubstrateAccount::NUM_FELTS
+    + (UnspendableAccount::NUM_FELTS - SECRET_NUM_TARGETS)
+    + FELTS_PER_AMOUNT;
+
+/// The maximum number of additional funding accounts of a leaf, see
+/// [`LeafInputs::funding_accounts`].
+pub const MAX_FUNDING_ACCOUNTS: usize = 8;
+
+/// The number of field elements of leaf inputs with `num_funding_accounts` additional funding
+/// accounts.
+pub const fn leaf_inputs_num_felts(num_funding_accounts: usize) -> usize {
+    LEAF_INPUTS_NUM_FELTS + num_funding_accounts * SubstrateAccount::NUM_FELTS
+}
+
+/// A transfer of `funding_amount` from `funding_account` to the unspendable `to_account`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafInputs {
+    pub funding_nonce: u32,
+    pub funding_account: SubstrateAccount,
+    /// The account id of the unspendable account the funds were transferred to.
+    pub to_account: Digest,
+    pub funding_amount: u128,
+    /// The accounts that funded the leaf along with `funding_account`, when several accounts
+    /// jointly fund one unspendable account. Empty for a leaf with a single funding account, whose
+    /// hash is then the same as before pooled funding was supported. At most
+    /// [`MAX_FUNDING_ACCOUNTS`].
+    pub funding_accounts: Vec<SubstrateAccount>,
+}
+
+impl LeafInputs {
+    /// The Poseidon hash of the field elements of the leaf inputs, which the last node of the
+    /// storage proof embeds.
+    pub fn hash(&self) -> Digest {
+        PoseidonHash::hash_no_pad(&self.to_field_elements()).elements
+    }
+
+    /// Same as [`LeafInputs::hash`], but with the deployment `domain` hashed before the leaf
+    /// inputs, as in a circuit built with
+    /// [`crate::storage_proof::StorageProofTargets::with_domain`].
+    pub fn hash_with_domain(&self, domain: F) -> Digest {
+        let mut preimage =
+            Vec::with_capacity(1 + leaf_inputs_num_felts(self.funding_accounts.len()));
+        preimage.push(domain);
+        preimage.extend(self.to_field_elements());
+        PoseidonHash::hash_no_pad(&preimage).elements
+    }
+
+    /// Sets the leaf input targets, except for the funding amount, which the storage proof sets
+    /// as it shares its targets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the targets weren't built for as many additional funding accounts as
+    /// the leaf has.
+    pub fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: &LeafInputsTargets,
+    ) -> anyhow::Result<()> {
+        if self.funding_accounts.len() != targets.funding_accounts.len() {
+            bail!(
+                "the circuit binds {} additional funding accounts, but the leaf has {}",
+                targets.funding_accounts.len(),
+                self.funding_accounts.len()
+            )
+        }
+
+        pw.set_target(targets.funding_nonce, Nonce::from(self.funding_nonce).0)?;
+        pw.set_hash_target(
+            targets.funding_account,
+            HashOut::from_partial(&self.funding_account.to_field_elements()),
+        )?;
+        pw.set_hash_target(targets.to_account, HashOut::from(self.to_account))?;
+        for (account, &target) in self.funding_accounts.iter().zip(&targets.funding_accounts) {
+            pw.set_hash_target(target, HashOut::from_partial(&account.to_field_elements()))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&CircuitInputs> for LeafInputs {
+    fn from(inputs: &CircuitInputs) -> Self {
+        Self {
+            funding_nonce: inputs.private.funding_nonce,
+            funding_account: inputs.private.funding_account,
+            to_account: inputs.private.unspendable_account.account_id,
+            funding_amount: inputs.public.funding_amount,
+            funding_accounts: inputs.private.funding_accounts.clone(),
+        }
+    }
+}
+
+impl FieldElementCodec for LeafInputs {
+    fn to_field_elements(&self) -> Vec<F> {
+        let mut elements = Vec::with_capacity(leaf_inputs_num_felts(self.funding_accounts.len()));
+        elements.push(Nonce::from(self.funding_nonce).0);
+        elements.extend(self.funding_account.to_field_elements());
+        elements.extend(self.to_account);
+        elements.extend(Amount::from(self.funding_amount).0);
+        for account in &self.funding_accounts {
+            elements.extend(account.to_field_elements());
+        }
+        elements
+    }
+
+    fn from_field_elements(elements: &[F]) -> anyhow::Result<Self> {
+        let num_extra_felts = elements.len().saturating_sub(LEAF_INPUTS_NUM_FELTS);
+        if elements.len() < LEAF_INPUTS_NUM_FELTS
+            || num_extra_felts % SubstrateAccount::NUM_FELTS != 0
+            || num_extra_felts / SubstrateAccount::NUM_FELTS > MAX_FUNDING_ACCOUNTS
+        {
+            bail!(
+                "expected {} leaf input field elements, plus {} per additional funding account up to {} accounts, got: {}",
+                LEAF_INPUTS_NUM_FELTS,
+                SubstrateAccount::NUM_FELTS,
+                MAX_FUNDING_ACCOUNTS,
+                elements.len()
+            )
+        }
+
+        let (elements, funding_accounts) = elements.split_at(LEAF_INPUTS_NUM_FELTS);
+        let (nonce, elements) = elements.split_at(NONCE_NUM_TARGETS);
+        let (funding_account, elements) = elements.split_at(SubstrateAccount::NUM_FELTS);
+        let (to_account, funding_amount) = elements.split_at(elements.len() - FELTS_PER_AMOUNT);
+        let Ok(funding_nonce) = u32::try_from(nonce[0].to_canonical_u64()) else {
+            bail!("leaf funding nonce doesn't fit in a u32")
+        };
+
+        Ok(Self {
+            funding_nonce,
+            funding_account: SubstrateAccount::from_field_elements(funding_account)?,
+            to_account: to_account
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("failed to deserialize leaf to_account"))?,
+            funding_amount: felts_to_u128(
+                funding_amount
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("failed to deserialize leaf funding amount"))?,
+            ),
+            funding_accounts: funding_accounts
+                .chunks(SubstrateAccount::NUM_FELTS)
+                .map(SubstrateAccount::from_field_elements)
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+/// The targets of [`LeafInputs`], hashed in the same order as
+/// [`FieldElementCodec::to_field_elements`].
+#[derive(Debug, Clone)]
+pub struct LeafInputsTargets {
+    pub funding_nonce: Target,
+    pub funding_account: HashOutTarget,
+    pub to_account: HashOutTarget,
+    /// The funding amount targets of the storage proof, see
+    /// [`crate::storage_proof::StorageProofTargets::funding_amount`].
+    pub funding_amount: [Target; FELTS_PER_AMOUNT],
+    /// The additional funding accounts, see [`LeafInputs::funding_accounts`].
+    pub funding_accounts: Vec<HashOutTarget>,
+    /// The deployment domain constant of a circuit built with
+    /// [`crate::storage_proof::StorageProofTargets::with_domain`].
+    pub domain: Option<Target>,
+}
+
+impl LeafInputsTargets {
+    /// Creates the leaf input targets for a leaf with `num_funding_accounts` additional funding
+    /// accounts, reusing `funding_amount` so that the funding amount of the storage proof is the
+    /// one of the leaf.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_funding_accounts` is greater than [`MAX_FUNDING_ACCOUNTS`].
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        funding_amount: [Target; FELTS_PER_AMOUNT],
+        num_funding_accounts: usize,
+    ) -> Self {
+        assert!(
+            num_funding_accounts <= MAX_FUNDING_ACCOUNTS,
+            "a leaf has at most {} additional funding accounts, got: {}",
+            MAX_FUNDING_ACCOUNTS,
+            num_funding_accounts
+        );
+        Self {
+            funding_nonce: builder.add_virtual_target(),
+            funding_account: builder.add_virtual_hash(),
+            to_account: builder.add_virtual_hash(),
+            funding_amount,
+            funding_accounts: builder.add_virtual_hashes(num_funding_accounts),
+            domain: None,
+        }
+    }
+
+    /// Computes the hash of the leaf inputs in-circuit, see [`LeafInputs::hash`], or
+    /// [`LeafInputs::hash_with_domain`] if the targets have a domain.
+    pub fn hash<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> HashOutTarget {
+        let mut preimage =
+            Vec::with_capacity(1 + leaf_inputs_num_felts(self.funding_accounts.len()));
+        preimage.extend(self.domain);
+        preimage.push(self.funding_nonce);
+        preimage.extend(self.funding_account.elements);
+        preimage.extend(self.to_account.elements);
+        preimage.extend(self.funding_amount);
+        for account in &self.funding_accounts {
+            preimage.extend(account.elements);
+        }
+        builder.hash_n_to_hash_no_pad::<PoseidonHash>(preimage)
+    }
+}
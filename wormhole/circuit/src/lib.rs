@@ -5,10 +5,22 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+pub mod blinded_key;
+pub mod blocklist;
 pub mod circuit;
 pub mod codec;
+#[cfg(feature = "debug-dump")]
+pub mod debug_dump;
+pub mod evm_account;
 pub mod inputs;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod leaf;
+pub mod nested_storage_proof;
 pub mod nullifier;
+pub mod storage_key;
 pub mod storage_proof;
 pub mod substrate_account;
+pub mod system_account;
+pub mod timestamp;
 pub mod unspendable_account;
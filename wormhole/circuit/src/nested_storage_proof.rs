@@ -0,0 +1,131 @@
+//! Storage proofs whose leaf value is itself the root of another trie.
+//!
+//! The outer proof proves a key-value pair under the state root, and the inner proof proves a
+//! leaf under the value of that pair. Both are verified as regular [`StorageProof`]s, and the
+//! inner root is constrained to appear in the outer leaf node, at a field element boundary.
+use anyhow::anyhow;
+use plonky2::{
+    field::types::Field,
+    hash::{
+        hash_types::{HashOutTarget, NUM_HASH_OUT_ELTS},
+        poseidon::PoseidonHash,
+    },
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{circuit_builder::CircuitBuilder, config::AlgebraicHasher},
+};
+
+use crate::storage_proof::{
+    PublicInputLayout, StorageProof, StorageProofTargets, PROOF_NODE_MAX_SIZE_F,
+};
+use zk_circuits_common::circuit::{CircuitFragment, D, F};
+
+#[derive(Debug, Clone)]
+pub struct NestedStorageProofTargets {
+    pub outer: StorageProofTargets,
+    pub inner: StorageProofTargets,
+    /// The field element index of the inner root within the outer leaf node.
+    pub value_offset: Target,
+}
+
+impl NestedStorageProofTargets {
+    /// Creates the targets of both proofs. The outer root and the inner funding amount are the
+    /// public inputs, in that order, as the inner root is constrained in-circuit.
+    pub fn new(builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self {
+            outer: StorageProofTargets::with_layout(builder, PublicInputLayout::RootOnly),
+            inner: StorageProofTargets::with_layout(builder, PublicInputLayout::AmountOnly),
+            value_offset: builder.add_virtual_target(),
+        }
+    }
+}
+
+/// A storage proof chained under the value of another storage proof's leaf.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NestedStorageProof<H = PoseidonHash> {
+    pub outer: StorageProof<H>,
+    pub inner: StorageProof<H>,
+    value_offset: usize,
+}
+
+impl<H: AlgebraicHasher<F>> NestedStorageProof<H> {
+    /// Chains `inner` under the leaf of `outer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the leaf node of `outer` doesn't contain the root of `inner`, starting
+    /// at a field element boundary.
+    pub fn new(outer: StorageProof<H>, inner: StorageProof<H>) -> anyhow::Result<Self> {
+        let leaf = outer
+            .proof
+            .last()
+            .ok_or_else(|| anyhow!("outer storage proof must not be empty"))?;
+        let value_offset = leaf
+            .windows(NUM_HASH_OUT_ELTS)
+            .position(|window| window == inner.root_hash)
+            .ok_or_else(|| anyhow!("outer leaf does not contain the inner root"))?;
+
+        Ok(Self {
+            outer,
+            inner,
+            value_offset,
+        })
+    }
+}
+
+impl<H: AlgebraicHasher<F>> CircuitFragment for NestedStorageProof<H> {
+    type Targets = NestedStorageProofTargets;
+
+    fn circuit(
+        Self::Targets {
+            outer,
+            inner,
+            value_offset,
+        }: &Self::Targets,
+        builder: &mut CircuitBuilder<F, D>,
+    ) {
+        StorageProof::<H>::circuit(outer, builder);
+        StorageProof::<H>::circuit(inner, builder);
+
+        // Select the outer leaf node, i.e. the node at `proof_len - 1`.
+        let zero = builder.zero();
+        let mut leaf = [zero; PROOF_NODE_MAX_SIZE_F];
+        for (i, node) in outer.proof_data.iter().enumerate() {
+            let index = builder.constant(F::from_canonical_usize(i + 1));
+            let is_leaf = builder.is_equal(outer.proof_len, index);
+            for (felt, &node_felt) in leaf.iter_mut().zip(node) {
+                *felt = builder.mul_add(is_leaf.target, node_felt, *felt);
+            }
+        }
+
+        // Select the value at `value_offset` within the leaf, which must be a valid offset.
+        let mut value = [zero; NUM_HASH_OUT_ELTS];
+        let mut num_offsets = zero;
+        for offset in 0..=PROOF_NODE_MAX_SIZE_F - NUM_HASH_OUT_ELTS {
+            let index = builder.constant(F::from_canonical_usize(offset));
+            let is_offset = builder.is_equal(*value_offset, index);
+            num_offsets = builder.add(num_offsets, is_offset.target);
+            for (y, felt) in value.iter_mut().enumerate() {
+                *felt = builder.mul_add(is_offset.target, leaf[offset + y], *felt);
+            }
+        }
+        builder.assert_one(num_offsets);
+
+        builder.connect_hashes(HashOutTarget { elements: value }, inner.root_hash);
+    }
+
+    fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: Self::Targets,
+    ) -> anyhow::Result<()> {
+        self.outer.fill_targets(pw, targets.outer)?;
+        self.inner.fill_targets(pw, targets.inner)?;
+        pw.set_target(
+            targets.value_offset,
+            F::from_canonical_usize(self.value_offset),
+        )
+    }
+}
@@ -5,7 +5,6 @@ use std::vec::Vec;
 
 use crate::codec::ByteCodec;
 use crate::codec::FieldElementCodec;
-use plonky2::field::types::Field;
 use plonky2::{
     hash::{hash_types::HashOutTarget, poseidon::PoseidonHash},
     iop::{
@@ -15,7 +14,7 @@ use plonky2::{
     plonk::{circuit_builder::CircuitBuilder, config::Hasher},
 };
 use zk_circuits_common::circuit::{CircuitFragment, D, F};
-use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, string_to_felt, Digest};
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, string_to_felt, Digest, Nonce};
 
 pub const NULLIFIER_SALT: &str = "~nullif~";
 pub const SECRET_NUM_TARGETS: usize = 4;
@@ -39,7 +38,7 @@ impl Nullifier {
         let mut preimage = Vec::new();
         let salt = string_to_felt(NULLIFIER_SALT);
         let secret = bytes_to_felts(secret);
-        let funding_nonce = F::from_canonical_u32(funding_nonce);
+        let Nonce(funding_nonce) = funding_nonce.into();
         let funding_account = bytes_to_felts(funding_account);
         preimage.push(salt);
         preimage.extend(secret.clone());
@@ -183,7 +182,7 @@ impl FieldElementCodec for Nullifier {
 pub struct NullifierTargets {
     hash: HashOutTarget,
     pub secret: Vec<Target>,
-    funding_nonce: Target,
+    pub funding_nonce: Target,
     pub funding_account: Vec<Target>,
 }
 
@@ -0,0 +1,66 @@
+//! Off-circuit computation of Substrate storage keys, e.g. to fetch a storage proof with
+//! `state_getReadProof`.
+//!
+//! A storage key is `Twox128(pallet) ++ Twox128(item)`, followed for map items by the hashed
+//! SCALE encoded key.
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::{string::String, vec::Vec};
+
+use blake2::{
+    digest::consts::{U16, U32},
+    Blake2b, Digest as _,
+};
+use core::hash::Hasher as _;
+use twox_hash::XxHash64;
+
+/// The hasher of a storage map key, as declared in the pallet's storage item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageHasher {
+    Blake2_128,
+    Blake2_256,
+    Blake2_128Concat,
+    Twox128,
+    Twox256,
+    Twox64Concat,
+    Identity,
+}
+
+impl StorageHasher {
+    /// Hashes a SCALE encoded map key.
+    pub fn hash(&self, key: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Blake2_128 => Blake2b::<U16>::digest(key).to_vec(),
+            Self::Blake2_256 => Blake2b::<U32>::digest(key).to_vec(),
+            Self::Blake2_128Concat => [Blake2b::<U16>::digest(key).as_slice(), key].concat(),
+            Self::Twox128 => twox(key, 2),
+            Self::Twox256 => twox(key, 4),
+            Self::Twox64Concat => [twox(key, 1).as_slice(), key].concat(),
+            Self::Identity => key.to_vec(),
+        }
+    }
+}
+
+/// Computes the storage key of `item` in `pallet`, as a `0x` prefixed hex string.
+///
+/// `key` is the SCALE encoded map key, hashed with `hasher`. For a plain storage value, pass an
+/// empty key with [`StorageHasher::Identity`].
+pub fn compute_storage_key(pallet: &str, item: &str, key: &[u8], hasher: StorageHasher) -> String {
+    let mut storage_key = twox(pallet.as_bytes(), 2);
+    storage_key.extend(twox(item.as_bytes(), 2));
+    storage_key.extend(hasher.hash(key));
+    format!("0x{}", hex::encode(storage_key))
+}
+
+/// Substrate's `Twox` hashes: the concatenation of `num_words` little-endian XXH64 hashes, seeded
+/// with their index.
+fn twox(data: &[u8], num_words: u64) -> Vec<u8> {
+    (0..num_words)
+        .flat_map(|seed| {
+            let mut hasher = XxHash64::with_seed(seed);
+            hasher.write(data);
+            hasher.finish().to_le_bytes()
+        })
+        .collect()
+}
@@ -1,75 +1,515 @@
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{format, vec, vec::Vec};
 #[cfg(feature = "std")]
 use std::vec::Vec;
 
+use core::fmt;
+use core::marker::PhantomData;
+
 use plonky2::{
-    field::types::Field,
+    field::{
+        extension::Extendable,
+        types::{Field, PrimeField64},
+    },
     hash::{
-        hash_types::{HashOut, HashOutTarget},
+        hash_types::{HashOut, HashOutTarget, RichField},
         poseidon::PoseidonHash,
     },
-    iop::{target::Target, witness::WitnessWrite},
-    plonk::circuit_builder::CircuitBuilder,
+    iop::{
+        generator::generate_partial_witness,
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, CircuitData},
+        config::{AlgebraicHasher, Hasher},
+        proof::ProofWithPublicInputs,
+    },
 };
 
+use anyhow::{bail, Context};
+
+use crate::codec::{ByteCodec, FieldElementCodec};
 use crate::inputs::CircuitInputs;
-use zk_circuits_common::circuit::{CircuitFragment, D, F};
-use zk_circuits_common::gadgets::is_const_less_than;
-use zk_circuits_common::utils::{bytes_to_felts, u128_to_felts, ZERO_DIGEST};
+use crate::leaf::{LeafInputs, LeafInputsTargets};
+use crate::system_account::SystemAccountLeaf;
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::gadgets::{canonical_le_bytes, is_const_less_than};
+use zk_circuits_common::utils::{
+    bytes_to_felts, decode_compact, felts_to_bytes, Amount, Digest, ZERO_DIGEST,
+};
 
 pub const MAX_PROOF_LEN: usize = 20;
 pub const PROOF_NODE_MAX_SIZE_F: usize = 73;
-pub const PROOF_NODE_MAX_SIZE_B: usize = 256;
+/// The maximum size of a proof node in bytes, i.e. the bytes that [`bytes_to_felts`] packs into
+/// [`PROOF_NODE_MAX_SIZE_F`] field elements. Longer nodes are rejected rather than truncated.
+///
+/// The limit is sized for the largest branch node: 16 children, each a 32-byte hash with its
+/// compact length prefix (528 bytes), the 2-byte child bitmap, and an inline value of up to 32
+/// bytes with its prefix (33 bytes), i.e. 563 bytes. The remaining 21 bytes hold a 1-byte header
+/// and a partial key of up to 40 nibbles, which branches of hashed storage keys don't exceed.
+///
+/// Every node is hashed in-circuit at its full size, i.e. 10 Poseidon permutations of 8 field
+/// elements each, so [`MAX_PROOF_LEN`] nodes cost 200 permutations, against 80 for 256-byte nodes.
+pub const PROOF_NODE_MAX_SIZE_B: usize = PROOF_NODE_MAX_SIZE_F * 8;
 pub const FELTS_PER_AMOUNT: usize = 2;
+/// The size of the child hash embedded in a proof node, in bytes.
+const CHILD_HASH_BYTES: usize = 32;
+/// The largest byte offset of a child hash within a node, such that it ends within the node.
+const MAX_CHILD_OFFSET: usize = PROOF_NODE_MAX_SIZE_B - CHILD_HASH_BYTES;
 #[derive(Debug, Clone)]
 pub struct StorageProofTargets {
     pub funding_amount: [Target; 2],
+    /// The Poseidon hash of the root node, packed 8 bytes per field element like any node hash.
+    /// Roots of other hashers, e.g. 32-byte Blake2b state roots, aren't supported: the circuit
+    /// compares the root to the Poseidon hash of the first node, so it can't be any other digest.
     pub root_hash: HashOutTarget,
     pub proof_len: Target,
     pub proof_data: Vec<Vec<Target>>,
     pub hashes: Vec<HashOutTarget>,
+    /// The byte offset of the child hash within each node. The circuit reads the child hash from
+    /// the node at this offset, so that it can't be chosen independently of the node.
+    pub child_offsets: Vec<Target>,
+    /// The value proven to be included by a circuit built with
+    /// [`StorageProofTargets::with_leaf_value`].
+    pub leaf_value: Option<HashOutTarget>,
+    /// The leaf inputs of a circuit built with [`StorageProofTargets::with_leaf_inputs`].
+    pub leaf_inputs: Option<LeafInputsTargets>,
+    /// The child trie targets of a circuit built with [`StorageProofTargets::with_child_trie`].
+    pub child_trie: Option<ChildTrieTargets>,
+}
+
+/// The targets of a child trie storage proof, see [`StorageProof::with_child_trie`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChildTrieTargets {
+    /// The root of the child trie, registered as a public input.
+    pub root: HashOutTarget,
+    /// The number of top trie nodes, the last of which embeds the child root.
+    pub top_len: Target,
+}
+
+/// Which of the storage proof values are registered as public inputs, and in what order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PublicInputLayout {
+    /// Only the root hash is public, the funding amount stays private.
+    RootOnly,
+    /// The funding amount followed by the root hash, as in the wormhole circuit.
+    #[default]
+    AmountThenRoot,
+    /// The root hash followed by the funding amount.
+    RootThenAmount,
+    /// Only the funding amount is public, for proofs whose root is constrained in-circuit, e.g.
+    /// the inner proof of a [`crate::nested_storage_proof::NestedStorageProof`].
+    AmountOnly,
+}
+
+impl PublicInputLayout {
+    /// Orders the funding amount and root hash as public inputs, leaving out private ones.
+    pub(crate) fn arrange<T: Clone>(self, funding_amount: &[T], root_hash: &[T]) -> Vec<T> {
+        match self {
+            Self::RootOnly => root_hash.to_vec(),
+            Self::AmountOnly => funding_amount.to_vec(),
+            Self::AmountThenRoot => [funding_amount, root_hash].concat(),
+            Self::RootThenAmount => [root_hash, funding_amount].concat(),
+        }
+    }
 }
 
 impl StorageProofTargets {
-    pub fn new(builder: &mut CircuitBuilder<F, D>) -> Self {
+    /// Creates the storage proof targets, registering the funding amount followed by the root
+    /// hash as public inputs. The circuit proves a path of nodes hanging off the root hash, and
+    /// nothing pins the node it ends at: a prefix of a valid path is valid too. Binding the last
+    /// node to what it holds, e.g. with [`StorageProofTargets::with_leaf_value`], makes it terminal.
+    ///
+    /// # Invariant
+    ///
+    /// No other target is registered as a public input, so the funding amount always occupies
+    /// the first [`FELTS_PER_AMOUNT`] public inputs registered by the fragment, and the root hash
+    /// the next four. The public input indices of the wormhole circuit, e.g.
+    /// [`crate::inputs::ROOT_HASH_START_INDEX`], rely on this order.
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self {
+        Self::with_layout(builder, PublicInputLayout::default())
+    }
+
+    /// Same as [`StorageProofTargets::new`], but registers the public inputs following `layout`.
+    pub fn with_layout<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        layout: PublicInputLayout,
+    ) -> Self {
+        Self::build(builder, layout, MAX_PROOF_LEN)
+    }
+
+    /// Same as [`StorageProofTargets::new`], but for proofs of up to `max_len` nodes rather than
+    /// [`MAX_PROOF_LEN`]. Proofs must be padded to the same length with
+    /// [`StorageProof::with_capacity`].
+    pub fn with_max_len<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        max_len: usize,
+    ) -> Self {
+        Self::build(builder, PublicInputLayout::default(), max_len)
+    }
+
+    fn build<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        layout: PublicInputLayout,
+        max_len: usize,
+    ) -> Self {
         // Setup targets. Each 8-bytes are represented as their equivalent field element. We also
         // need to track total proof length to allow for variable length.
-        let proof_data: Vec<_> = (0..MAX_PROOF_LEN)
+        assert_node_targets_len(PROOF_NODE_MAX_SIZE_F);
+        let proof_data: Vec<_> = (0..max_len)
             .map(|_| builder.add_virtual_targets(PROOF_NODE_MAX_SIZE_F))
             .collect();
 
-        let hashes: Vec<_> = (0..MAX_PROOF_LEN)
-            .map(|_| builder.add_virtual_hash())
-            .collect();
+        let hashes: Vec<_> = (0..max_len).map(|_| builder.add_virtual_hash()).collect();
+        let child_offsets = builder.add_virtual_targets(max_len);
+
+        let funding_amount = builder.add_virtual_target_arr::<FELTS_PER_AMOUNT>();
+        let root_hash = builder.add_virtual_hash();
+        builder.register_public_inputs(&layout.arrange(&funding_amount, &root_hash.elements));
 
         Self {
-            funding_amount: builder.add_virtual_public_input_arr::<FELTS_PER_AMOUNT>(),
-            root_hash: builder.add_virtual_hash_public_input(),
+            funding_amount,
+            root_hash,
             proof_len: builder.add_virtual_target(),
             proof_data,
             hashes,
+            child_offsets,
+            leaf_value: None,
+            leaf_inputs: None,
+            child_trie: None,
+        }
+    }
+
+    /// Creates targets for a plain Merkle path proof, that proves the inclusion of `leaf_value`,
+    /// e.g. an arbitrary 32-byte value, rather than of a storage leaf. The last node must embed
+    /// `leaf_value` at its child offset instead of having no child, and only the root hash is
+    /// public. The value
+    /// is set by the caller, e.g. by connecting it to another fragment. Proofs must end with the
+    /// value, see [`StorageProof::with_leaf_value`].
+    pub fn with_leaf_value<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        leaf_value: HashOutTarget,
+    ) -> Self {
+        let mut targets = Self::with_layout(builder, PublicInputLayout::RootOnly);
+        targets.leaf_value = Some(leaf_value);
+        targets
+    }
+
+    /// Same as [`StorageProofTargets::new`], but binds the proof to its leaf inputs: the last node
+    /// must embed the hash of the leaf inputs at its child offset, like the value of
+    /// [`StorageProofTargets::with_leaf_value`]. The funding amount is one of the leaf inputs, so
+    /// the public funding amount is the one of the proven leaf. Proofs must be bound to their leaf
+    /// inputs, see [`StorageProof::with_leaf_inputs`].
+    pub fn with_leaf_inputs<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self {
+        Self::with_funding_accounts(builder, 0)
+    }
+
+    /// Same as [`StorageProofTargets::with_leaf_inputs`], for leaves jointly funded by
+    /// `num_funding_accounts` additional funding accounts, which are all absorbed into the leaf
+    /// hash, see [`LeafInputs::funding_accounts`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_funding_accounts` is greater than [`crate::leaf::MAX_FUNDING_ACCOUNTS`].
+    pub fn with_funding_accounts<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        num_funding_accounts: usize,
+    ) -> Self {
+        let mut targets = Self::new(builder);
+        targets.leaf_inputs = Some(LeafInputsTargets::new(
+            builder,
+            targets.funding_amount,
+            num_funding_accounts,
+        ));
+        targets
+    }
+
+    /// Same as [`StorageProofTargets::with_leaf_inputs`], but bakes the deployment `domain` into
+    /// the circuit as a constant hashed before the leaf inputs, see
+    /// [`LeafInputs::hash_with_domain`]. The last node must then embed the domain's leaf hash, so
+    /// a proof of one domain, e.g. testnet, can't be proven or verified with the circuit of
+    /// another, e.g. mainnet. Proofs must be bound with [`StorageProof::with_leaf_inputs_in_domain`].
+    pub fn with_domain<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        domain: F,
+    ) -> Self {
+        let mut targets = Self::with_leaf_inputs(builder);
+        if let Some(leaf_inputs) = targets.leaf_inputs.as_mut() {
+            leaf_inputs.domain = Some(builder.constant(domain));
+        }
+        targets
+    }
+
+    /// Same as [`StorageProofTargets::with_domain`], but the domain is the id of the chain the
+    /// leaf belongs to, which the prover sets rather than the circuit baking it in. A single
+    /// circuit then proves leaves of several chains, each hashed in the domain of its own chain.
+    /// Proofs must be bound with [`StorageProof::with_leaf_inputs_in_domain`], e.g. with
+    /// [`StorageProof::on_chain`].
+    ///
+    /// The chain id isn't registered as a public input here, so that the public inputs of the
+    /// fragment keep their indices. The caller registers it, see
+    /// [`crate::circuit::WormholeCircuit::with_chain_id`].
+    pub fn with_chain_id<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self {
+        let mut targets = Self::with_leaf_inputs(builder);
+        if let Some(leaf_inputs) = targets.leaf_inputs.as_mut() {
+            leaf_inputs.domain = Some(builder.add_virtual_target());
+        }
+        targets
+    }
+
+    /// Same as [`StorageProofTargets::new`], for proofs of child trie storage built with
+    /// [`StorageProof::with_child_trie`]. The child root is constrained to be the child hash of
+    /// the last top trie node, and is registered as the last public input, so that a verifier can
+    /// check which child trie the storage belongs to.
+    pub fn with_child_trie<F: RichField + Extendable<D>, const D: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self {
+        let mut targets = Self::new(builder);
+        let root = builder.add_virtual_hash();
+        builder.register_public_inputs(&root.elements);
+        targets.child_trie = Some(ChildTrieTargets {
+            root,
+            top_len: builder.add_virtual_target(),
+        });
+        targets
+    }
+}
+
+/// Panics unless `num_targets` is the number of field elements that [`bytes_to_felts`] packs a
+/// full proof node into. This catches a change of packing that [`PROOF_NODE_MAX_SIZE_F`] wasn't
+/// updated for when the targets are created, rather than when they are filled.
+pub fn assert_node_targets_len(num_targets: usize) {
+    assert_node_targets_len_for(num_targets, PROOF_NODE_MAX_SIZE_B);
+}
+
+/// Same as [`assert_node_targets_len`], but for proof nodes of up to `node_max_bytes` bytes
+/// rather than [`PROOF_NODE_MAX_SIZE_B`], e.g. to check that the targets would catch a change of
+/// the maximum node size.
+pub fn assert_node_targets_len_for(num_targets: usize, node_max_bytes: usize) {
+    let packed_len = bytes_to_felts(&vec![0u8; node_max_bytes]).len();
+    assert_eq!(
+        num_targets, packed_len,
+        "proof nodes of {} bytes pack into {} field elements, but {} targets are allocated per node",
+        node_max_bytes, packed_len, num_targets
+    );
+}
+
+/// Builds a standalone storage proof circuit, whose nodes are hashed with Poseidon. The circuit
+/// only needs to be built once: each proof is generated by filling a fresh witness with
+/// [`CircuitFragment::fill_targets`] and proving it with the returned circuit data.
+pub fn build_storage_proof_circuit(
+    config: CircuitConfig,
+) -> (CircuitData<F, C, D>, StorageProofTargets) {
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let targets = StorageProofTargets::new(&mut builder);
+    StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+
+    (builder.build(), targets)
+}
+
+/// Builds the storage proof circuit, proves the storage proof of `inputs` with it and verifies
+/// the resulting proof, which is returned.
+///
+/// ```no_run
+/// # use wormhole_circuit::{inputs::CircuitInputs, storage_proof::prove_and_verify};
+/// # fn example(inputs: &CircuitInputs) -> anyhow::Result<()> {
+/// let proof = prove_and_verify(inputs)?;
+/// assert_eq!(proof.public_inputs.len(), 6);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the storage proof can't be built from `inputs`, or if proving or
+/// verification fails.
+pub fn prove_and_verify(inputs: &CircuitInputs) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (data, targets) = build_storage_proof_circuit(CircuitConfig::standard_recursion_config());
+    let mut pw = PartialWitness::new();
+    StorageProof::try_from(inputs)?.fill_targets(&mut pw, targets)?;
+
+    let proof = data.prove(pw)?;
+    data.verify(proof.clone())?;
+    Ok(proof)
+}
+
+/// The smallest [`MAX_PROOF_LEN`] whose circuit fits every proof in `proofs`, i.e. the length of
+/// the deepest proof.
+pub fn required_len_for<H: AlgebraicHasher<F>>(proofs: &[StorageProof<H>]) -> usize {
+    proofs
+        .iter()
+        .map(StorageProof::required_len)
+        .max()
+        .unwrap_or_default()
+}
+
+/// The position where the hash chain of a storage proof breaks, as reported by
+/// [`StorageProof::verify_chain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainBreak {
+    /// The index of the node whose hash doesn't match.
+    pub at_index: usize,
+    /// The hash expected for the node, either the root hash or the child hash in its parent.
+    pub expected: Digest,
+    /// The actual hash of the node.
+    pub got: Digest,
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "storage proof chain breaks at node {}: expected hash {:?}, got {:?}",
+            self.at_index, self.expected, self.got
+        )
+    }
+}
+
+/// A storage proof target, as named in a [`WitnessConflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageProofTarget {
+    /// An element of [`StorageProofTargets::root_hash`].
+    RootHash { element: usize },
+    /// An element of the child hash of a node, in [`StorageProofTargets::hashes`].
+    ChildHash { node: usize, element: usize },
+}
+
+impl fmt::Display for StorageProofTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RootHash { element } => write!(f, "root_hash element {}", element),
+            Self::ChildHash { node, element } => write!(f, "hashes[{}] element {}", node, element),
         }
     }
 }
 
+/// A witness value that the circuit would overwrite with a different value, which plonky2 only
+/// reports as a target being "set twice with different values".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WitnessConflict {
+    /// The conflicting target.
+    pub target: StorageProofTarget,
+    /// The value set in the witness.
+    pub set: F,
+    /// The value the circuit constrains the target to.
+    pub then: F,
+}
+
+impl fmt::Display for WitnessConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} set to {} then {}", self.target, self.set, self.then)
+    }
+}
+
+/// A storage proof, whose nodes are hashed in-circuit with `H`. This defaults to Poseidon, which
+/// is what the chain uses, but other hashers can be plugged in for experimentation.
 #[derive(Debug)]
 #[allow(dead_code)]
-pub struct StorageProof {
+pub struct StorageProof<H = PoseidonHash> {
     funding_amount: [F; FELTS_PER_AMOUNT],
+    /// The nonce the proof was built with, e.g. the funding nonce of the [`CircuitInputs`] it was
+    /// converted from. The circuit only sees it as the funding nonce of the leaf inputs, see
+    /// [`StorageProof::with_leaf_inputs`].
+    nonce: u32,
     pub proof: Vec<Vec<F>>,
     hashes: Vec<Vec<F>>,
-    pub root_hash: [u8; 32],
+    /// The byte offset of each child hash within its node, see
+    /// [`StorageProofTargets::child_offsets`]. The last node has no child, so its offset is zero.
+    child_offsets: Vec<usize>,
+    pub root_hash: Digest,
+    /// The root of the child trie, for proofs of child trie storage. The proof then holds the top
+    /// trie nodes, down to the node embedding this root, followed by the child trie nodes.
+    pub child_root: Option<Digest>,
+    /// The leaf inputs the proof is bound to, see [`StorageProof::with_leaf_inputs`].
+    leaf_inputs: Option<LeafInputs>,
+    /// The domain the leaf inputs are hashed in, see [`StorageProof::with_leaf_inputs_in_domain`].
+    domain: Option<F>,
+    /// The number of top trie nodes of a child trie proof.
+    top_len: usize,
+    /// The byte length of each node before it was converted to field elements.
+    node_byte_lengths: Vec<usize>,
+    /// The number of nodes the proof is padded to, i.e. the maximum proof length of the circuit
+    /// it is proven with.
+    capacity: usize,
+    _hasher: PhantomData<H>,
 }
 
+// The node byte lengths are only diagnostics, which are lost when building a proof from field
+// elements, so they don't take part in comparisons.
+impl<H> PartialEq for StorageProof<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.funding_amount == other.funding_amount
+            && self.nonce == other.nonce
+            && self.proof == other.proof
+            && self.hashes == other.hashes
+            && self.root_hash == other.root_hash
+            && self.child_root == other.child_root
+            && self.leaf_inputs == other.leaf_inputs
+            && self.domain == other.domain
+            && self.capacity == other.capacity
+    }
+}
+
+impl<H> Eq for StorageProof<H> {}
+
 impl StorageProof {
     /// The input is a storage proof as a tuple where each part is split at the index where the child node's
     /// hash, if any, appears within this proof node; and a root hash.
-    pub fn new(proof: &[(Vec<u8>, Vec<u8>)], root_hash: [u8; 32], funding_amount: u128) -> Self {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a proof node is longer than [`PROOF_NODE_MAX_SIZE_B`].
+    pub fn new(
+        proof: &[(Vec<u8>, Vec<u8>)],
+        root_hash: [u8; 32],
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
+        let root_hash = bytes_to_felts(&root_hash).try_into().unwrap();
+        Self::from_parts(proof, root_hash, funding_amount)
+    }
+
+    /// Same as [`StorageProof::new`], but takes the root hash as field elements. This is useful
+    /// when the root is the output of another circuit, as it avoids a round-trip through bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a proof node is longer than [`PROOF_NODE_MAX_SIZE_B`].
+    pub fn from_parts(
+        proof: &[(Vec<u8>, Vec<u8>)],
+        root_hash: Digest,
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
         // First construct the proof and the hash array
         let mut constructed_proof = Vec::with_capacity(proof.len());
         let mut hashes = Vec::with_capacity(proof.len());
-        for (left, right) in proof {
+        for (i, (left, right)) in proof.iter().enumerate() {
+            // Check the lengths before converting, as oversized nodes would otherwise be silently
+            // truncated when padding them to the circuit's node size.
+            if left.len() > PROOF_NODE_MAX_SIZE_B {
+                bail!(
+                    "left part of proof node {} is too long: {} bytes, max {}",
+                    i,
+                    left.len(),
+                    PROOF_NODE_MAX_SIZE_B
+                )
+            }
+            if left.len() + right.len() > PROOF_NODE_MAX_SIZE_B {
+                bail!(
+                    "combined left and right parts of proof node {} are too long: {} bytes, max {}",
+                    i,
+                    left.len() + right.len(),
+                    PROOF_NODE_MAX_SIZE_B
+                )
+            }
+
             let mut proof_node = Vec::with_capacity(PROOF_NODE_MAX_SIZE_B);
             proof_node.extend_from_slice(left);
             proof_node.extend_from_slice(right);
@@ -82,97 +522,994 @@ impl StorageProof {
             hashes.push(hash);
         }
 
-        StorageProof {
-            funding_amount: u128_to_felts(funding_amount),
-            proof: constructed_proof,
+        let mut storage_proof =
+            Self::from_nodes(constructed_proof, hashes, root_hash, funding_amount)?;
+        storage_proof.node_byte_lengths = proof
+            .iter()
+            .map(|(left, right)| left.len() + right.len())
+            .collect();
+        Ok(storage_proof)
+    }
+
+    /// Same as [`StorageProof::from_parts`], but takes the leaf inputs as field elements, e.g. when
+    /// they are the output of another circuit, in the layout of [`LeafInputs`]. The proof is bound
+    /// to the decoded leaf inputs, see [`StorageProof::with_leaf_inputs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `leaf_felts` aren't valid [`LeafInputs`] field elements, if a proof
+    /// node is too long, or if the last node doesn't contain the hash of the leaf inputs.
+    pub fn with_leaf_felts(
+        proof: &[(Vec<u8>, Vec<u8>)],
+        root_hash: Digest,
+        leaf_felts: Vec<F>,
+    ) -> anyhow::Result<Self> {
+        let leaf_inputs = LeafInputs::from_field_elements(&leaf_felts)?;
+        Self::from_parts(proof, root_hash, leaf_inputs.funding_amount)?
+            .with_leaf_inputs(leaf_inputs)
+    }
+
+    /// Same as [`StorageProof::from_parts`], but takes the funding amount from the free balance
+    /// of a SCALE encoded `AccountInfo`, i.e. a `System::Account` storage value as read from the
+    /// chain, and the nonce from its account nonce. See [`SystemAccountLeaf`] for the decoded
+    /// layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `account_info` isn't a valid `AccountInfo` encoding, or if a proof node
+    /// is too long.
+    pub fn from_account_info_scale(
+        proof: &[(Vec<u8>, Vec<u8>)],
+        root_hash: Digest,
+        account_info: &[u8],
+    ) -> anyhow::Result<Self> {
+        let account = SystemAccountLeaf::from_bytes(account_info)?;
+        let storage_proof = Self::from_parts(proof, root_hash, account.free)?;
+        Ok(storage_proof.with_nonce(account.nonce))
+    }
+
+    /// Creates a proof of child trie storage. `top_proof` proves the inclusion of `child_root`
+    /// under the top trie `root_hash`, and `child_proof` proves the leaf under `child_root`. Both
+    /// are verified as a single chain, so the last node of `top_proof` must be split right before
+    /// the child root it embeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `top_proof` is empty, if its last node doesn't embed `child_root`, or if
+    /// a proof node is too long.
+    pub fn with_child_trie(
+        top_proof: &[(Vec<u8>, Vec<u8>)],
+        root_hash: [u8; 32],
+        child_root: [u8; 32],
+        child_proof: &[(Vec<u8>, Vec<u8>)],
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
+        let Some((_, right)) = top_proof.last() else {
+            bail!("top trie proof must not be empty")
+        };
+        if !right.starts_with(&child_root) {
+            bail!("last top trie node does not embed the child root")
+        }
+
+        let nodes: Vec<_> = top_proof.iter().chain(child_proof).cloned().collect();
+        let mut proof = Self::new(&nodes, root_hash, funding_amount)?;
+        proof.child_root = Some(bytes_to_felts(&child_root).try_into().unwrap());
+        proof.top_len = top_proof.len();
+        Ok(proof)
+    }
+
+    /// Same as [`StorageProof::new`], but takes each node as a single, unsplit byte blob. The
+    /// split point of each node is found by hashing its child and locating that hash within the
+    /// node. The last node has no child, so its child hash is left empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node is longer than [`PROOF_NODE_MAX_SIZE_B`], or if it does not
+    /// contain the hash of the node that follows it.
+    pub fn from_raw_nodes(
+        nodes: &[Vec<u8>],
+        root_hash: [u8; 32],
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
+        let mut proof = Vec::with_capacity(nodes.len());
+        let mut hashes = Vec::with_capacity(nodes.len());
+        for (i, node) in nodes.iter().enumerate() {
+            if node.len() > PROOF_NODE_MAX_SIZE_B {
+                bail!(
+                    "proof node {} is too long: {} bytes, max {}",
+                    i,
+                    node.len(),
+                    PROOF_NODE_MAX_SIZE_B
+                )
+            }
+            let hash = match nodes.get(i + 1) {
+                Some(child) => {
+                    let mut child_f = bytes_to_felts(child);
+                    child_f.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
+                    let child_hash = felts_to_bytes(&PoseidonHash::hash_no_pad(&child_f).elements);
+
+                    let Some(split_index) = node
+                        .windows(child_hash.len())
+                        .position(|window| window == child_hash)
+                    else {
+                        bail!(
+                            "proof node {} does not contain the hash of node {}",
+                            i,
+                            i + 1
+                        )
+                    };
+                    bytes_to_felts(&node[split_index..])[..4].to_vec()
+                }
+                None => ZERO_DIGEST.to_vec(),
+            };
+
+            proof.push(bytes_to_felts(node));
+            hashes.push(hash);
+        }
+
+        let root_hash = bytes_to_felts(&root_hash).try_into().unwrap();
+        let mut storage_proof = Self::from_nodes(proof, hashes, root_hash, funding_amount)?;
+        storage_proof.node_byte_lengths = nodes.iter().map(Vec::len).collect();
+        Ok(storage_proof)
+    }
+
+    /// Same as [`StorageProof::from_raw_nodes`], but takes the nodes as a single SCALE encoded
+    /// `Vec<Vec<u8>>`, as handed out by SCALE tooling. The nodes must be ordered from the root to
+    /// the leaf.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `blob` is not a valid SCALE encoded list of nodes, or if a node does not
+    /// contain the hash of the node that follows it.
+    pub fn from_scale(
+        blob: &[u8],
+        root_hash: [u8; 32],
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
+        let (num_nodes, mut offset) = decode_compact(blob)?;
+        let mut nodes = Vec::new();
+        for i in 0..num_nodes {
+            let (len, prefix_len) = decode_compact(&blob[offset..])
+                .with_context(|| format!("failed to decode the length of proof node {}", i))?;
+            offset += prefix_len;
+            let Some(node) = blob[offset..].get(..len as usize) else {
+                bail!("SCALE encoded proof node {} is truncated", i)
+            };
+            nodes.push(node.to_vec());
+            offset += node.len();
+        }
+        if offset != blob.len() {
+            bail!(
+                "SCALE encoded proof has {} trailing bytes",
+                blob.len() - offset
+            )
+        }
+
+        Self::from_raw_nodes(&nodes, root_hash, funding_amount)
+    }
+
+    /// Creates a storage proof from nodes that are already encoded as field elements, along with
+    /// the hash of the child that each node points to. The last node has no child in the proof,
+    /// so its child hash is cleared. The original byte lengths of the nodes are unknown, so
+    /// [`StorageProof::node_byte_lengths`] reports 8 bytes per field element.
+    ///
+    /// Each child hash is located within its parent node, in the same way as the circuit reads
+    /// it: from the canonical bytes of the node's field elements. A node with a field element that
+    /// isn't canonical, which a random 8-byte chunk is with a probability of about 2^-32, can't
+    /// be proven.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there isn't exactly one child hash per proof node, if a node has more
+    /// than [`PROOF_NODE_MAX_SIZE_F`] field elements, or if a node other than the leaf doesn't
+    /// contain its child hash.
+    pub fn from_nodes(
+        proof: Vec<Vec<F>>,
+        mut hashes: Vec<Vec<F>>,
+        root_hash: Digest,
+        funding_amount: u128,
+    ) -> anyhow::Result<Self> {
+        if proof.len() != hashes.len() {
+            bail!(
+                "expected one hash per proof node, got {} nodes and {} hashes",
+                proof.len(),
+                hashes.len()
+            )
+        }
+        if let Some((i, node)) = proof
+            .iter()
+            .enumerate()
+            .find(|(_, node)| node.len() > PROOF_NODE_MAX_SIZE_F)
+        {
+            bail!(
+                "proof node {} is too long: {} field elements, max {}",
+                i,
+                node.len(),
+                PROOF_NODE_MAX_SIZE_F
+            )
+        }
+
+        if let Some(leaf_hash) = hashes.last_mut() {
+            *leaf_hash = ZERO_DIGEST.to_vec();
+        }
+
+        let mut child_offsets = proof
+            .iter()
+            .zip(&hashes)
+            .take(proof.len().saturating_sub(1))
+            .enumerate()
+            .map(|(i, (node, hash))| {
+                find_child_offset(node, hash)
+                    .with_context(|| format!("proof node {} does not contain its child hash", i))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        child_offsets.extend(proof.last().map(|_| 0));
+
+        Ok(StorageProof {
+            funding_amount: Amount::from(funding_amount).0,
+            nonce: 0,
+            node_byte_lengths: proof.iter().map(|node| node.len() * 8).collect(),
+            proof,
             hashes,
+            child_offsets,
             root_hash,
+            child_root: None,
+            leaf_inputs: None,
+            domain: None,
+            top_len: 0,
+            capacity: MAX_PROOF_LEN,
+            _hasher: PhantomData,
+        })
+    }
+}
+
+impl<H: AlgebraicHasher<F>> StorageProof<H> {
+    /// Converts this proof into one whose nodes are hashed with `H2` in-circuit. The witness data
+    /// is unchanged.
+    pub fn into_hasher<H2: AlgebraicHasher<F>>(self) -> StorageProof<H2> {
+        StorageProof {
+            funding_amount: self.funding_amount,
+            nonce: self.nonce,
+            proof: self.proof,
+            hashes: self.hashes,
+            child_offsets: self.child_offsets,
+            root_hash: self.root_hash,
+            child_root: self.child_root,
+            leaf_inputs: self.leaf_inputs,
+            domain: self.domain,
+            top_len: self.top_len,
+            node_byte_lengths: self.node_byte_lengths,
+            capacity: self.capacity,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Sets the child hash of the last node to `leaf_value`, to prove the inclusion of the value
+    /// with a circuit built with [`StorageProofTargets::with_leaf_value`]. The last node then
+    /// isn't a leaf, but the node that points to the value, which the circuit reads from the node
+    /// like any other child hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proof is empty, or if its last node doesn't contain `leaf_value`.
+    pub fn with_leaf_value(mut self, leaf_value: Digest) -> anyhow::Result<Self> {
+        let Some(last) = self.proof.last() else {
+            bail!("a Merkle path proof must not be empty")
+        };
+        let Some(offset) = find_child_offset(last, &leaf_value) else {
+            bail!("last proof node does not contain the leaf value")
+        };
+
+        let last = self.proof.len() - 1;
+        self.hashes[last] = leaf_value.to_vec();
+        self.child_offsets[last] = offset;
+        Ok(self)
+    }
+
+    /// Binds the proof to `leaf_inputs`, to prove it with a circuit built with
+    /// [`StorageProofTargets::with_leaf_inputs`]. The child hash of the last node is set to the
+    /// hash of the leaf inputs, as with [`StorageProof::with_leaf_value`], and the funding amount
+    /// and nonce to the ones of the leaf. An empty proof has no node to bind, so only the leaf
+    /// inputs are set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the last node doesn't contain the hash of the leaf inputs.
+    pub fn with_leaf_inputs(self, leaf_inputs: LeafInputs) -> anyhow::Result<Self> {
+        let leaf_hash = leaf_inputs.hash();
+        self.bind_leaf_inputs(leaf_inputs, leaf_hash)
+    }
+
+    /// Same as [`StorageProof::with_leaf_inputs`], for a circuit built with
+    /// [`StorageProofTargets::with_domain`]: the last node must contain the hash of the leaf
+    /// inputs in `domain`, see [`LeafInputs::hash_with_domain`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the last node doesn't contain the hash of the leaf inputs in `domain`.
+    pub fn with_leaf_inputs_in_domain(
+        self,
+        leaf_inputs: LeafInputs,
+        domain: F,
+    ) -> anyhow::Result<Self> {
+        let leaf_hash = leaf_inputs.hash_with_domain(domain);
+        let mut proof = self.bind_leaf_inputs(leaf_inputs, leaf_hash)?;
+        proof.domain = Some(domain);
+        Ok(proof)
+    }
+
+    fn bind_leaf_inputs(
+        mut self,
+        leaf_inputs: LeafInputs,
+        leaf_hash: Digest,
+    ) -> anyhow::Result<Self> {
+        if !self.proof.is_empty() {
+            self = self
+                .with_leaf_value(leaf_hash)
+                .context("last proof node does not contain the hash of the leaf inputs")?;
+        }
+        self.funding_amount = Amount::from(leaf_inputs.funding_amount).0;
+        self.nonce = leaf_inputs.funding_nonce;
+        self.leaf_inputs = Some(leaf_inputs);
+        Ok(self)
+    }
+
+    /// The first byte offset at which the last node of the proof holds `bytes`, as read by
+    /// [`read_last_node_bytes`]. Returns `None` if the proof is empty, or if its last node doesn't
+    /// hold `bytes`.
+    pub fn last_node_offset(&self, bytes: &[u8]) -> Option<usize> {
+        let node_bytes = canonical_node_bytes(self.proof.last()?);
+        (0..=node_bytes.len().checked_sub(bytes.len())?)
+            .find(|&offset| node_bytes[offset..offset + bytes.len()] == *bytes)
+    }
+
+    /// Sets the nonce the proof was built with, see [`StorageProof::nonce`].
+    pub fn with_nonce(mut self, nonce: u32) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// The nonce the proof was built with, e.g. the funding nonce of the [`CircuitInputs`] it was
+    /// converted from. The circuit only constrains it as one of the leaf inputs.
+    pub fn nonce(&self) -> u32 {
+        self.nonce
+    }
+
+    /// Pads the proof to `max` nodes instead of [`MAX_PROOF_LEN`], to prove it with a circuit
+    /// built with [`StorageProofTargets::with_max_len`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proof has more than `max` nodes.
+    pub fn with_capacity(mut self, max: usize) -> anyhow::Result<Self> {
+        if self.proof.len() > max {
+            bail!(
+                "proof has {} nodes, more than the capacity of {}",
+                self.proof.len(),
+                max
+            )
+        }
+        self.capacity = max;
+        Ok(self)
+    }
+
+    /// The number of nodes the proof is padded to when filling the circuit's targets.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The byte length of each node, before it was converted to field elements, e.g. to choose
+    /// [`PROOF_NODE_MAX_SIZE_B`] from the node sizes seen in practice.
+    pub fn node_byte_lengths(&self) -> Vec<usize> {
+        self.node_byte_lengths.clone()
+    }
+
+    /// Whether the proof is the one built from `inputs` with [`StorageProof::try_from`], i.e. its
+    /// funding amount is [`encode_leaf`] of `inputs`, its nonce is the funding nonce of `inputs`,
+    /// and its leaf inputs, root hash and nodes are the ones of `inputs`.
+    pub fn matches_inputs(&self, inputs: &CircuitInputs) -> bool {
+        let Ok(expected) = StorageProof::try_from(inputs) else {
+            return false;
+        };
+        self.funding_amount == expected.funding_amount
+            && self.nonce == expected.nonce
+            && self.leaf_inputs == expected.leaf_inputs
+            && self.root_hash == expected.root_hash
+            && self.proof == expected.proof
+            && self.hashes == expected.hashes
+    }
+
+    /// Checks the hash chain of the proof off-circuit, in the same way as the circuit does. The
+    /// nodes are walked bottom-up from the leaf, so the deepest node whose hash doesn't match the
+    /// one embedded in its parent is reported.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChainBreak`] describing the first mismatching node.
+    pub fn verify_chain(&self) -> Result<(), ChainBreak> {
+        for (i, node) in self.proof.iter().enumerate().rev() {
+            let expected = match i {
+                0 => self.root_hash,
+                _ => self.hashes[i - 1][..4].try_into().unwrap_or(ZERO_DIGEST),
+            };
+
+            let mut padded_node = node.clone();
+            padded_node.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
+            let got = H::hash_no_pad(&padded_node).elements;
+
+            if got != expected {
+                return Err(ChainBreak {
+                    at_index: i,
+                    expected,
+                    got,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The number of real, non-padding nodes in the proof.
+    pub fn real_node_count(&self) -> usize {
+        self.proof.len()
+    }
+
+    /// The smallest [`MAX_PROOF_LEN`] whose circuit fits this proof.
+    pub fn required_len(&self) -> usize {
+        self.real_node_count()
+    }
+
+    /// The number of empty padding nodes needed to fill the proof up to `max` nodes.
+    pub fn padding_count(&self, max: usize) -> usize {
+        max.saturating_sub(self.proof.len())
+    }
+
+    /// The public inputs of the storage proof circuit built with [`StorageProofTargets::new`], so
+    /// that they can be compared to those of a proof without proving.
+    pub fn expected_public_inputs(&self) -> Vec<F> {
+        self.expected_public_inputs_with_layout(PublicInputLayout::default())
+    }
+
+    /// Same as [`StorageProof::expected_public_inputs`], for a circuit built with
+    /// [`StorageProofTargets::with_layout`].
+    pub fn expected_public_inputs_with_layout(&self, layout: PublicInputLayout) -> Vec<F> {
+        layout.arrange(&self.funding_amount, &self.root_hash)
+    }
+
+    /// Returns the field elements that [`CircuitFragment::fill_targets`] assigns when the proof is
+    /// padded to `max` nodes, in the order they are assigned: the root hash, the proof length,
+    /// every node padded to [`PROOF_NODE_MAX_SIZE_F`] elements, the child hash of every node, the
+    /// child hash offset of every node, and the funding amount. Padding nodes, hashes and offsets
+    /// are all zeros. This is exactly the circuit's view of the proof, so it can be hashed to
+    /// commit to the proof off-circuit. The leaf inputs, which only the circuit built with
+    /// [`StorageProofTargets::with_leaf_inputs`] holds, are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proof has more than `max` nodes.
+    pub fn padded_felts(&self, max: usize) -> Vec<F> {
+        assert!(
+            self.proof.len() <= max,
+            "proof has {} nodes, more than the padded length {}",
+            self.proof.len(),
+            max
+        );
+
+        let mut felts = self.root_hash.to_vec();
+        felts.push(F::from_canonical_usize(self.proof.len()));
+        felts.extend(self.padded_nodes(max).into_iter().flatten());
+        felts.extend(self.padded_hashes(max).into_iter().flatten());
+        felts.extend(self.padded_child_offsets(max));
+        felts.extend(self.funding_amount);
+        felts
+    }
+
+    /// The nodes of the proof, each padded to [`PROOF_NODE_MAX_SIZE_F`] elements, followed by
+    /// empty nodes up to `max` nodes.
+    fn padded_nodes(&self, max: usize) -> Vec<Vec<F>> {
+        (0..max)
+            .map(|i| {
+                let mut node = self.proof.get(i).cloned().unwrap_or_default();
+                node.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
+                node
+            })
+            .collect()
+    }
+
+    /// The child hash of every node, followed by empty hashes up to `max` nodes.
+    fn padded_hashes(&self, max: usize) -> Vec<Digest> {
+        (0..max)
+            .map(|i| match self.hashes.get(i) {
+                Some(hash) => HashOut::from_partial(&hash[..4]).elements,
+                None => ZERO_DIGEST,
+            })
+            .collect()
+    }
+
+    /// The child hash offset of every node, followed by zero offsets up to `max` nodes.
+    fn padded_child_offsets(&self, max: usize) -> Vec<F> {
+        (0..max)
+            .map(|i| F::from_canonical_usize(self.child_offsets.get(i).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// Checks the witness of the proof against the values the circuit computes, without proving.
+    /// This names the conflicting target when proving would fail on an inconsistent hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`WitnessConflict`], checking the child hash embedded in each node first,
+    /// then walking the hash chain bottom-up.
+    pub fn check_witness(&self) -> Result<(), WitnessConflict> {
+        // Every node but the last embeds its child hash at its child offset.
+        let num_parents = self.proof.len().saturating_sub(1);
+        let parents = self.proof.iter().zip(&self.hashes).take(num_parents);
+        for (node, (node_felts, hash)) in parents.enumerate() {
+            let offset = self.child_offsets.get(node).copied().unwrap_or_default();
+            let embedded =
+                embedded_hash(&canonical_node_bytes(node_felts), offset).unwrap_or(ZERO_DIGEST);
+            if let Some(element) = (0..4).find(|&y| hash.get(y) != Some(&embedded[y])) {
+                return Err(WitnessConflict {
+                    target: StorageProofTarget::ChildHash { node, element },
+                    set: hash.get(element).copied().unwrap_or(F::ZERO),
+                    then: embedded[element],
+                });
+            }
+        }
+
+        self.verify_chain().map_err(|chain_break| {
+            let (expected, got) = (chain_break.expected, chain_break.got);
+            let element = (0..4).find(|&y| expected[y] != got[y]).unwrap_or_default();
+            let target = match chain_break.at_index {
+                0 => StorageProofTarget::RootHash { element },
+                i => StorageProofTarget::ChildHash {
+                    node: i - 1,
+                    element,
+                },
+            };
+            WitnessConflict {
+                target,
+                set: expected[element],
+                then: got[element],
+            }
+        })
+    }
+
+    /// Checks that the proof satisfies the constraints of the storage proof circuit, without the
+    /// cost of proving. The circuit is built and its witness generated, which fails on the first
+    /// constraint that doesn't hold.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the violated constraint. Hash chain violations are reported as
+    /// a [`WitnessConflict`] naming the target, other violations with plonky2's description.
+    pub fn check_constraints(&self) -> anyhow::Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let targets = StorageProofTargets::new(&mut builder);
+        Self::circuit(&targets, &mut builder);
+        let data = builder.build_prover::<C>();
+
+        let mut pw = PartialWitness::new();
+        self.fill_targets(&mut pw, targets)?;
+        match generate_partial_witness(pw, &data.prover_only, &data.common) {
+            Ok(_) => Ok(()),
+            Err(e) => match self.check_witness() {
+                Err(conflict) => Err(anyhow::Error::msg(conflict)),
+                Ok(()) => Err(e),
+            },
         }
     }
+
+    /// Same as [`CircuitFragment::fill_targets`], followed by [`StorageProof::check_witness`], for
+    /// debugging proofs that fail to generate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if filling the targets fails, or describing the first witness conflict.
+    pub fn fill_targets_checked(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: StorageProofTargets,
+    ) -> anyhow::Result<()> {
+        self.fill_targets(pw, targets)?;
+        self.check_witness().map_err(anyhow::Error::msg)
+    }
 }
 
-impl From<&CircuitInputs> for StorageProof {
-    fn from(inputs: &CircuitInputs) -> Self {
-        Self::new(
-            &inputs.private.storage_proof,
-            inputs.public.root_hash,
-            inputs.public.funding_amount,
+/// The bytes of `node` as the circuit sees them: the canonical bytes of each field element,
+/// padded with zeros to [`PROOF_NODE_MAX_SIZE_B`] bytes.
+fn canonical_node_bytes(node: &[F]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = node
+        .iter()
+        .flat_map(|felt| felt.to_canonical_u64().to_le_bytes())
+        .collect();
+    bytes.resize(PROOF_NODE_MAX_SIZE_B, 0);
+    bytes
+}
+
+/// The child hash that the circuit reads at byte `offset` of `node_bytes`, as returned by
+/// [`canonical_node_bytes`], packed like [`bytes_to_felts`]. Returns `None` if the hash wouldn't
+/// end within the node.
+fn embedded_hash(node_bytes: &[u8], offset: usize) -> Option<Digest> {
+    if offset > MAX_CHILD_OFFSET {
+        return None;
+    }
+    bytes_to_felts(&node_bytes[offset..offset + CHILD_HASH_BYTES])
+        .try_into()
+        .ok()
+}
+
+/// The first byte offset at which `node` embeds `child_hash`, as read by the circuit.
+fn find_child_offset(node: &[F], child_hash: &[F]) -> Option<usize> {
+    let node_bytes = canonical_node_bytes(node);
+    (0..=MAX_CHILD_OFFSET).find(|&offset| {
+        embedded_hash(&node_bytes, offset).is_some_and(|hash| hash[..] == *child_hash)
+    })
+}
+
+/// Sets `targets` to `values`, naming the field `name` in the error if they can't be set, since
+/// plonky2 only reports the index of the offending target.
+fn set_named(
+    pw: &mut PartialWitness<F>,
+    name: &str,
+    targets: &[Target],
+    values: &[F],
+) -> anyhow::Result<()> {
+    if targets.len() != values.len() {
+        bail!(
+            "failed to set {}: expected {} values, got {}",
+            name,
+            targets.len(),
+            values.len()
         )
     }
+    pw.set_target_arr(targets, values)
+        .with_context(|| format!("failed to set {}", name))
 }
 
-impl CircuitFragment for StorageProof {
-    type Targets = StorageProofTargets;
+/// Encodes the leaf inputs of `inputs`, including their additional funding accounts if any, in the
+/// layout expected by [`StorageProof::with_leaf_felts`]. This is the only place where circuit
+/// inputs are mapped to the leaf, see [`LeafInputs`].
+pub fn encode_leaf(inputs: &CircuitInputs) -> Vec<F> {
+    LeafInputs::from(inputs).to_field_elements()
+}
+
+impl TryFrom<&CircuitInputs> for StorageProof {
+    type Error = anyhow::Error;
 
-    #[allow(unused_variables)]
-    fn circuit(
-        &Self::Targets {
+    fn try_from(inputs: &CircuitInputs) -> Result<Self, Self::Error> {
+        let root_hash = bytes_to_felts(&inputs.public.root_hash).try_into().unwrap();
+        Self::with_leaf_felts(
+            &inputs.private.storage_proof,
             root_hash,
-            proof_len,
-            ref proof_data,
-            ref hashes,
-            ref funding_amount,
-        }: &Self::Targets,
-        builder: &mut CircuitBuilder<F, D>,
-    ) {
-        // Setup constraints.
-        // The first node should be the root node so we initialize `prev_hash` to the provided `root_hash`.
-        let mut prev_hash = root_hash;
-        let n_log = (usize::BITS - (MAX_PROOF_LEN - 1).leading_zeros()) as usize;
-        for i in 0..MAX_PROOF_LEN {
-            let node = &proof_data[i];
+            encode_leaf(inputs),
+        )
+    }
+}
 
-            let is_proof_node = is_const_less_than(builder, i, proof_len, n_log);
-            let computed_hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(node.clone());
+impl StorageProof {
+    /// Same as [`StorageProof::try_from`], but the leaf inputs are hashed in the domain of
+    /// `chain_id`, for a circuit built with [`StorageProofTargets::with_chain_id`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a proof node is too long, or if the last node doesn't contain the hash
+    /// of the leaf inputs of `inputs` in the domain of `chain_id`.
+    pub fn on_chain(inputs: &CircuitInputs, chain_id: u32) -> anyhow::Result<Self> {
+        let root_hash = bytes_to_felts(&inputs.public.root_hash).try_into().unwrap();
+        let leaf_inputs = LeafInputs::from(inputs);
+        Self::from_parts(
+            &inputs.private.storage_proof,
+            root_hash,
+            leaf_inputs.funding_amount,
+        )?
+        .with_leaf_inputs_in_domain(leaf_inputs, F::from_canonical_u32(chain_id))
+    }
+}
 
-            for y in 0..4 {
-                let diff = builder.sub(computed_hash.elements[y], prev_hash.elements[y]);
-                let result = builder.mul(diff, is_proof_node.target);
-                let zero = builder.zero();
-                builder.connect(result, zero);
-            }
+impl<H: AlgebraicHasher<F>> CircuitFragment for StorageProof<H> {
+    type Targets = StorageProofTargets;
 
-            // Update `prev_hash` to the hash of the child that's stored within this node.
-            prev_hash = hashes[i];
-        }
+    fn circuit(targets: &Self::Targets, builder: &mut CircuitBuilder<F, D>) {
+        storage_proof_circuit::<F, D, H>(targets, builder)
     }
 
     fn fill_targets(
         &self,
-        pw: &mut plonky2::iop::witness::PartialWitness<F>,
+        pw: &mut PartialWitness<F>,
         targets: Self::Targets,
     ) -> anyhow::Result<()> {
-        const EMPTY_PROOF_NODE: [F; PROOF_NODE_MAX_SIZE_F] = [F::ZERO; PROOF_NODE_MAX_SIZE_F];
+        // Padding with empty hashes is only valid past the end of the proof.
+        debug_assert_eq!(self.proof.len(), self.hashes.len());
+        if targets.proof_data.len() != self.capacity {
+            bail!(
+                "proof is padded to {} nodes, but the circuit holds {}",
+                self.capacity,
+                targets.proof_data.len()
+            )
+        }
 
-        pw.set_hash_target(targets.root_hash, slice_to_hashout(&self.root_hash))?;
-        pw.set_target(targets.proof_len, F::from_canonical_usize(self.proof.len()))?;
+        set_named(
+            pw,
+            "root_hash",
+            &targets.root_hash.elements,
+            &self.root_hash,
+        )?;
+        set_named(
+            pw,
+            "proof_len",
+            &[targets.proof_len],
+            &[F::from_canonical_usize(self.proof.len())],
+        )?;
 
-        for i in 0..MAX_PROOF_LEN {
-            match self.proof.get(i) {
-                Some(node) => {
-                    let mut padded_proof_node = node.clone();
-                    padded_proof_node.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
-                    pw.set_target_arr(&targets.proof_data[i], &padded_proof_node)?;
-                }
-                None => pw.set_target_arr(&targets.proof_data[i], &EMPTY_PROOF_NODE)?,
+        let nodes = targets
+            .proof_data
+            .iter()
+            .zip(self.padded_nodes(self.capacity));
+        for (i, (node_targets, node)) in nodes.enumerate() {
+            set_named(pw, &format!("proof_data[{}]", i), node_targets, &node)?;
+        }
+        let hashes = targets.hashes.iter().zip(self.padded_hashes(self.capacity));
+        for (i, (hash_target, hash)) in hashes.enumerate() {
+            set_named(pw, &format!("hashes[{}]", i), &hash_target.elements, &hash)?;
+        }
+        set_named(
+            pw,
+            "child_offsets",
+            &targets.child_offsets,
+            &self.padded_child_offsets(self.capacity),
+        )?;
+        if let Some(leaf_targets) = &targets.leaf_inputs {
+            let Some(leaf_inputs) = &self.leaf_inputs else {
+                bail!("the circuit binds the leaf inputs, but the proof has none")
+            };
+            leaf_inputs.fill_targets(pw, leaf_targets)?;
+            if let Some(domain_target) = leaf_targets.domain {
+                let Some(domain) = self.domain else {
+                    bail!("the circuit hashes the leaf inputs in a domain, but the proof has none")
+                };
+                set_named(pw, "leaf_inputs.domain", &[domain_target], &[domain])?;
+            }
+        }
+        if let Some(child_trie) = targets.child_trie {
+            let Some(child_root) = self.child_root else {
+                bail!("the circuit proves child trie storage, but the proof has no child root")
+            };
+            set_named(
+                pw,
+                "child_trie.root",
+                &child_trie.root.elements,
+                &child_root,
+            )?;
+            set_named(
+                pw,
+                "child_trie.top_len",
+                &[child_trie.top_len],
+                &[F::from_canonical_usize(self.top_len)],
+            )?;
+        }
+        set_named(
+            pw,
+            "funding_amount",
+            &targets.funding_amount,
+            &self.funding_amount,
+        )
+    }
+}
+
+/// The constraints of [`StorageProof`], for any field rather than only the Goldilocks field of
+/// [`CircuitFragment`], e.g. to reuse the storage proof in a circuit of another plonky2 config.
+/// At the default field, this builds the same circuit as [`StorageProof::circuit`].
+pub fn storage_proof_circuit<F, const D: usize, H>(
+    &StorageProofTargets {
+        root_hash,
+        proof_len,
+        ref proof_data,
+        ref hashes,
+        ref child_offsets,
+        funding_amount: _,
+        leaf_value,
+        ref leaf_inputs,
+        child_trie,
+    }: &StorageProofTargets,
+    builder: &mut CircuitBuilder<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    H: AlgebraicHasher<F>,
+{
+    // Setup constraints.
+    // The first node should be the root node so we initialize `prev_hash` to the provided `root_hash`.
+    let mut prev_hash = root_hash;
+    let max_len = proof_data.len();
+    // A proof bound to its leaf inputs ends with their hash, as a Merkle path proof ends with its
+    // value.
+    let leaf_value = match leaf_inputs {
+        Some(leaf_inputs) => Some(leaf_inputs.hash(builder)),
+        None => leaf_value,
+    };
+    // The constants are created once, outside of the loops over the nodes.
+    let zero = builder.zero();
+    let one = builder.one();
+    let mut non_empty_nodes = zero;
+    let mut child_root_links = zero;
+    // Enough bits to hold any proof length up to and including `max_len`.
+    let n_log = (usize::BITS - max_len.leading_zeros()) as usize;
+    for i in 0..max_len {
+        let node = &proof_data[i];
+
+        let is_proof_node = is_const_less_than(builder, i, proof_len, n_log);
+
+        // A real node is never empty, so its felt sum must be non-zero. Padding nodes are
+        // replaced by one so that the inverse exists.
+        let node_sum = builder.add_many(node);
+        let masked_sum = builder.select(is_proof_node, node_sum, one);
+        builder.inverse(masked_sum);
+
+        let is_empty = builder.is_equal(node_sum, zero);
+        let is_non_empty = builder.not(is_empty);
+        non_empty_nodes = builder.add(non_empty_nodes, is_non_empty.target);
+
+        let computed_hash = builder.hash_n_to_hash_no_pad::<H>(node.clone());
+
+        for y in 0..4 {
+            let diff = builder.sub(computed_hash.elements[y], prev_hash.elements[y]);
+            let result = builder.mul(diff, is_proof_node.target);
+            builder.connect(result, zero);
+        }
+
+        // Every real node but the last points to its child, whose hash must be the one embedded
+        // in the node at its child offset. Otherwise the child hash would be a free witness. The
+        // last node of a Merkle path proof points to the leaf value, which is bound the same way.
+        let has_child = is_const_less_than(builder, i + 1, proof_len, n_log);
+        let is_bound = match leaf_value {
+            Some(_) => is_proof_node,
+            None => has_child,
+        };
+        let embedded_hash = read_child_hash(builder, node, child_offsets[i]);
+        for y in 0..4 {
+            let diff = builder.sub(embedded_hash.elements[y], hashes[i].elements[y]);
+            let result = builder.mul(diff, is_bound.target);
+            builder.connect(result, zero);
+        }
+
+        // The last real node of a Merkle path proof embeds the leaf value. As no node above it
+        // embeds the value too, `proof_len` can't stop short of the node holding it.
+        let next_index = builder.constant(F::from_canonical_usize(i + 1));
+        let is_leaf = builder.is_equal(proof_len, next_index);
+        if let Some(value) = leaf_value {
+            for y in 0..4 {
+                let diff = builder.sub(hashes[i].elements[y], value.elements[y]);
+                let result = builder.mul(diff, is_leaf.target);
+                builder.connect(result, zero);
             }
         }
 
-        let empty_hash = ZERO_DIGEST.to_vec();
-        for i in 0..MAX_PROOF_LEN {
-            let hash = self.hashes.get(i).unwrap_or(&empty_hash);
-            pw.set_hash_target(targets.hashes[i], HashOut::from_partial(&hash[..4]))?;
+        // The child root of a child trie proof is the child hash of the last top trie node, which
+        // must be followed by the child trie nodes.
+        if let Some(child_trie) = child_trie {
+            let is_top_last = builder.is_equal(child_trie.top_len, next_index);
+            let is_child_root = builder.and(is_top_last, has_child);
+            for y in 0..4 {
+                let diff = builder.sub(hashes[i].elements[y], child_trie.root.elements[y]);
+                let result = builder.mul(diff, is_child_root.target);
+                builder.connect(result, zero);
+            }
+            child_root_links = builder.add(child_root_links, is_child_root.target);
         }
-        // TODO: just a placeholder until we complete leaf hash
-        pw.set_target(targets.funding_amount[0], F::ZERO)?;
-        pw.set_target(targets.funding_amount[1], F::ZERO)?;
-        Ok(())
+
+        // Update `prev_hash` to the hash of the child that's stored within this node.
+        prev_hash = hashes[i];
     }
+
+    // Exactly one top trie node embeds the child root.
+    if child_trie.is_some() {
+        builder.connect(child_root_links, one);
+    }
+
+    // The proof length must match the number of non-empty nodes, so padding nodes can't hold
+    // any data.
+    builder.connect(non_empty_nodes, proof_len);
+}
+
+/// Reads the [`CHILD_HASH_BYTES`] bytes at byte `offset` of `node` in a circuit, packed into field
+/// elements like [`bytes_to_felts`]. The bytes are the canonical bytes of the node's field
+/// elements, so the result is the hash that [`find_child_offset`] locates off-circuit. The offset
+/// is constrained to at most [`MAX_CHILD_OFFSET`], so that the hash ends within the node.
+fn read_child_hash<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    node: &[Target],
+    offset: Target,
+) -> HashOutTarget {
+    let hash_bytes = read_node_bytes(builder, node, offset, CHILD_HASH_BYTES);
+
+    let zero = builder.zero();
+    let elements = core::array::from_fn(|y| {
+        hash_bytes[8 * y..8 * (y + 1)]
+            .iter()
+            .rev()
+            .fold(zero, |acc, &byte| {
+                builder.mul_const_add(F::from_canonical_u16(256), acc, byte)
+            })
+    });
+    HashOutTarget { elements }
+}
+
+/// Reads the `len` bytes at byte `offset` of `node` in a circuit, one target per byte. The bytes
+/// are the canonical bytes of the node's field elements, as returned by [`canonical_node_bytes`].
+/// The offset is constrained so that the bytes end within [`PROOF_NODE_MAX_SIZE_B`] bytes.
+fn read_node_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    node: &[Target],
+    offset: Target,
+    len: usize,
+) -> Vec<Target> {
+    let max_offset = PROOF_NODE_MAX_SIZE_B - len;
+    let offset_bits = ((usize::BITS - max_offset.leading_zeros()) as usize).max(3);
+    let (byte_shift, felt_index) = builder.split_low_high(offset, 3, offset_bits);
+    let max_offset_target = builder.constant(F::from_canonical_usize(max_offset));
+    let headroom = builder.sub(max_offset_target, offset);
+    builder.range_check(headroom, offset_bits);
+
+    // Select the field elements the bytes overlap, starting at `felt_index`. The bytes span one
+    // more element than their size unless they are aligned, which is zero past the end of the
+    // node.
+    let zero = builder.zero();
+    let mut window = vec![zero; len.div_ceil(8) + 1];
+    for start in 0..=max_offset / 8 {
+        let start_target = builder.constant(F::from_canonical_usize(start));
+        let is_start = builder.is_equal(felt_index, start_target);
+        for (j, felt) in window.iter_mut().enumerate() {
+            let node_felt = node.get(start + j).copied().unwrap_or(zero);
+            *felt = builder.mul_add(is_start.target, node_felt, *felt);
+        }
+    }
+
+    // Shift the bytes of the window by `byte_shift`.
+    let window_bytes: Vec<Target> = window
+        .iter()
+        .flat_map(|&felt| canonical_le_bytes(builder, felt))
+        .collect();
+    let mut bytes = vec![zero; len];
+    for shift in 0..8 {
+        let shift_target = builder.constant(F::from_canonical_usize(shift));
+        let is_shift = builder.is_equal(byte_shift, shift_target);
+        for (k, byte) in bytes.iter_mut().enumerate() {
+            *byte = builder.mul_add(is_shift.target, window_bytes[shift + k], *byte);
+        }
+    }
+    bytes
 }
 
-fn slice_to_hashout(slice: &[u8]) -> HashOut<F> {
-    let elements = bytes_to_felts(slice);
-    HashOut {
-        elements: elements.try_into().unwrap(),
+/// Reads `len` bytes of the last node of a storage proof in a circuit, i.e. of the node at index
+/// `proof_len - 1`, starting at the byte `offset`. Each byte is its own target. The prover sets
+/// `offset` to [`StorageProof::last_node_offset`] of the bytes. Only the offset is a witness:
+/// where the bytes sit within the node, e.g. in the partial key of a leaf node rather than in its
+/// value, is up to the caller to constrain.
+///
+/// # Panics
+///
+/// Panics if `len` is greater than [`PROOF_NODE_MAX_SIZE_B`].
+pub fn read_last_node_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    targets: &StorageProofTargets,
+    offset: Target,
+    len: usize,
+) -> Vec<Target> {
+    assert!(
+        len <= PROOF_NODE_MAX_SIZE_B,
+        "can't read {} bytes from a node of at most {} bytes",
+        len,
+        PROOF_NODE_MAX_SIZE_B
+    );
+
+    let zero = builder.zero();
+    let mut last_node = vec![zero; PROOF_NODE_MAX_SIZE_F];
+    for (i, node) in targets.proof_data.iter().enumerate() {
+        let index = builder.constant(F::from_canonical_usize(i + 1));
+        let is_last = builder.is_equal(targets.proof_len, index);
+        for (felt, &node_felt) in last_node.iter_mut().zip(node) {
+            *felt = builder.mul_add(is_last.target, node_felt, *felt);
+        }
     }
+    read_node_bytes(builder, &last_node, offset, len)
 }
@@ -3,56 +3,114 @@ use alloc::vec::Vec;
 #[cfg(feature = "std")]
 use std::vec::Vec;
 
+use anyhow::{anyhow, bail};
+use blake2::{Blake2b512, Digest as _};
+
 use crate::codec::{ByteCodec, FieldElementCodec};
 use plonky2::{
-    hash::hash_types::HashOutTarget,
+    hash::hash_types::{HashOut, HashOutTarget},
     iop::witness::{PartialWitness, WitnessWrite},
     plonk::circuit_builder::CircuitBuilder,
 };
 use zk_circuits_common::circuit::{CircuitFragment, D, F};
 use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, Digest};
 
-#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
-pub struct SubstrateAccount(Digest);
+/// A 32-byte Substrate account id. Each 8 bytes are represented by a field element in circuit.
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+pub struct SubstrateAccount([u8; 32]);
 
 impl SubstrateAccount {
+    /// The number of field elements returned by [`FieldElementCodec::to_field_elements`].
+    pub const NUM_FELTS: usize = 4;
+
     pub fn new(address: &[u8]) -> anyhow::Result<Self> {
         Self::from_bytes(address)
     }
 }
 
+impl From<[u8; 32]> for SubstrateAccount {
+    fn from(address: [u8; 32]) -> Self {
+        Self(address)
+    }
+}
+
+impl AsRef<[u8]> for SubstrateAccount {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl ByteCodec for SubstrateAccount {
     fn to_bytes(&self) -> Vec<u8> {
-        felts_to_bytes(&self.0)
+        self.0.to_vec()
     }
 
     fn from_bytes(slice: &[u8]) -> anyhow::Result<Self> {
-        let address = bytes_to_felts(slice).try_into().map_err(|_| {
+        let address: Digest = bytes_to_felts(slice).try_into().map_err(|_| {
             anyhow::anyhow!("failed to deserialize bytes into exit account address")
         })?;
-        Ok(SubstrateAccount(address))
+        Self::from_field_elements(&address)
     }
 }
 
 impl FieldElementCodec for SubstrateAccount {
     fn to_field_elements(&self) -> Vec<F> {
-        self.0.to_vec()
+        bytes_to_felts(&self.0)
     }
 
     fn from_field_elements(elements: &[F]) -> anyhow::Result<Self> {
-        if elements.len() != 4 {
+        if elements.len() != Self::NUM_FELTS {
             return Err(anyhow::anyhow!(
-                "Expected 4 field elements for SubstrateAccount, got: {}",
+                "Expected {} field elements for SubstrateAccount, got: {}",
+                Self::NUM_FELTS,
                 elements.len()
             ));
         }
-        let account_id: [F; 4] = elements
+        let address: [u8; 32] = felts_to_bytes(elements)
             .try_into()
-            .map_err(|_| anyhow::anyhow!("Failed to convert slice to [GoldilocksField; 4]"))?;
-        Ok(Self(account_id))
+            .map_err(|_| anyhow::anyhow!("Failed to convert field elements to [u8; 32]"))?;
+        Ok(Self(address))
     }
 }
 
+/// The prefix hashed together with an SS58 payload to compute its checksum.
+const SS58_CHECKSUM_PREFIX: &[u8] = b"SS58PRE";
+/// The number of checksum bytes at the end of an SS58 address.
+const SS58_CHECKSUM_LEN: usize = 2;
+
+/// Decodes an SS58 address into its 32-byte account id. Both the one and two byte network prefix
+/// formats are accepted, and the checksum is verified.
+pub fn decode_ss58(address: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|e| anyhow!("invalid base58 in SS58 address: {}", e))?;
+
+    let prefix_len = match bytes.first() {
+        Some(0..=63) => 1,
+        Some(64..=127) => 2,
+        _ => bail!("invalid SS58 address prefix"),
+    };
+    if bytes.len() != prefix_len + 32 + SS58_CHECKSUM_LEN {
+        bail!(
+            "expected {} bytes for SS58 address, got: {}",
+            prefix_len + 32 + SS58_CHECKSUM_LEN,
+            bytes.len()
+        )
+    }
+
+    let (payload, checksum) = bytes.split_at(bytes.len() - SS58_CHECKSUM_LEN);
+    let mut hasher = Blake2b512::new();
+    hasher.update(SS58_CHECKSUM_PREFIX);
+    hasher.update(payload);
+    if hasher.finalize()[..SS58_CHECKSUM_LEN] != *checksum {
+        bail!("invalid SS58 address checksum")
+    }
+
+    let mut account_id = [0u8; 32];
+    account_id.copy_from_slice(&payload[prefix_len..]);
+    Ok(account_id)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ExitAccountTargets {
     pub address: HashOutTarget,
@@ -77,6 +135,9 @@ impl CircuitFragment for SubstrateAccount {
         pw: &mut PartialWitness<F>,
         targets: Self::Targets,
     ) -> anyhow::Result<()> {
-        pw.set_hash_target(targets.address, self.0.into())
+        pw.set_hash_target(
+            targets.address,
+            HashOut::from_partial(&self.to_field_elements()),
+        )
     }
 }
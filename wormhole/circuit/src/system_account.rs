@@ -0,0 +1,194 @@
+//! The value of a `System::Account` storage leaf.
+//!
+//! Substrate stores an account's `AccountInfo` SCALE encoded, which for the default
+//! `AccountData` is a fixed width little-endian layout:
+//!
+//! ```text
+//! nonce: u32 | consumers: u32 | providers: u32 | sufficients: u32 |
+//! free: u128 | reserved: u128 | frozen: u128 | flags: u128
+//! ```
+//!
+//! The field element representation packs each 8 bytes of this encoding into one field element,
+//! exactly like any other leaf value, so the leaf hash matches the hash of the raw storage value.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use plonky2::{
+    field::types::Field,
+    hash::poseidon::PoseidonHash,
+    iop::target::Target,
+    plonk::{circuit_builder::CircuitBuilder, config::Hasher},
+};
+
+use crate::codec::{ByteCodec, FieldElementCodec};
+use crate::storage_proof::{read_last_node_bytes, StorageProofTargets};
+use zk_circuits_common::blake2::blake2_128;
+use zk_circuits_common::circuit::{D, F};
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, Digest};
+
+/// The size of a SCALE encoded `AccountInfo`.
+pub const SYSTEM_ACCOUNT_LEAF_NUM_BYTES: usize = 4 * 4 + 4 * 16;
+/// The number of field elements representing a SCALE encoded `AccountInfo`.
+pub const SYSTEM_ACCOUNT_LEAF_NUM_FELTS: usize = SYSTEM_ACCOUNT_LEAF_NUM_BYTES / 8;
+
+/// The storage key prefix of `System::Account`, i.e. `Twox128("System") ++ Twox128("Account")`.
+/// It doesn't depend on the account, so it is a constant rather than hashed in-circuit.
+pub const SYSTEM_ACCOUNT_KEY_PREFIX: [u8; 32] = [
+    0x26, 0xaa, 0x39, 0x4e, 0xea, 0x56, 0x30, 0xe0, 0x7c, 0x48, 0xae, 0x0c, 0x95, 0x58, 0xce, 0xf7,
+    0xb9, 0x9d, 0x88, 0x0e, 0xc6, 0x81, 0x79, 0x9c, 0x0c, 0xf3, 0x0e, 0x88, 0x86, 0x37, 0x1d, 0xa9,
+];
+/// The size of a `System::Account` storage key: the prefix, followed by the `Blake2_128Concat`
+/// hash of the account id.
+pub const SYSTEM_ACCOUNT_KEY_NUM_BYTES: usize = 32 + 16 + 32;
+
+/// The number of trailing bytes of a `System::Account` key that [`bind_system_account_key`] finds
+/// in the last node of a storage proof: the last 8 bytes of the `Blake2_128` hash, followed by the
+/// account id. The nodes above a leaf consume the leading nibbles of its key, i.e. the prefix
+/// shared by all accounts and the first nibbles of the hash, so the partial key of the leaf only
+/// holds a suffix of the key. This suffix is held by any leaf less than 16 nibbles below the
+/// prefix, which takes a trie of billions of accounts to exceed.
+pub const SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES: usize = 8 + 32;
+
+/// Reconstructs the `System::Account` storage key of `account` in a circuit, where `account` is
+/// the 32 bytes of an account id.
+///
+/// On its own, the key proves nothing about which account a storage proof belongs to, see
+/// [`bind_system_account_key`] to bind it to one.
+///
+/// # Returns
+/// - `Vec<Target>`: The [`SYSTEM_ACCOUNT_KEY_NUM_BYTES`] bytes of the storage key.
+pub fn system_account_key(builder: &mut CircuitBuilder<F, D>, account: &[Target]) -> Vec<Target> {
+    assert_eq!(account.len(), 32, "account id must be 32 bytes");
+
+    let mut key = Vec::with_capacity(SYSTEM_ACCOUNT_KEY_NUM_BYTES);
+    for byte in SYSTEM_ACCOUNT_KEY_PREFIX {
+        key.push(builder.constant(F::from_canonical_u8(byte)));
+    }
+    key.extend(blake2_128(builder, account));
+    key.extend_from_slice(account);
+    key
+}
+
+/// Reconstructs the `System::Account` storage key of `account` like [`system_account_key`], and
+/// binds it to the storage proof of `storage_proof`: the last
+/// [`SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES`] bytes of the key must be the bytes of the last proof
+/// node at the byte `offset`, i.e. the end of the partial key of the proven leaf. The prover sets
+/// `offset` to [`crate::storage_proof::StorageProof::last_node_offset`] of those bytes.
+///
+/// The bytes aren't otherwise located within the node, but as they include a hash of the account
+/// id, no node of another account holds them.
+///
+/// # Returns
+/// - `Vec<Target>`: The [`SYSTEM_ACCOUNT_KEY_NUM_BYTES`] bytes of the storage key.
+pub fn bind_system_account_key(
+    builder: &mut CircuitBuilder<F, D>,
+    storage_proof: &StorageProofTargets,
+    account: &[Target],
+    offset: Target,
+) -> Vec<Target> {
+    let key = system_account_key(builder, account);
+    let node_bytes = read_last_node_bytes(
+        builder,
+        storage_proof,
+        offset,
+        SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES,
+    );
+    let suffix = &key[SYSTEM_ACCOUNT_KEY_NUM_BYTES - SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES..];
+    for (&key_byte, &node_byte) in suffix.iter().zip(&node_bytes) {
+        builder.connect(key_byte, node_byte);
+    }
+    key
+}
+
+/// A `frame_system::AccountInfo` with the default `pallet_balances::AccountData`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SystemAccountLeaf {
+    pub nonce: u32,
+    pub consumers: u32,
+    pub providers: u32,
+    pub sufficients: u32,
+    pub free: u128,
+    pub reserved: u128,
+    pub frozen: u128,
+    pub flags: u128,
+}
+
+impl SystemAccountLeaf {
+    /// Computes the leaf hash, i.e. the hash of the SCALE encoded value as field elements.
+    pub fn hash(&self) -> Digest {
+        self.hash_with_padding(false)
+    }
+
+    /// Computes the leaf hash like [`SystemAccountLeaf::hash`], optionally with the standard
+    /// `10*1` sponge padding to a multiple of the Poseidon width. The unpadded hash is specific to
+    /// plonky2, while the padded one can be reproduced by other Poseidon implementations, e.g. an
+    /// on-chain verifier.
+    pub fn hash_with_padding(&self, padded: bool) -> Digest {
+        let elements = self.to_field_elements();
+        if padded {
+            PoseidonHash::hash_pad(&elements).elements
+        } else {
+            PoseidonHash::hash_no_pad(&elements).elements
+        }
+    }
+}
+
+impl ByteCodec for SystemAccountLeaf {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SYSTEM_ACCOUNT_LEAF_NUM_BYTES);
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.extend_from_slice(&self.consumers.to_le_bytes());
+        bytes.extend_from_slice(&self.providers.to_le_bytes());
+        bytes.extend_from_slice(&self.sufficients.to_le_bytes());
+        bytes.extend_from_slice(&self.free.to_le_bytes());
+        bytes.extend_from_slice(&self.reserved.to_le_bytes());
+        bytes.extend_from_slice(&self.frozen.to_le_bytes());
+        bytes.extend_from_slice(&self.flags.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(slice: &[u8]) -> anyhow::Result<Self> {
+        if slice.len() != SYSTEM_ACCOUNT_LEAF_NUM_BYTES {
+            return Err(anyhow::anyhow!(
+                "Expected {} bytes for SystemAccountLeaf, got: {}",
+                SYSTEM_ACCOUNT_LEAF_NUM_BYTES,
+                slice.len()
+            ));
+        }
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(slice[offset..offset + 4].try_into().unwrap());
+        let u128_at =
+            |offset: usize| u128::from_le_bytes(slice[offset..offset + 16].try_into().unwrap());
+
+        Ok(Self {
+            nonce: u32_at(0),
+            consumers: u32_at(4),
+            providers: u32_at(8),
+            sufficients: u32_at(12),
+            free: u128_at(16),
+            reserved: u128_at(32),
+            frozen: u128_at(48),
+            flags: u128_at(64),
+        })
+    }
+}
+
+impl FieldElementCodec for SystemAccountLeaf {
+    fn to_field_elements(&self) -> Vec<F> {
+        bytes_to_felts(&self.to_bytes())
+    }
+
+    fn from_field_elements(elements: &[F]) -> anyhow::Result<Self> {
+        if elements.len() != SYSTEM_ACCOUNT_LEAF_NUM_FELTS {
+            return Err(anyhow::anyhow!(
+                "Expected {} field elements for SystemAccountLeaf, got: {}",
+                SYSTEM_ACCOUNT_LEAF_NUM_FELTS,
+                elements.len()
+            ));
+        }
+        Self::from_bytes(&felts_to_bytes(elements))
+    }
+}
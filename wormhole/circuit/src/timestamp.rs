@@ -0,0 +1,186 @@
+//! A range check of a block timestamp against public bounds, e.g. for time-bounded claims.
+//!
+//! The timestamp either comes from a caller's target that another fragment constrains, see
+//! [`TimestampRangeTargets::new`], or is read from the `Timestamp::Now` leaf node that ends a
+//! storage proof, see [`TimestampRangeTargets::from_storage_proof`]. With a caller's target, the
+//! range check proves nothing about where the timestamp comes from.
+//!
+//! `Timestamp::Now` holds the milliseconds since the Unix epoch as a SCALE encoded `u64`, i.e. 8
+//! little-endian bytes, which a leaf node stores inline after their compact length prefix.
+use anyhow::bail;
+use plonky2::{
+    field::types::Field,
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{circuit_builder::CircuitBuilder, config::AlgebraicHasher},
+};
+
+use crate::storage_proof::{read_last_node_bytes, StorageProof, StorageProofTargets};
+use zk_circuits_common::circuit::{CircuitFragment, D, F};
+
+/// The number of bits a timestamp is range checked to. Milliseconds since the epoch fit in 48
+/// bits until the year 10889.
+pub const TIMESTAMP_BITS: usize = 48;
+
+/// The SCALE compact length prefix of the 8 bytes of a `Timestamp::Now` value, which precedes
+/// them in the leaf node.
+const TIMESTAMP_VALUE_PREFIX: u8 = 8 << 2;
+
+#[derive(Debug, Clone)]
+pub struct TimestampRangeTargets {
+    /// The timestamp checked against the bounds, a target of another fragment.
+    pub timestamp: Target,
+    pub min: Target,
+    pub max: Target,
+    /// The byte offset of the timestamp value within the last storage proof node, for targets
+    /// built with [`TimestampRangeTargets::from_storage_proof`].
+    pub value_offset: Option<Target>,
+}
+
+impl TimestampRangeTargets {
+    /// Checks `timestamp`, e.g. the leaf value target of a `Timestamp::Now` storage proof,
+    /// registering `min` and `max` as public inputs.
+    pub fn new(builder: &mut CircuitBuilder<F, D>, timestamp: Target) -> Self {
+        Self {
+            timestamp,
+            min: builder.add_virtual_public_input(),
+            max: builder.add_virtual_public_input(),
+            value_offset: None,
+        }
+    }
+
+    /// Checks the timestamp held by the last node of `storage_proof`, a `Timestamp::Now` leaf
+    /// node, registering `min` and `max` as public inputs. The timestamp is read at a byte offset
+    /// the prover sets, see [`TimestampRange::with_storage_proof`], and must follow the compact
+    /// length prefix of an 8-byte value.
+    ///
+    /// The offset isn't pinned to the end of the node, so any prefix byte followed by 8 bytes of
+    /// the node would do. The caller must bind the last node to the `Timestamp::Now` key, whose
+    /// leaf node holds nothing but its header, its partial key and the value.
+    pub fn from_storage_proof(
+        builder: &mut CircuitBuilder<F, D>,
+        storage_proof: &StorageProofTargets,
+    ) -> Self {
+        let value_offset = builder.add_virtual_target();
+        let bytes = read_last_node_bytes(builder, storage_proof, value_offset, 1 + 8);
+        let prefix = builder.constant(F::from_canonical_u8(TIMESTAMP_VALUE_PREFIX));
+        builder.connect(bytes[0], prefix);
+
+        // Pack the little-endian value bytes, most significant first.
+        let byte_base = builder.constant(F::from_canonical_u32(1 << 8));
+        let timestamp = bytes[1..].iter().rev().fold(builder.zero(), |acc, &byte| {
+            builder.mul_add(acc, byte_base, byte)
+        });
+
+        Self {
+            value_offset: Some(value_offset),
+            ..Self::new(builder, timestamp)
+        }
+    }
+}
+
+/// A timestamp that must be within `[min, max]`, both inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampRange {
+    pub timestamp: u64,
+    pub min: u64,
+    pub max: u64,
+    /// The byte offset of the timestamp value within the last storage proof node, see
+    /// [`TimestampRange::with_storage_proof`].
+    pub value_offset: Option<usize>,
+}
+
+impl TimestampRange {
+    pub fn new(timestamp: u64, min: u64, max: u64) -> Self {
+        Self {
+            timestamp,
+            min,
+            max,
+            value_offset: None,
+        }
+    }
+
+    /// Locates the timestamp in the last node of `storage_proof`, for a circuit built with
+    /// [`TimestampRangeTargets::from_storage_proof`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the last node doesn't hold the timestamp as a `Timestamp::Now` value.
+    pub fn with_storage_proof<H: AlgebraicHasher<F>>(
+        mut self,
+        storage_proof: &StorageProof<H>,
+    ) -> anyhow::Result<Self> {
+        let mut value = vec![TIMESTAMP_VALUE_PREFIX];
+        value.extend(self.timestamp.to_le_bytes());
+        let Some(offset) = storage_proof.last_node_offset(&value) else {
+            bail!(
+                "last proof node does not hold the timestamp {}",
+                self.timestamp
+            );
+        };
+        self.value_offset = Some(offset);
+        Ok(self)
+    }
+
+    /// Decodes the timestamp from the raw value of a `Timestamp::Now` storage leaf.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `leaf_value` isn't a SCALE encoded `u64`.
+    pub fn from_leaf(leaf_value: &[u8], min: u64, max: u64) -> anyhow::Result<Self> {
+        let Ok(bytes) = <[u8; 8]>::try_from(leaf_value) else {
+            bail!(
+                "Expected 8 bytes for a timestamp leaf, got: {}",
+                leaf_value.len()
+            );
+        };
+        Ok(Self::new(u64::from_le_bytes(bytes), min, max))
+    }
+}
+
+impl CircuitFragment for TimestampRange {
+    type Targets = TimestampRangeTargets;
+
+    /// Builds a circuit that asserts `min <= timestamp <= max`.
+    fn circuit(
+        &Self::Targets {
+            timestamp,
+            min,
+            max,
+        }: &Self::Targets,
+        builder: &mut CircuitBuilder<F, D>,
+    ) {
+        // With all values below 2^TIMESTAMP_BITS, a difference fits in TIMESTAMP_BITS exactly when
+        // it doesn't wrap around the field order, i.e. when it isn't negative.
+        for value in [timestamp, min, max] {
+            builder.range_check(value, TIMESTAMP_BITS);
+        }
+        let since_min = builder.sub(timestamp, min);
+        builder.range_check(since_min, TIMESTAMP_BITS);
+        let until_max = builder.sub(max, timestamp);
+        builder.range_check(until_max, TIMESTAMP_BITS);
+    }
+
+    fn fill_targets(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: Self::Targets,
+    ) -> anyhow::Result<()> {
+        match (targets.value_offset, self.value_offset) {
+            (Some(target), Some(offset)) => {
+                pw.set_target(target, F::from_canonical_usize(offset))?
+            }
+            (Some(_), None) => {
+                bail!(
+                    "the circuit reads the timestamp from the storage proof, but it wasn't located"
+                )
+            }
+            (None, _) => {}
+        }
+        pw.set_target(targets.timestamp, F::from_noncanonical_u64(self.timestamp))?;
+        pw.set_target(targets.min, F::from_noncanonical_u64(self.min))?;
+        pw.set_target(targets.max, F::from_noncanonical_u64(self.max))
+    }
+}
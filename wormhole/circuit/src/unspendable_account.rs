@@ -3,7 +3,11 @@ use alloc::vec::Vec;
 #[cfg(feature = "std")]
 use std::vec::Vec;
 
+use core::cmp::Ordering;
+use core::hash::Hash;
+
 use plonky2::{
+    field::types::PrimeField64,
     hash::{hash_types::HashOutTarget, poseidon::PoseidonHash},
     iop::{
         target::Target,
@@ -12,10 +16,15 @@ use plonky2::{
     plonk::{circuit_builder::CircuitBuilder, config::Hasher},
 };
 
+use anyhow::anyhow;
+
 use crate::codec::ByteCodec;
 use crate::codec::FieldElementCodec;
+use crate::substrate_account::decode_ss58;
 use zk_circuits_common::circuit::{CircuitFragment, D, F};
-use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, string_to_felt, Digest};
+use zk_circuits_common::utils::{
+    bytes_to_felts, felts_to_bytes, string_to_felt, Digest, DIGEST_NUM_FIELD_ELEMENTS,
+};
 
 pub const SECRET_NUM_TARGETS: usize = 4;
 pub const PREIMAGE_NUM_TARGETS: usize = 5;
@@ -28,6 +37,10 @@ pub struct UnspendableAccount {
 }
 
 impl UnspendableAccount {
+    /// The number of field elements returned by [`FieldElementCodec::to_field_elements`], for an
+    /// account derived from its secret.
+    pub const NUM_FELTS: usize = DIGEST_NUM_FIELD_ELEMENTS + SECRET_NUM_TARGETS;
+
     pub fn new(secret: &[u8]) -> Self {
         // First, convert the preimage to its representation as field elements.
         let mut preimage = Vec::new();
@@ -53,6 +66,84 @@ impl UnspendableAccount {
             secret: secret_felts,
         }
     }
+
+    /// The account id and secret as canonical integers, so that the ordering and hash agree with
+    /// the field equality used by [`PartialEq`].
+    fn canonical_key(&self) -> ([u64; 4], Vec<u64>) {
+        (
+            self.account_id.map(|felt| felt.to_canonical_u64()),
+            self.secret
+                .iter()
+                .map(|felt| felt.to_canonical_u64())
+                .collect(),
+        )
+    }
+}
+
+impl Hash for UnspendableAccount {
+    fn hash<S: core::hash::Hasher>(&self, state: &mut S) {
+        self.canonical_key().hash(state);
+    }
+}
+
+impl PartialOrd for UnspendableAccount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UnspendableAccount {
+    /// Orders accounts by account id, then by secret.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_key().cmp(&other.canonical_key())
+    }
+}
+
+impl From<[u8; 32]> for UnspendableAccount {
+    /// Derives the unspendable account from a 32-byte secret. Unlike [`UnspendableAccount::new`],
+    /// this cannot fail as the secret length is known statically.
+    fn from(secret: [u8; 32]) -> Self {
+        Self::new(&secret)
+    }
+}
+
+impl TryFrom<&str> for UnspendableAccount {
+    type Error = anyhow::Error;
+
+    /// Parses the account id of an unspendable account from its address, given either as hex,
+    /// when it starts with `0x` or is 64 hex characters long, or in the SS58 format otherwise. The
+    /// secret can't be recovered from an address, so the account's secret is left empty.
+    fn try_from(address: &str) -> Result<Self, Self::Error> {
+        let is_bare_hex = address.len() == 64 && address.bytes().all(|b| b.is_ascii_hexdigit());
+        let account_id = match address.strip_prefix("0x") {
+            Some(hex_address) => decode_hex_address(hex_address)?,
+            None if is_bare_hex => decode_hex_address(address)?,
+            None => decode_ss58(address).map_err(|e| {
+                anyhow!(
+                    "unspendable address is neither hex nor a valid SS58 address: {}",
+                    e
+                )
+            })?,
+        };
+
+        Ok(Self {
+            account_id: bytes_to_felts(&account_id)
+                .try_into()
+                .map_err(|_| anyhow!("failed to convert address to an unspendable account id"))?,
+            secret: Vec::new(),
+        })
+    }
+}
+
+fn decode_hex_address(address: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes =
+        hex::decode(address).map_err(|e| anyhow!("invalid hex unspendable address: {}", e))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow!(
+            "expected 32 bytes for unspendable address, got: {}",
+            bytes.len()
+        )
+    })
 }
 
 // impl From<&CircuitInputs> for UnspendableAccount {
@@ -113,9 +204,9 @@ impl FieldElementCodec for UnspendableAccount {
 
     fn from_field_elements(elements: &[F]) -> anyhow::Result<Self> {
         // Expected sizes
-        let account_id_size = 4;
-        let secret_size = 4;
-        let total_size = account_id_size + secret_size;
+        let account_id_size = DIGEST_NUM_FIELD_ELEMENTS;
+        let secret_size = SECRET_NUM_TARGETS;
+        let total_size = Self::NUM_FELTS;
 
         if elements.len() != total_size {
             return Err(anyhow::anyhow!(
@@ -141,7 +232,7 @@ impl FieldElementCodec for UnspendableAccount {
 
 #[derive(Debug, Clone)]
 pub struct UnspendableAccountTargets {
-    account_id: HashOutTarget,
+    pub account_id: HashOutTarget,
     pub secret: Vec<Target>,
 }
 
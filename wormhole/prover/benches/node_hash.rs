@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use plonky2::{
+    hash::poseidon::PoseidonHash,
+    iop::witness::PartialWitness,
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, CircuitData},
+    },
+};
+use test_helpers::storage_proof::TestInputs;
+use wormhole_circuit::storage_proof::{StorageProof, StorageProofTargets};
+use zk_circuits_common::blake2::blake2_256;
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::gadgets::canonical_le_bytes;
+
+const MEASUREMENT_TIME_S: u64 = 20;
+
+/// The numbers of node slots to measure. The test proof has 3 nodes, so it fits all of them.
+const NODE_COUNTS: [usize; 3] = [4, 8, 16];
+
+/// How the nodes of the storage proof circuit are hashed.
+#[derive(Clone, Copy)]
+enum NodeHasher {
+    /// Poseidon over the node packed into field elements, as the storage proof circuit does.
+    Poseidon,
+    /// Blake2_256 over the node bytes, as Substrate hashes trie nodes, in addition to Poseidon.
+    Blake2,
+}
+
+impl NodeHasher {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Poseidon => "poseidon",
+            Self::Blake2 => "blake2",
+        }
+    }
+
+    /// Builds the storage proof circuit with `max_proof_len` node slots. The storage proof
+    /// fragment only hashes nodes with an [`plonky2::plonk::config::AlgebraicHasher`], which
+    /// Blake2 isn't, so the Blake2 circuit also hashes the canonical bytes of every full size
+    /// node slot with the Blake2 gadget. Its cost over the Poseidon circuit is the cost of a
+    /// Blake2 trie hashing mode.
+    fn build(self, max_proof_len: usize) -> (CircuitData<F, C, D>, StorageProofTargets, usize) {
+        let config = CircuitConfig::standard_recursion_zk_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let targets = StorageProofTargets::with_max_len(&mut builder, max_proof_len);
+        StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+
+        if let Self::Blake2 = self {
+            for node in &targets.proof_data {
+                let bytes: Vec<_> = node
+                    .iter()
+                    .flat_map(|&felt| canonical_le_bytes(&mut builder, felt))
+                    .collect();
+                let hash = blake2_256(&mut builder, &bytes);
+                builder.register_public_inputs(&hash);
+            }
+        }
+
+        let num_gates = builder.num_gates();
+        (builder.build::<C>(), targets, num_gates)
+    }
+}
+
+/// Compares the cost of the storage proof circuit when its nodes are hashed with Poseidon and
+/// with Blake2. The gate count of each circuit is part of its benchmark id, so that criterion
+/// reports it next to the proving time.
+fn node_hash_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("node_hash_prove");
+
+    for max_proof_len in NODE_COUNTS {
+        let proof = StorageProof::test_inputs()
+            .with_capacity(max_proof_len)
+            .unwrap();
+
+        for hasher in [NodeHasher::Poseidon, NodeHasher::Blake2] {
+            let (circuit_data, targets, num_gates) = hasher.build(max_proof_len);
+            let id = BenchmarkId::new(
+                hasher.name(),
+                format!("{} nodes, {} gates", max_proof_len, num_gates),
+            );
+
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    let mut pw = PartialWitness::new();
+                    proof.fill_targets(&mut pw, targets.clone()).unwrap();
+                    circuit_data.prove(pw).unwrap()
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(MEASUREMENT_TIME_S))
+        .sample_size(10);
+    targets = node_hash_benchmark
+);
+criterion_main!(benches);
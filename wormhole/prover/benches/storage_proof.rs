@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use plonky2::{
+    hash::poseidon::PoseidonHash,
+    iop::witness::PartialWitness,
+    plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+};
+use test_helpers::storage_proof::TestInputs;
+use wormhole_circuit::storage_proof::{StorageProof, StorageProofTargets, MAX_PROOF_LEN};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+
+const MEASUREMENT_TIME_S: u64 = 20;
+
+/// The numbers of node slots to measure, i.e. the maximum proof lengths of the circuit. The test
+/// proof has 3 nodes, so it fits all of them.
+const NODE_COUNTS: [usize; 5] = [4, 8, 12, 16, MAX_PROOF_LEN];
+
+/// Proves the storage proof fragment on its own, so that the cost of the node slots isn't hidden
+/// by the rest of the wormhole circuit. The same proof is padded to each number of node slots, so
+/// the measurements only differ by the size of the circuit.
+fn storage_proof_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("storage_proof_prove");
+
+    for max_proof_len in NODE_COUNTS {
+        let config = CircuitConfig::standard_recursion_zk_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let targets = StorageProofTargets::with_max_len(&mut builder, max_proof_len);
+        StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+        let circuit_data = builder.build::<C>();
+        let proof = StorageProof::test_inputs()
+            .with_capacity(max_proof_len)
+            .unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(max_proof_len),
+            &max_proof_len,
+            |b, _| {
+                b.iter(|| {
+                    let mut pw = PartialWitness::new();
+                    proof.fill_targets(&mut pw, targets.clone()).unwrap();
+                    circuit_data.prove(pw).unwrap()
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(MEASUREMENT_TIME_S))
+        .sample_size(10);
+    targets = storage_proof_benchmark
+);
+criterion_main!(benches);
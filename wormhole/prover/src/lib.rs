@@ -9,6 +9,12 @@
 //! 3. Committing user inputs using [`WormholeProver::commit`].
 //! 4. Generating a proof using [`WormholeProver::prove`].
 //!
+//! With the `tracing` feature enabled, circuit building, witness filling and proving are each
+//! wrapped in a `tracing` span. With the `metrics` feature enabled, every proof built increments
+//! the [`PROOFS_BUILT_COUNTER`] counter. With the `timing` feature enabled, the prover records how
+//! long building the circuit and the last proof took, see [`WormholeProver::last_build_duration`]
+//! and [`WormholeProver::last_prove_duration`].
+//!
 //! # Example
 //!
 //! ```
@@ -26,6 +32,7 @@
 //!         secret: vec![1u8; 32],
 //!         funding_nonce: 0,
 //!         funding_account: SubstrateAccount::new(&[2u8; 32])?,
+//!         funding_accounts: vec![],
 //!         storage_proof: vec![],
 //!         unspendable_account: UnspendableAccount::new(&[1u8; 32]),
 //!     },
@@ -43,9 +50,23 @@
 //! # Ok(())
 //! # }
 //! ```
-use anyhow::bail;
+use core::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+#[cfg(feature = "timing")]
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, ensure};
 use plonky2::{
-    iop::witness::PartialWitness,
+    field::types::{Field, Field64, PrimeField64},
+    iop::{
+        target::Target,
+        wire::Wire,
+        witness::{PartialWitness, WitnessWrite},
+    },
     plonk::{
         circuit_data::{CircuitConfig, ProverCircuitData},
         proof::ProofWithPublicInputs,
@@ -56,56 +77,183 @@ use wormhole_circuit::circuit::WormholeCircuit;
 use wormhole_circuit::storage_proof::StorageProof;
 use wormhole_circuit::{circuit::CircuitTargets, inputs::CircuitInputs};
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::Digest;
+
+/// The name of the counter of proofs built, incremented with the `metrics` feature.
+pub const PROOFS_BUILT_COUNTER: &str = "wormhole_proofs_built_total";
+
+/// The size of a target assignment in a serialized witness: a tag, two indices and the value.
+const WITNESS_ENTRY_LEN: usize = 1 + 3 * 8;
+const WIRE_TARGET_TAG: u8 = 0;
+const VIRTUAL_TARGET_TAG: u8 = 1;
 
 #[derive(Debug)]
 pub struct WormholeProver {
-    pub circuit_data: ProverCircuitData<F, C, D>,
+    /// The circuit data, shared by all provers forked with [`WormholeProver::fork`].
+    pub circuit_data: Arc<ProverCircuitData<F, C, D>>,
     partial_witness: PartialWitness<F>,
     targets: Option<CircuitTargets>,
+    /// The durations recorded with the `timing` feature, shared by all forked provers.
+    #[cfg(feature = "timing")]
+    timings: Arc<Timings>,
+}
+
+#[cfg(feature = "timing")]
+#[derive(Debug)]
+struct Timings {
+    build: Duration,
+    last_prove: Mutex<Option<Duration>>,
 }
 
 impl Default for WormholeProver {
     fn default() -> Self {
-        let wormhole_circuit = WormholeCircuit::default();
-        let partial_witness = PartialWitness::new();
-
-        let targets = Some(wormhole_circuit.targets());
-        let circuit_data = wormhole_circuit.build_prover();
-
-        Self {
-            circuit_data,
-            partial_witness,
-            targets,
-        }
+        Self::from_circuit(WormholeCircuit::default())
     }
 }
 
 impl WormholeProver {
     /// Creates a new [`WormholeProver`].
     pub fn new(config: CircuitConfig) -> Self {
-        let wormhole_circuit = WormholeCircuit::new(config);
+        Self::from_circuit(WormholeCircuit::new(config))
+    }
+
+    /// Creates a new [`WormholeProver`] whose circuit refuses leaves whose to_account is any of
+    /// the `forbidden` account ids, see [`WormholeCircuit::with_blocklist`].
+    pub fn with_blocklist(config: CircuitConfig, forbidden: &[Digest]) -> Self {
+        Self::from_circuit(WormholeCircuit::with_blocklist(config, forbidden))
+    }
+
+    /// Creates a new [`WormholeProver`] whose circuit hashes the leaf in the domain of its chain
+    /// and reveals the chain id as a public input, see [`WormholeCircuit::with_chain_id`]. Inputs
+    /// must be committed with [`WormholeProver::commit_on_chain`].
+    pub fn with_chain_id(config: CircuitConfig) -> Self {
+        Self::from_circuit(WormholeCircuit::with_chain_id(config))
+    }
+
+    /// Creates a new [`WormholeProver`] whose circuit reveals the to_account of the leaf as a
+    /// public input, see [`WormholeCircuit::with_public_to_account`].
+    pub fn with_public_to_account(config: CircuitConfig) -> Self {
+        Self::from_circuit(WormholeCircuit::with_public_to_account(config))
+    }
+
+    /// Toggles blinding of proofs at runtime. Randomized proofs are generated with the circuit's
+    /// zero-knowledge mode enabled, while non-randomized proofs are deterministic, which is useful
+    /// for golden tests. The circuit is rebuilt if the mode changes, discarding any committed
+    /// inputs, so this should be called before [`WormholeProver::commit`].
+    pub fn with_randomness(self, randomized: bool) -> Self {
+        let mut config = self.circuit_data.common.config.clone();
+        if config.zero_knowledge == randomized {
+            return self;
+        }
+        config.zero_knowledge = randomized;
+        Self::new(config)
+    }
+
+    fn from_circuit(wormhole_circuit: WormholeCircuit) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("build_circuit").entered();
+
+        #[cfg(feature = "timing")]
+        let start = Instant::now();
+
         let partial_witness = PartialWitness::new();
 
         let targets = Some(wormhole_circuit.targets());
-        let circuit_data = wormhole_circuit.build_prover();
+        let circuit_data = Arc::new(wormhole_circuit.build_prover());
 
         Self {
             circuit_data,
             partial_witness,
             targets,
+            #[cfg(feature = "timing")]
+            timings: Arc::new(Timings {
+                build: start.elapsed(),
+                last_prove: Mutex::new(None),
+            }),
         }
     }
 
+    /// How long building the circuit of this prover took. Forked provers share the circuit, so
+    /// they report the duration of the original build.
+    #[cfg(feature = "timing")]
+    pub fn last_build_duration(&self) -> Duration {
+        self.timings.build
+    }
+
+    /// How long the last proof of this prover, or of any prover forked from it, took to generate.
+    /// Returns `None` until a proof was generated.
+    #[cfg(feature = "timing")]
+    pub fn last_prove_duration(&self) -> Option<Duration> {
+        *self
+            .timings
+            .last_prove
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Creates a new prover which shares the circuit data of this one, with no inputs committed.
+    /// Building the circuit is expensive while forking is cheap, so a single prover can be built
+    /// once and forked for every proof, including on other threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this prover has already commited to inputs.
+    pub fn fork(&self) -> anyhow::Result<Self> {
+        let Some(targets) = self.targets.clone() else {
+            bail!("prover has already commited to inputs");
+        };
+
+        Ok(Self {
+            circuit_data: Arc::clone(&self.circuit_data),
+            partial_witness: PartialWitness::new(),
+            targets: Some(targets),
+            #[cfg(feature = "timing")]
+            timings: Arc::clone(&self.timings),
+        })
+    }
+
     /// Commits the provided [`CircuitInputs`] to the circuit by filling relevant targets.
     ///
     /// # Errors
     ///
     /// Returns an error if the prover has already commited to inputs previously.
-    pub fn commit(mut self, circuit_inputs: &CircuitInputs) -> anyhow::Result<Self> {
+    pub fn commit(self, circuit_inputs: &CircuitInputs) -> anyhow::Result<Self> {
+        let storage_proof = StorageProof::try_from(circuit_inputs)?;
+        self.commit_storage_proof(circuit_inputs, storage_proof)
+    }
+
+    /// Same as [`WormholeProver::commit`], for a prover built with
+    /// [`WormholeProver::with_chain_id`]: the leaf of the inputs is hashed in the domain of
+    /// `chain_id`, see [`StorageProof::on_chain`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prover has already commited to inputs previously, or if the storage
+    /// proof doesn't prove the leaf of the inputs on `chain_id`.
+    pub fn commit_on_chain(
+        self,
+        circuit_inputs: &CircuitInputs,
+        chain_id: u32,
+    ) -> anyhow::Result<Self> {
+        let storage_proof = StorageProof::on_chain(circuit_inputs, chain_id)?;
+        self.commit_storage_proof(circuit_inputs, storage_proof)
+    }
+
+    fn commit_storage_proof(
+        mut self,
+        circuit_inputs: &CircuitInputs,
+        storage_proof: StorageProof,
+    ) -> anyhow::Result<Self> {
         let Some(targets) = self.targets.take() else {
             bail!("prover has already commited to inputs");
         };
-        let storage_proof = StorageProof::from(circuit_inputs);
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "fill_witness",
+            proof_nodes = storage_proof.real_node_count()
+        )
+        .entered();
 
         circuit_inputs
             .public
@@ -124,6 +272,84 @@ impl WormholeProver {
         Ok(self)
     }
 
+    /// Serializes the committed witness, so that it can be proven on another machine with
+    /// [`WormholeProver::commit_witness_bytes`]. Each target assignment is encoded as a tag, the
+    /// wire row and column or the virtual target index, and the value, all little-endian. The
+    /// assignments are sorted, so the encoding of a witness is deterministic.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prover has not commited to any inputs.
+    pub fn witness_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        if self.targets.is_some() {
+            bail!("prover has not commited to any inputs")
+        }
+
+        let mut entries: Vec<(u8, u64, u64, u64)> = self
+            .partial_witness
+            .target_values
+            .iter()
+            .map(|(target, value)| {
+                let value = value.to_canonical_u64();
+                match *target {
+                    Target::Wire(Wire { row, column }) => {
+                        (WIRE_TARGET_TAG, row as u64, column as u64, value)
+                    }
+                    Target::VirtualTarget { index } => (VIRTUAL_TARGET_TAG, index as u64, 0, value),
+                }
+            })
+            .collect();
+        entries.sort_unstable();
+
+        let mut bytes = Vec::with_capacity(entries.len() * WITNESS_ENTRY_LEN);
+        for (tag, a, b, value) in entries {
+            bytes.push(tag);
+            bytes.extend_from_slice(&a.to_le_bytes());
+            bytes.extend_from_slice(&b.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Commits a witness serialized with [`WormholeProver::witness_bytes`], instead of filling it
+    /// from [`CircuitInputs`]. The witness must have been generated for the same circuit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prover has already commited to inputs, or if `bytes` is not a
+    /// valid serialized witness.
+    pub fn commit_witness_bytes(mut self, bytes: &[u8]) -> anyhow::Result<Self> {
+        if self.targets.take().is_none() {
+            bail!("prover has already commited to inputs");
+        }
+        ensure!(
+            bytes.len() % WITNESS_ENTRY_LEN == 0,
+            "serialized witness length {} is not a multiple of {}",
+            bytes.len(),
+            WITNESS_ENTRY_LEN
+        );
+
+        for entry in bytes.chunks_exact(WITNESS_ENTRY_LEN) {
+            let u64_at =
+                |offset: usize| u64::from_le_bytes(entry[offset..offset + 8].try_into().unwrap());
+            let (a, b, value) = (u64_at(1), u64_at(9), u64_at(17));
+            let target = match entry[0] {
+                WIRE_TARGET_TAG => Target::wire(a as usize, b as usize),
+                VIRTUAL_TARGET_TAG => Target::VirtualTarget { index: a as usize },
+                tag => bail!("invalid target tag in serialized witness: {}", tag),
+            };
+            ensure!(
+                value < F::ORDER,
+                "serialized witness value {} is not a canonical field element",
+                value
+            );
+            self.partial_witness
+                .set_target(target, F::from_canonical_u64(value))?;
+        }
+
+        Ok(self)
+    }
+
     /// Prove the circuit with commited values. It's necessary to call [`WormholeProver::commit`]
     /// before running this function.
     ///
@@ -134,6 +360,51 @@ impl WormholeProver {
         if self.targets.is_some() {
             bail!("prover has not commited to any inputs")
         }
-        self.circuit_data.prove(self.partial_witness)
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("prove").entered();
+
+        #[cfg(feature = "timing")]
+        let start = Instant::now();
+
+        let proof = self.circuit_data.prove(self.partial_witness)?;
+
+        #[cfg(feature = "timing")]
+        {
+            let mut last_prove = self
+                .timings
+                .last_prove
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            *last_prove = Some(start.elapsed());
+        }
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!(PROOFS_BUILT_COUNTER).increment(1);
+
+        Ok(proof)
     }
+
+    /// Same as [`WormholeProver::prove`], but converts a panic while proving into an error with
+    /// the panic message. Plonky2 panics on an inconsistent witness, e.g. with "set twice with
+    /// different values" when the committed inputs don't satisfy the circuit, which must not tear
+    /// down a long-running service. The panic is still reported by the panic hook.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`WormholeProver::prove`] fails or panics.
+    pub fn prove_catching(self) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        std::panic::catch_unwind(AssertUnwindSafe(|| self.prove())).unwrap_or_else(|payload| {
+            Err(anyhow!("proving panicked: {}", panic_message(&*payload)))
+        })
+    }
+}
+
+/// The message of a panic payload, which is a `&str` or a `String` for panics with a message.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic payload")
 }
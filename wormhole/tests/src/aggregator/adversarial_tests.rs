@@ -0,0 +1,185 @@
+//! Adversarial batches for the aggregator, mixing valid, dummy and malformed proofs.
+use std::panic::{self, AssertUnwindSafe};
+
+use plonky2::{
+    field::types::Field,
+    plonk::{circuit_data::CircuitConfig, proof::ProofWithPublicInputs},
+};
+use wormhole_aggregator::{aggregator::WormholeProofAggregator, DEFAULT_NUM_PROOFS_TO_AGGREGATE};
+use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_prover::WormholeProver;
+use wormhole_verifier::WormholeVerifier;
+use zk_circuits_common::circuit::{C, D, F};
+
+use crate::aggregator::circuit_config;
+use test_helpers::storage_proof::TestInputs;
+
+const DUMMY_PROOF_BYTES: &[u8] = include_bytes!("../../../aggregator/data/dummy_proof.bin");
+const ADVERSARIAL_ITERATIONS: usize = 4;
+
+/// The kind of proof placed in a batch slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    /// A valid proof of the wormhole circuit.
+    Valid,
+    /// The dummy proof used by the aggregator for padding.
+    Dummy,
+    /// A valid proof whose first public input was changed.
+    TamperedPublicInputs,
+    /// A valid proof whose wires commitment was changed.
+    TamperedProof,
+    /// A proof of the wormhole circuit built with a different config.
+    WrongCircuit,
+}
+
+impl Entry {
+    const ALL: [Entry; 5] = [
+        Entry::Valid,
+        Entry::Dummy,
+        Entry::TamperedPublicInputs,
+        Entry::TamperedProof,
+        Entry::WrongCircuit,
+    ];
+
+    fn is_malformed(self) -> bool {
+        !matches!(self, Entry::Valid | Entry::Dummy)
+    }
+}
+
+/// Generates proofs for batches of [`Entry`]s. Proofs are only generated once, and cloned into
+/// every slot that needs them.
+struct BatchGenerator {
+    valid: ProofWithPublicInputs<F, C, D>,
+    dummy: ProofWithPublicInputs<F, C, D>,
+    wrong_circuit: ProofWithPublicInputs<F, C, D>,
+}
+
+impl BatchGenerator {
+    fn new() -> Self {
+        let inputs = CircuitInputs::test_inputs();
+        let prover = WormholeProver::new(circuit_config());
+        let valid = prover.commit(&inputs).unwrap().prove().unwrap();
+
+        let verifier = WormholeVerifier::new(circuit_config(), None);
+        let dummy = ProofWithPublicInputs::from_bytes(
+            DUMMY_PROOF_BYTES.to_vec(),
+            &verifier.circuit_data.common,
+        )
+        .unwrap();
+
+        let prover = WormholeProver::new(CircuitConfig::standard_recursion_zk_config());
+        let wrong_circuit = prover.commit(&inputs).unwrap().prove().unwrap();
+
+        Self {
+            valid,
+            dummy,
+            wrong_circuit,
+        }
+    }
+
+    fn proof(&self, entry: Entry) -> ProofWithPublicInputs<F, C, D> {
+        match entry {
+            Entry::Valid => self.valid.clone(),
+            Entry::Dummy => self.dummy.clone(),
+            Entry::TamperedPublicInputs => {
+                let mut proof = self.valid.clone();
+                proof.public_inputs[0] += F::ONE;
+                proof
+            }
+            Entry::TamperedProof => {
+                let mut proof = self.valid.clone();
+                proof.proof.wires_cap.0[0].elements[0] += F::ONE;
+                proof
+            }
+            Entry::WrongCircuit => self.wrong_circuit.clone(),
+        }
+    }
+
+    fn batch(&self, entries: &[Entry]) -> Vec<ProofWithPublicInputs<F, C, D>> {
+        entries.iter().map(|&entry| self.proof(entry)).collect()
+    }
+
+    /// A batch of random length, with every slot drawn from all entry kinds.
+    fn random_entries(&self) -> Vec<Entry> {
+        let len = rand::random_range(1..=DEFAULT_NUM_PROOFS_TO_AGGREGATE);
+        (0..len)
+            .map(|_| Entry::ALL[rand::random_range(0..Entry::ALL.len())])
+            .collect()
+    }
+}
+
+/// Aggregates `proofs`, proves the aggregation and verifies the aggregate proof. Panics raised
+/// while proving are reported as errors.
+fn run_batch(proofs: Vec<ProofWithPublicInputs<F, C, D>>) -> anyhow::Result<()> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut aggregator =
+            WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+        for proof in proofs {
+            aggregator.push_proof(proof)?;
+        }
+        aggregator.aggregate()?;
+        let verifier_data = aggregator.verifier_data();
+        let proof = aggregator.prove()?;
+        verifier_data.verify(proof)
+    }))
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("aggregation panicked")))
+}
+
+#[test]
+fn valid_and_dummy_proofs_are_accepted() {
+    let generator = BatchGenerator::new();
+    let batch = generator.batch(&[Entry::Valid, Entry::Dummy, Entry::Valid]);
+    run_batch(batch).unwrap();
+}
+
+#[test]
+fn tampered_public_inputs_are_rejected() {
+    let generator = BatchGenerator::new();
+    let batch = generator.batch(&[Entry::Valid, Entry::TamperedPublicInputs]);
+    assert!(run_batch(batch).is_err());
+}
+
+#[test]
+fn tampered_proof_is_rejected() {
+    let generator = BatchGenerator::new();
+    let batch = generator.batch(&[Entry::TamperedProof, Entry::Valid]);
+    assert!(run_batch(batch).is_err());
+}
+
+#[test]
+fn proof_of_wrong_circuit_is_rejected() {
+    let generator = BatchGenerator::new();
+    let batch = generator.batch(&[Entry::Valid, Entry::WrongCircuit]);
+    assert!(run_batch(batch).is_err());
+}
+
+#[test]
+fn oversized_batch_is_rejected() {
+    let generator = BatchGenerator::new();
+    let batch = generator.batch(&[Entry::Valid; DEFAULT_NUM_PROOFS_TO_AGGREGATE + 1]);
+
+    let mut aggregator =
+        WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    let result = aggregator.inner.set_proofs(batch);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "proofs to aggregate was more than the maximum allowed"
+    );
+}
+
+#[test]
+fn random_adversarial_batches() {
+    let generator = BatchGenerator::new();
+
+    for i in 0..ADVERSARIAL_ITERATIONS {
+        let entries = generator.random_entries();
+        let expect_rejected = entries.iter().any(|entry| entry.is_malformed());
+
+        let result = run_batch(generator.batch(&entries));
+        assert_eq!(
+            result.is_err(),
+            expect_rejected,
+            "iteration {i}: unexpected result {result:?} for batch {entries:?}"
+        );
+    }
+}
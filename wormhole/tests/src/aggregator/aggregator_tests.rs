@@ -1,10 +1,15 @@
 #![cfg(test)]
-use wormhole_aggregator::{aggregator::WormholeProofAggregator, DEFAULT_NUM_PROOFS_TO_AGGREGATE};
+use wormhole_aggregator::{
+    aggregator::WormholeProofAggregator,
+    circuit::{aggregate_chain_ids, batch_commitment},
+    DEFAULT_NUM_PROOFS_TO_AGGREGATE,
+};
 use wormhole_circuit::inputs::CircuitInputs;
 use wormhole_prover::WormholeProver;
 
 use crate::aggregator::circuit_config;
 use test_helpers::storage_proof::TestInputs;
+use test_helpers::test_inputs_on_chain;
 
 #[test]
 fn push_proof_to_buffer() {
@@ -57,3 +62,131 @@ fn aggregate_single_proof() {
     aggregator.aggregate().unwrap();
     aggregator.prove().unwrap();
 }
+
+#[cfg(feature = "tracing")]
+#[test]
+fn aggregate_emits_spans() {
+    // Create a proof.
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let (aggregate_proof, spans) = crate::tracing_helpers::record_spans(|| {
+        let mut aggregator =
+            WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+        aggregator.push_proof(proof).unwrap();
+        aggregator.aggregate().unwrap();
+        aggregator.prove()
+    });
+    aggregate_proof.unwrap();
+
+    assert_eq!(
+        spans,
+        [
+            "wormhole_aggregator::aggregator::build_circuit",
+            "wormhole_aggregator::aggregator::fill_witness",
+            "wormhole_aggregator::aggregator::prove",
+        ]
+    );
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn aggregate_increments_counter() {
+    use wormhole_aggregator::aggregator::AGGREGATION_BATCHES_COUNTER;
+
+    // Create a proof.
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let (aggregate_proof, counters) = crate::metrics_helpers::record_counters(|| {
+        let mut aggregator =
+            WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+        aggregator.push_proof(proof).unwrap();
+        aggregator.aggregate().unwrap();
+        aggregator.prove()
+    });
+    aggregate_proof.unwrap();
+
+    assert_eq!(counters.get(AGGREGATION_BATCHES_COUNTER), Some(&1));
+}
+
+#[test]
+fn duplicate_proofs_are_rejected() {
+    // Create a proof.
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let mut aggregator =
+        WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config())
+            .with_unique_proofs();
+    aggregator.push_proof(proof.clone()).unwrap();
+    aggregator.push_proof(proof).unwrap();
+
+    let result = aggregator.aggregate();
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "proof 1 duplicates proof 0 in the batch"
+    );
+}
+
+#[test]
+fn duplicate_proofs_are_allowed_by_default() {
+    // Create a proof.
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let mut aggregator =
+        WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    aggregator.push_proof(proof.clone()).unwrap();
+    aggregator.push_proof(proof).unwrap();
+    aggregator.aggregate().unwrap();
+}
+
+#[test]
+fn aggregate_proofs_of_two_chains() {
+    let prover = WormholeProver::with_chain_id(circuit_config());
+    let proofs: Vec<_> = [1, 2]
+        .into_iter()
+        .map(|chain_id| {
+            let inputs = test_inputs_on_chain(chain_id);
+            prover
+                .fork()
+                .unwrap()
+                .commit_on_chain(&inputs, chain_id)
+                .unwrap()
+                .prove()
+                .unwrap()
+        })
+        .collect();
+
+    let mut aggregator = WormholeProofAggregator::<2>::with_chain_ids(circuit_config());
+    let aggregate_proof = aggregator.prove_batch(proofs.clone()).unwrap();
+    aggregator
+        .verifier_data()
+        .verify(aggregate_proof.clone())
+        .unwrap();
+
+    assert_eq!(
+        aggregate_proof.public_inputs[..4],
+        batch_commitment(&proofs).elements
+    );
+    assert_eq!(aggregate_chain_ids::<2>(&aggregate_proof).unwrap(), [1, 2]);
+}
+
+#[test]
+fn chain_id_batches_are_not_padded() {
+    let prover = WormholeProver::with_chain_id(circuit_config());
+    let inputs = test_inputs_on_chain(1);
+    let proof = prover.commit_on_chain(&inputs, 1).unwrap().prove().unwrap();
+
+    let mut aggregator = WormholeProofAggregator::<2>::with_chain_ids(circuit_config());
+    let err = aggregator.prove_batch(vec![proof]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "there is no dummy proof to pad the batch with, it must hold 2 proofs"
+    );
+}
@@ -1,10 +1,16 @@
 #![cfg(test)]
+use std::fs;
+
+use plonky2::field::types::Field;
+
 use crate::aggregator::circuit_config;
 use crate::circuit_helpers::{build_and_prove_test, setup_test_builder_and_witness};
 use test_helpers::storage_proof::TestInputs;
-use wormhole_aggregator::circuit::{WormholeProofAggregatorInner, WormholeProofAggregatorTargets};
+use wormhole_aggregator::circuit::{
+    batch_commitment, WormholeProofAggregatorInner, WormholeProofAggregatorTargets,
+};
 use wormhole_aggregator::DEFAULT_NUM_PROOFS_TO_AGGREGATE;
-use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_circuit::inputs::{CircuitInputs, PUBLIC_INPUTS_FELTS_LEN};
 use wormhole_prover::WormholeProver;
 use wormhole_verifier::ProofWithPublicInputs;
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
@@ -13,13 +19,11 @@ fn run_test(
     proofs: Vec<ProofWithPublicInputs<F, C, D>>,
 ) -> anyhow::Result<plonky2::plonk::proof::ProofWithPublicInputs<F, C, D>> {
     let (mut builder, mut pw) = setup_test_builder_and_witness(false);
-    let targets = WormholeProofAggregatorTargets::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(
-        &mut builder,
-        circuit_config(),
-    );
+    let mut aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
     WormholeProofAggregatorInner::circuit(&targets, &mut builder);
 
-    let mut aggregator = WormholeProofAggregatorInner::new(circuit_config());
     aggregator.set_proofs(proofs)?;
     aggregator.fill_targets(&mut pw, targets)?;
     build_and_prove_test(builder, pw)
@@ -51,3 +55,156 @@ fn few_proofs_pass() {
 
     run_test(proofs).unwrap();
 }
+
+#[test]
+fn dummy_proof_from_path_pads_batch() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let dummy_proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let path = std::env::temp_dir().join("wormhole_dummy_proof_from_path.bin");
+    fs::write(&path, dummy_proof.to_bytes()).unwrap();
+
+    let aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::with_dummy_proof_path(
+            circuit_config(),
+            &path,
+        );
+    fs::remove_file(&path).unwrap();
+    let mut aggregator = aggregator.unwrap();
+
+    let (mut builder, mut pw) = setup_test_builder_and_witness(false);
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
+    WormholeProofAggregatorInner::circuit(&targets, &mut builder);
+
+    aggregator.set_proofs(vec![]).unwrap();
+    aggregator.fill_targets(&mut pw, targets).unwrap();
+    build_and_prove_test(builder, pw).unwrap();
+}
+
+#[test]
+fn invalid_dummy_proof_from_path_is_rejected() {
+    let path = std::env::temp_dir().join("wormhole_invalid_dummy_proof.bin");
+    fs::write(&path, [0u8; 64]).unwrap();
+
+    let aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::with_dummy_proof_path(
+            circuit_config(),
+            &path,
+        );
+    fs::remove_file(&path).unwrap();
+    assert!(aggregator.is_err());
+}
+
+#[test]
+fn targets_share_aggregator_config() {
+    let aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    assert_eq!(aggregator.config(), &circuit_config());
+
+    // The targets can only be built from an aggregator, so their inner circuit data always
+    // matches the data the aggregator fills the witness with.
+    let (mut builder, _) = setup_test_builder_and_witness(false);
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
+    assert_eq!(
+        targets.common_data(),
+        &aggregator.inner_verifier.circuit_data.common
+    );
+}
+
+#[test]
+fn targets_expose_cap_height() {
+    let aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    let (mut builder, _) = setup_test_builder_and_witness(false);
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
+    assert_eq!(targets.cap_height(), circuit_config().fri_config.cap_height);
+}
+
+#[test]
+fn zero_cap_height_is_accepted() {
+    // A cap of height zero is a single merkle root, which is a valid plonky2 config.
+    let mut config = circuit_config();
+    config.fri_config.cap_height = 0;
+    let aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(config);
+
+    let (mut builder, _) = setup_test_builder_and_witness(false);
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
+    assert_eq!(targets.cap_height(), 0);
+}
+
+#[test]
+fn targets_layout_is_stable_across_builds() {
+    let build_layout = || {
+        let aggregator = WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(
+            circuit_config(),
+        );
+        let (mut builder, _) = setup_test_builder_and_witness(false);
+        WormholeProofAggregatorTargets::new(&mut builder, &aggregator).layout()
+    };
+    let layout = build_layout();
+    assert_eq!(layout, build_layout());
+    assert_eq!(layout.to_json(), build_layout().to_json());
+
+    let cap_height = circuit_config().fri_config.cap_height;
+    assert_eq!(layout.constants_sigmas_cap.len(), 4 * (1 << cap_height));
+    assert_eq!(layout.circuit_digest.len(), 4);
+    assert_eq!(layout.proofs.len(), DEFAULT_NUM_PROOFS_TO_AGGREGATE);
+
+    // Proofs are laid out one after another, each followed by its public inputs.
+    let num_public_inputs = PUBLIC_INPUTS_FELTS_LEN;
+    for (proof, next) in layout.proofs.iter().zip(layout.proofs.iter().skip(1)) {
+        assert!(!proof.proof.is_empty());
+        assert_eq!(proof.public_inputs.len(), num_public_inputs);
+        assert_eq!(
+            proof.public_inputs,
+            (proof.proof.end..proof.proof.end + num_public_inputs).collect::<Vec<_>>()
+        );
+        assert_eq!(next.proof.start, proof.proof.end + num_public_inputs);
+    }
+}
+
+#[test]
+fn validate_sample_only_checks_given_indices() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let mut bad_proof = proof.clone();
+    bad_proof.public_inputs[0] += F::ONE;
+
+    let mut aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    aggregator
+        .set_proofs(vec![proof.clone(), bad_proof, proof])
+        .unwrap();
+
+    // The bad proof at index 1 isn't sampled, so it isn't flagged.
+    aggregator.validate_sample(&[0, 2]).unwrap();
+    assert_eq!(aggregator.validate_sample(&[2, 1, 0]), Err(vec![1]));
+    assert_eq!(
+        aggregator.validate_sample(&[DEFAULT_NUM_PROOFS_TO_AGGREGATE]),
+        Err(vec![DEFAULT_NUM_PROOFS_TO_AGGREGATE])
+    );
+}
+
+#[test]
+fn batch_commitment_matches_inner_public_inputs() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let (mut builder, mut pw) = setup_test_builder_and_witness(false);
+    let mut aggregator =
+        WormholeProofAggregatorInner::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(circuit_config());
+    let targets = WormholeProofAggregatorTargets::new(&mut builder, &aggregator);
+    WormholeProofAggregatorInner::circuit(&targets, &mut builder);
+    aggregator.set_proofs(vec![proof.clone()]).unwrap();
+    aggregator.fill_targets(&mut pw, targets).unwrap();
+    let aggregate = build_and_prove_test(builder, pw).unwrap();
+
+    // The commitment covers the padding proofs as well as the real one.
+    let commitment = aggregator.batch_commitment();
+    assert_eq!(aggregate.public_inputs, commitment.elements.to_vec());
+    assert_ne!(commitment, batch_commitment(&[proof]));
+}
@@ -0,0 +1,94 @@
+#![cfg(test)]
+use test_helpers::storage_proof::TestInputs;
+use wormhole_aggregator::layers::LayeredAggregator;
+use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_prover::WormholeProver;
+use wormhole_verifier::WormholeVerifier;
+
+use crate::aggregator::circuit_config;
+
+const PROOFS_PER_AGGREGATE: usize = 2;
+const AGGREGATES_PER_LAYER: usize = 2;
+
+type Aggregator = LayeredAggregator<PROOFS_PER_AGGREGATE, AGGREGATES_PER_LAYER>;
+
+#[test]
+fn prove_through_two_layers() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let mut aggregator = Aggregator::new(circuit_config(), 2).unwrap();
+    assert_eq!(aggregator.num_layers(), 2);
+    assert!(aggregator.layer_circuit_data(2).is_none());
+
+    // The first layer's circuit is reused for both aggregates.
+    let left = aggregator
+        .prove_layer(0, vec![proof.clone(), proof.clone()])
+        .unwrap();
+    let right = aggregator.prove_layer(0, vec![proof]).unwrap();
+    let top = aggregator
+        .prove_layer(1, vec![left.clone(), right])
+        .unwrap();
+
+    let layer_circuit_data = (0..aggregator.num_layers())
+        .map(|layer| aggregator.layer_circuit_data(layer).unwrap().clone())
+        .collect();
+    let verifier =
+        WormholeVerifier::new(circuit_config(), None).with_layer_circuit_data(layer_circuit_data);
+    verifier.verify_layer(0, left.clone()).unwrap();
+    verifier.verify_layer(1, top).unwrap();
+    assert_eq!(
+        verifier.verify_layer(2, left).unwrap_err().to_string(),
+        "verifier has no circuit data for aggregation layer 2, only 2 layers"
+    );
+}
+
+#[test]
+fn zero_layers_is_rejected() {
+    let result = Aggregator::new(circuit_config(), 0);
+    assert_eq!(
+        result.err().unwrap().to_string(),
+        "aggregation needs at least one layer"
+    );
+}
+
+#[test]
+fn missing_layer_is_rejected() {
+    let mut aggregator = Aggregator::new(circuit_config(), 1).unwrap();
+    let result = aggregator.prove_layer(1, vec![]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "no aggregation layer 1, only 1 layers"
+    );
+}
+
+#[test]
+fn prove_stream_through_two_layers() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    // Three aggregates of the first layer exactly fill the second one.
+    let mut aggregator =
+        LayeredAggregator::<PROOFS_PER_AGGREGATE, 3>::new(circuit_config(), 2).unwrap();
+    let proofs = std::iter::repeat(proof).take(3 * PROOFS_PER_AGGREGATE);
+    let top = aggregator.prove_stream(proofs).unwrap();
+
+    let layer_circuit_data = (0..aggregator.num_layers())
+        .map(|layer| aggregator.layer_circuit_data(layer).unwrap().clone())
+        .collect();
+    let verifier =
+        WormholeVerifier::new(circuit_config(), None).with_layer_circuit_data(layer_circuit_data);
+    verifier.verify_layer(1, top).unwrap();
+}
+
+#[test]
+fn prove_stream_rejects_empty_stream() {
+    let mut aggregator = Aggregator::new(circuit_config(), 1).unwrap();
+    let result = aggregator.prove_stream(std::iter::empty());
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "there are no proofs to aggregate"
+    );
+}
@@ -1,8 +1,11 @@
 #![cfg(test)]
 
 use plonky2::plonk::circuit_data::CircuitConfig;
+pub mod adversarial_tests;
 pub mod aggregator_tests;
 pub mod circuit_tests;
+pub mod layers_tests;
+pub mod top_level_tests;
 
 fn circuit_config() -> CircuitConfig {
     CircuitConfig::standard_recursion_config()
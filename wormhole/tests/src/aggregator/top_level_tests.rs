@@ -0,0 +1,55 @@
+#![cfg(test)]
+use plonky2::plonk::circuit_data::VerifierCircuitData;
+use test_helpers::storage_proof::TestInputs;
+use wormhole_aggregator::aggregator::WormholeProofAggregator;
+use wormhole_aggregator::top_level::TopLevelAggregator;
+use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_prover::WormholeProver;
+use wormhole_verifier::ProofWithPublicInputs;
+use zk_circuits_common::circuit::{C, D, F};
+
+use crate::aggregator::circuit_config;
+
+const PROOFS_PER_AGGREGATE: usize = 2;
+
+fn aggregate(
+    proofs: Vec<ProofWithPublicInputs<F, C, D>>,
+) -> (VerifierCircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>) {
+    let mut aggregator = WormholeProofAggregator::<PROOFS_PER_AGGREGATE>::new(circuit_config());
+    for proof in proofs {
+        aggregator.push_proof(proof).unwrap();
+    }
+    aggregator.aggregate().unwrap();
+
+    let verifier_data = aggregator.verifier_data();
+    (verifier_data, aggregator.prove().unwrap())
+}
+
+#[test]
+fn combine_two_aggregates() {
+    let prover = WormholeProver::new(circuit_config());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let (aggregate_circuit_data, left) = aggregate(vec![proof.clone(), proof.clone()]);
+    let (_, right) = aggregate(vec![proof.clone(), proof]);
+
+    let mut top_level = TopLevelAggregator::<2>::new(circuit_config(), aggregate_circuit_data);
+    top_level.aggregate(vec![left, right]).unwrap();
+
+    let top_level_circuit_data = top_level.verifier_data();
+    let top_level_proof = top_level.prove().unwrap();
+    top_level_circuit_data.verify(top_level_proof).unwrap();
+}
+
+#[test]
+fn wrong_number_of_aggregates_fails() {
+    let aggregator = WormholeProofAggregator::<PROOFS_PER_AGGREGATE>::new(circuit_config());
+    let mut top_level = TopLevelAggregator::<2>::new(circuit_config(), aggregator.verifier_data());
+
+    let result = top_level.aggregate(vec![]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "expected 2 aggregate proofs, got: 0"
+    );
+}
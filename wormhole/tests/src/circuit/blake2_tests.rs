@@ -0,0 +1,63 @@
+use plonky2::{field::types::Field, iop::witness::WitnessWrite};
+use zk_circuits_common::blake2::{blake2_128, blake2_256};
+use zk_circuits_common::circuit::F;
+
+#[cfg(test)]
+fn run_blake2_128(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = builder.add_virtual_targets(data.len());
+    let hash = blake2_128(&mut builder, &targets);
+    builder.register_public_inputs(&hash);
+
+    let felts: Vec<F> = data.iter().map(|&b| F::from_canonical_u8(b)).collect();
+    pw.set_target_arr(&targets, &felts)?;
+    let proof = crate::circuit_helpers::build_and_prove_test(builder, pw)?;
+
+    Ok(proof
+        .public_inputs
+        .iter()
+        .map(|felt| felt.0 as u8)
+        .collect())
+}
+
+#[test]
+fn blake2_128_empty() {
+    let hash = run_blake2_128(&[]).unwrap();
+    assert_eq!(hex::encode(hash), "cae66941d9efbd404e4d88758ea67670");
+}
+
+#[test]
+fn blake2_128_single_block() {
+    let hash = run_blake2_128(b"abc").unwrap();
+    assert_eq!(hex::encode(hash), "cf4ab791c62b8d2b2109c90275287816");
+}
+
+#[test]
+fn blake2_128_multiple_blocks() {
+    let data: Vec<u8> = (0..200).collect();
+    let hash = run_blake2_128(&data).unwrap();
+    assert_eq!(hex::encode(hash), "61479efa6267fea757b3f881e2979bbc");
+}
+
+#[test]
+fn blake2_256_single_block() {
+    let data = b"abc";
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = builder.add_virtual_targets(data.len());
+    let hash = blake2_256(&mut builder, &targets);
+    builder.register_public_inputs(&hash);
+
+    let felts: Vec<F> = data.iter().map(|&b| F::from_canonical_u8(b)).collect();
+    pw.set_target_arr(&targets, &felts).unwrap();
+    let proof = crate::circuit_helpers::build_and_prove_test(builder, pw).unwrap();
+
+    let hash: Vec<u8> = proof
+        .public_inputs
+        .iter()
+        .map(|felt| felt.0 as u8)
+        .collect();
+    assert_eq!(
+        hex::encode(hash),
+        "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+    );
+}
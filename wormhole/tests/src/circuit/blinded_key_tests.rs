@@ -0,0 +1,231 @@
+use std::panic;
+
+use plonky2::{
+    field::types::Field,
+    hash::poseidon::PoseidonHash,
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{circuit_data::CircuitData, config::Hasher, proof::ProofWithPublicInputs},
+};
+use test_helpers::storage_proof::storage_proof_ending_with;
+use wormhole_circuit::{
+    blinded_key::{BlindedKey, BlindedKeyTargets},
+    storage_key::{compute_storage_key, StorageHasher},
+    storage_proof::{StorageProof, StorageProofTargets},
+    system_account::{
+        system_account_key, SYSTEM_ACCOUNT_KEY_NUM_BYTES, SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES,
+    },
+};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+
+/// Builds a circuit that reconstructs the `System::Account` key of a private account and only
+/// reveals the blinded key commitment.
+#[cfg(test)]
+fn build_circuit() -> (CircuitData<F, C, D>, Vec<Target>, BlindedKeyTargets) {
+    let (mut builder, _) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let account = builder.add_virtual_targets(32);
+    let key = system_account_key(&mut builder, &account);
+    let targets = BlindedKeyTargets::new(&mut builder, SYSTEM_ACCOUNT_KEY_NUM_BYTES);
+    for (&reconstructed, &private) in key.iter().zip(&targets.key) {
+        builder.connect(reconstructed, private);
+    }
+    BlindedKey::circuit(&targets, &mut builder);
+
+    (builder.build::<C>(), account, targets)
+}
+
+#[cfg(test)]
+fn blinded_system_account_key(account: &[u8; 32], salt: [F; 4]) -> BlindedKey {
+    let key = compute_storage_key(
+        "System",
+        "Account",
+        account,
+        StorageHasher::Blake2_128Concat,
+    );
+    BlindedKey::new(&hex::decode(&key[2..]).unwrap(), salt)
+}
+
+#[test]
+fn different_keys_have_different_commitments() {
+    let (data, account_targets, targets) = build_circuit();
+    let salt = [F::from_canonical_u64(42); 4];
+
+    let mut commitments = vec![];
+    for account in [[1u8; 32], [2u8; 32]] {
+        let blinded_key = blinded_system_account_key(&account, salt);
+        let mut pw = PartialWitness::new();
+        let account_felts: Vec<F> = account.iter().map(|&b| F::from_canonical_u8(b)).collect();
+        pw.set_target_arr(&account_targets, &account_felts).unwrap();
+        blinded_key.fill_targets(&mut pw, targets.clone()).unwrap();
+
+        let proof = data.prove(pw).unwrap();
+        assert_eq!(proof.public_inputs, blinded_key.commitment().to_vec());
+        data.verify(proof.clone()).unwrap();
+        commitments.push(proof.public_inputs);
+    }
+
+    assert_ne!(commitments[0], commitments[1]);
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn commitment_must_match_private_key() {
+    let (data, account_targets, targets) = build_circuit();
+    let salt = [F::from_canonical_u64(42); 4];
+
+    // The commitment is computed for another account than the one the key is reconstructed from.
+    let mut pw = PartialWitness::new();
+    pw.set_target_arr(&account_targets, &[F::ONE; 32]).unwrap();
+    blinded_system_account_key(&[2u8; 32], salt)
+        .fill_targets(&mut pw, targets)
+        .unwrap();
+    data.prove(pw).unwrap();
+}
+
+#[test]
+fn salt_changes_commitment() {
+    let key = [7u8; SYSTEM_ACCOUNT_KEY_NUM_BYTES];
+    let a = BlindedKey::new(&key, [F::ONE; 4]).commitment();
+    let b = BlindedKey::new(&key, [F::TWO; 4]).commitment();
+    assert_ne!(a, b);
+}
+
+/// Proves the opening of a commitment to `key`, whose elements need not be bytes.
+#[cfg(test)]
+fn prove_key_elements(key: &[F]) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = BlindedKeyTargets::new(&mut builder, key.len());
+    BlindedKey::circuit(&targets, &mut builder);
+
+    let salt = [F::ONE; 4];
+    let commitment = PoseidonHash::hash_no_pad(&[key, &salt[..]].concat());
+    pw.set_target_arr(&targets.key, key)?;
+    pw.set_hash_target(targets.salt, salt.into())?;
+    pw.set_hash_target(targets.commitment, commitment)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn key_elements_must_be_bytes() {
+    prove_key_elements(&[F::from_canonical_u8(255), F::ZERO]).unwrap();
+
+    let result =
+        panic::catch_unwind(|| prove_key_elements(&[F::from_canonical_u16(256), F::ZERO]).unwrap());
+    assert!(result.is_err());
+}
+
+/// Builds a circuit that proves a storage path ending with the `System::Account` leaf of a
+/// private account, and only reveals the blinded key commitment along with the storage root.
+#[cfg(test)]
+fn build_storage_proof_circuit() -> (
+    CircuitData<F, C, D>,
+    StorageProofTargets,
+    Vec<Target>,
+    BlindedKeyTargets,
+) {
+    let (mut builder, _) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let storage_proof_targets = StorageProofTargets::new(&mut builder);
+    StorageProof::<PoseidonHash>::circuit(&storage_proof_targets, &mut builder);
+    let account = builder.add_virtual_targets(32);
+    let key = system_account_key(&mut builder, &account);
+    let targets = BlindedKeyTargets::from_storage_proof(
+        &mut builder,
+        &storage_proof_targets,
+        SYSTEM_ACCOUNT_KEY_NUM_BYTES,
+        SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES,
+    );
+    for (&reconstructed, &private) in key.iter().zip(&targets.key) {
+        builder.connect(reconstructed, private);
+    }
+    BlindedKey::circuit(&targets, &mut builder);
+
+    (
+        builder.build::<C>(),
+        storage_proof_targets,
+        account,
+        targets,
+    )
+}
+
+/// A storage proof ending with the `System::Account` leaf node of `key`, 69 nibbles below the
+/// root: the header of a leaf with a hashed value and 91 partial key nibbles, the odd leading
+/// nibble, the remaining key bytes, then the hash of the value.
+#[cfg(test)]
+fn system_account_storage_proof(key: &[u8]) -> StorageProof {
+    let mut left = vec![0x3f, 91 - 31, key[34] & 0x0f];
+    left.extend_from_slice(&key[35..]);
+    let (nodes, root_hash) = storage_proof_ending_with((left, vec![7u8; 32]));
+    StorageProof::new(&nodes, root_hash, 0).unwrap()
+}
+
+#[test]
+fn blinded_keys_of_proven_leaves() {
+    let (data, storage_proof_targets, account_targets, targets) = build_storage_proof_circuit();
+    let salt = [F::from_canonical_u64(42); 4];
+
+    let mut commitments = vec![];
+    for account in [[1u8; 32], [2u8; 32]] {
+        let blinded_key = blinded_system_account_key(&account, salt);
+        let storage_proof = system_account_storage_proof(&blinded_key.key);
+        let blinded_key = blinded_key
+            .with_storage_proof(&storage_proof, SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES)
+            .unwrap();
+
+        let mut pw = PartialWitness::new();
+        storage_proof
+            .fill_targets(&mut pw, storage_proof_targets.clone())
+            .unwrap();
+        let account_felts: Vec<F> = account.iter().map(|&b| F::from_canonical_u8(b)).collect();
+        pw.set_target_arr(&account_targets, &account_felts).unwrap();
+        blinded_key.fill_targets(&mut pw, targets.clone()).unwrap();
+
+        let proof = data.prove(pw).unwrap();
+        // The commitment is registered after the funding amount and the root hash.
+        let commitment = proof.public_inputs[proof.public_inputs.len() - 4..].to_vec();
+        assert_eq!(commitment, blinded_key.commitment().to_vec());
+        data.verify(proof).unwrap();
+        commitments.push(commitment);
+    }
+
+    assert_ne!(commitments[0], commitments[1]);
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn blinded_key_must_be_proven_leaf() {
+    let (data, storage_proof_targets, account_targets, targets) = build_storage_proof_circuit();
+    let salt = [F::from_canonical_u64(42); 4];
+
+    // Commit to the key of another account than the one the storage proof ends with, at the
+    // offset of the proven key.
+    let proven = blinded_system_account_key(&[1u8; 32], salt);
+    let storage_proof = system_account_storage_proof(&proven.key);
+    let proven = proven
+        .with_storage_proof(&storage_proof, SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES)
+        .unwrap();
+    let mut blinded_key = blinded_system_account_key(&[2u8; 32], salt);
+    blinded_key.key_offset = proven.key_offset;
+
+    let mut pw = PartialWitness::new();
+    storage_proof
+        .fill_targets(&mut pw, storage_proof_targets)
+        .unwrap();
+    pw.set_target_arr(&account_targets, &[F::TWO; 32]).unwrap();
+    blinded_key.fill_targets(&mut pw, targets).unwrap();
+    data.prove(pw).unwrap();
+}
+
+#[test]
+fn with_storage_proof_of_another_key() {
+    let salt = [F::from_canonical_u64(42); 4];
+    let storage_proof =
+        system_account_storage_proof(&blinded_system_account_key(&[1u8; 32], salt).key);
+    let result = blinded_system_account_key(&[2u8; 32], salt)
+        .with_storage_proof(&storage_proof, SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "last proof node does not hold the key suffix"
+    );
+}
@@ -0,0 +1,52 @@
+use plonky2::{field::types::Field, plonk::proof::ProofWithPublicInputs};
+use test_helpers::storage_proof::TestInputs;
+use wormhole_circuit::{
+    blocklist::{Blocklist, BlocklistTargets},
+    inputs::CircuitInputs,
+    unspendable_account::UnspendableAccount,
+};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::Digest;
+
+#[cfg(test)]
+fn test_to_account() -> Digest {
+    CircuitInputs::test_inputs()
+        .private
+        .unspendable_account
+        .account_id
+}
+
+#[cfg(test)]
+fn forbidden() -> [Digest; 2] {
+    [
+        UnspendableAccount::new(&[1u8; 32]).account_id,
+        test_to_account(),
+    ]
+}
+
+#[cfg(test)]
+fn run_test(to_account: Digest) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let account_target = builder.add_virtual_hash();
+    let targets = BlocklistTargets::new(&mut builder, account_target, &forbidden());
+    Blocklist::circuit(&targets, &mut builder);
+
+    Blocklist::new(to_account).fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn allowed_to_account_passes() {
+    run_test(UnspendableAccount::new(&[3u8; 32]).account_id).unwrap();
+
+    // Sharing some, but not all, elements with a forbidden account is fine.
+    let mut account = forbidden()[1];
+    account[3] += F::ONE;
+    run_test(account).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn blocklisted_to_account_fails() {
+    run_test(test_to_account()).unwrap();
+}
@@ -0,0 +1,14 @@
+use std::fs;
+
+use wormhole_circuit::debug_dump::dump_circuit;
+
+#[test]
+fn dump_default_circuit() {
+    let path = std::env::temp_dir().join("wormhole_storage_proof_circuit.txt");
+    dump_circuit(&path).unwrap();
+
+    let listing = fs::read_to_string(&path).unwrap();
+    assert!(!listing.is_empty());
+    assert!(listing.contains("PoseidonGate"));
+    assert!(listing.contains("0: funding_amount[0]"));
+}
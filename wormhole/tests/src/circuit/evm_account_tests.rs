@@ -0,0 +1,63 @@
+use plonky2::field::types::Field;
+use wormhole_circuit::{
+    codec::{ByteCodec, FieldElementCodec},
+    evm_account::{EvmAccount, EVM_ACCOUNT_NUM_BYTES},
+};
+use zk_circuits_common::circuit::F;
+
+const ADDRESS: [u8; EVM_ACCOUNT_NUM_BYTES] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+];
+
+#[test]
+fn encodes_20_byte_address() {
+    let account = EvmAccount::new(&ADDRESS).unwrap();
+    let elements = account.to_field_elements();
+    assert_eq!(elements.len(), EvmAccount::NUM_FELTS);
+    assert_eq!(EvmAccount::NUM_FELTS, 3);
+
+    // The last element only holds the final 4 bytes of the address.
+    let expected: Vec<F> = ADDRESS
+        .chunks(8)
+        .map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            F::from_noncanonical_u64(u64::from_le_bytes(bytes))
+        })
+        .collect();
+    assert_eq!(elements, expected);
+    assert_eq!(
+        elements[2],
+        F::from_canonical_u64(u32::from_le_bytes([17, 18, 19, 20]) as u64)
+    );
+
+    let decoded = EvmAccount::from_field_elements(&elements).unwrap();
+    assert_eq!(decoded, account);
+    assert_eq!(decoded.to_bytes(), ADDRESS.to_vec());
+}
+
+#[test]
+fn rejects_wrong_byte_length() {
+    let err = EvmAccount::new(&[0u8; 32]).unwrap_err();
+    assert_eq!(err.to_string(), "Expected 20 bytes for EvmAccount, got: 32");
+}
+
+#[test]
+fn rejects_wrong_number_of_elements() {
+    let err = EvmAccount::from_field_elements(&[F::ZERO; 4]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected 3 field elements for EvmAccount, got: 4"
+    );
+}
+
+#[test]
+fn rejects_bytes_past_address() {
+    let mut elements = EvmAccount::from(ADDRESS).to_field_elements();
+    elements[2] += F::from_canonical_u64(1 << 32);
+    let err = EvmAccount::from_field_elements(&elements).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Field elements for EvmAccount exceed 20 bytes"
+    );
+}
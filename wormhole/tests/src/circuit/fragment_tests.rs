@@ -0,0 +1,37 @@
+use wormhole_circuit::{
+    blocklist::{Blocklist, BlocklistTargets},
+    unspendable_account::UnspendableAccount,
+};
+use zk_circuits_common::circuit::{unassigned_targets, CircuitFragment, C};
+
+#[test]
+fn partially_filled_witness_reports_missing_targets() {
+    let forbidden = [UnspendableAccount::new(&[1u8; 32]).account_id];
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let (filled_account, unfilled_account) =
+        (builder.add_virtual_hash(), builder.add_virtual_hash());
+    let filled = BlocklistTargets::new(&mut builder, filled_account, &forbidden);
+    let unfilled = BlocklistTargets::new(&mut builder, unfilled_account, &forbidden);
+    Blocklist::circuit(&filled, &mut builder);
+    Blocklist::circuit(&unfilled, &mut builder);
+    let data = builder.build::<C>();
+
+    let missing = unassigned_targets(&pw, &data).unwrap();
+    assert_eq!(missing.len(), 8);
+
+    let account = UnspendableAccount::new(&[3u8; 32]).account_id;
+    Blocklist::new(account)
+        .fill_targets(&mut pw, filled)
+        .unwrap();
+    let missing = unassigned_targets(&pw, &data).unwrap();
+    assert_eq!(missing.len(), unfilled.account.elements.len());
+    for target in unfilled.account.elements {
+        assert!(missing.contains(&target));
+    }
+
+    Blocklist::new(account)
+        .fill_targets(&mut pw, unfilled)
+        .unwrap();
+    assert!(unassigned_targets(&pw, &data).unwrap().is_empty());
+    data.prove(pw).unwrap();
+}
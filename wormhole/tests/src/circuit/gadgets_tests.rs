@@ -0,0 +1,232 @@
+use plonky2::{
+    field::types::{Field, Field64},
+    hash::{hash_types::HashOut, poseidon::PoseidonHash},
+    iop::witness::WitnessWrite,
+    plonk::{config::Hasher, proof::ProofWithPublicInputs},
+};
+use test_helpers::fixtures;
+use test_helpers::storage_proof::{default_root_hash, TestInputs};
+use wormhole_circuit::storage_proof::{StorageProof, StorageProofTargets};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::gadgets::{
+    canonical_le_bytes, connect_hash_constant, decode_compact, hash_var_len, is_equal_hash,
+    is_zero_hash,
+};
+use zk_circuits_common::utils::bytes_to_felts;
+
+#[cfg(test)]
+fn run_decode_compact(
+    bytes: &[u8],
+    expected_value: u64,
+    expected_len: u64,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = builder.add_virtual_targets(bytes.len());
+    let (value, len) = decode_compact(&mut builder, &targets);
+
+    let expected_value = builder.constant(F::from_canonical_u64(expected_value));
+    let expected_len = builder.constant(F::from_canonical_u64(expected_len));
+    builder.connect(value, expected_value);
+    builder.connect(len, expected_len);
+
+    let felts: Vec<F> = bytes.iter().map(|&b| F::from_canonical_u8(b)).collect();
+    pw.set_target_arr(&targets, &felts)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn decode_compact_single_byte_mode() {
+    run_decode_compact(&[0x00], 0, 1).unwrap();
+    run_decode_compact(&[0x04], 1, 1).unwrap();
+    run_decode_compact(&[0xfc], 63, 1).unwrap();
+}
+
+#[test]
+fn decode_compact_two_byte_mode() {
+    run_decode_compact(&[0x15, 0x01], 69, 2).unwrap();
+    run_decode_compact(&[0xfd, 0xff], 16383, 2).unwrap();
+}
+
+#[test]
+fn decode_compact_four_byte_mode() {
+    run_decode_compact(&[0x02, 0x00, 0x01, 0x00], 16384, 4).unwrap();
+    run_decode_compact(&[0xfe, 0xff, 0xff, 0xff], (1 << 30) - 1, 4).unwrap();
+}
+
+#[test]
+fn decode_compact_big_integer_mode() {
+    run_decode_compact(&[0x03, 0x00, 0x00, 0x00, 0x40], 1 << 30, 5).unwrap();
+    run_decode_compact(&[0x07, 0x00, 0x00, 0x00, 0x00, 0x01], 1 << 32, 6).unwrap();
+}
+
+#[test]
+fn decode_compact_ignores_trailing_bytes() {
+    // Bytes past the encoded length must not leak into the decoded value.
+    run_decode_compact(&[0x04, 0xff, 0xff, 0xff, 0xff, 0xff], 1, 1).unwrap();
+    run_decode_compact(&[0x03, 0x01, 0x00, 0x00, 0x00, 0xff], 1, 5).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn decode_compact_wrong_value_fails() {
+    run_decode_compact(&[0x15, 0x01], 70, 2).unwrap();
+}
+
+#[cfg(test)]
+fn run_hash_var_len(data: &[u64], len: usize) -> anyhow::Result<HashOut<F>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let data_targets = builder.add_virtual_targets(data.len());
+    let len_target = builder.add_virtual_target();
+    let hash = hash_var_len::<F, D, PoseidonHash>(&mut builder, &data_targets, len_target);
+    builder.register_public_inputs(&hash.elements);
+
+    let felts: Vec<F> = data.iter().map(|&x| F::from_canonical_u64(x)).collect();
+    pw.set_target_arr(&data_targets, &felts)?;
+    pw.set_target(len_target, F::from_canonical_usize(len))?;
+    let proof = crate::circuit_helpers::build_and_prove_test(builder, pw)?;
+
+    Ok(HashOut::from_partial(&proof.public_inputs))
+}
+
+#[test]
+fn hash_var_len_matches_native_hash() {
+    let hash = run_hash_var_len(&[1, 2, 3, 4], 3).unwrap();
+
+    let expected = PoseidonHash::hash_no_pad(&[
+        F::from_canonical_u64(3),
+        F::from_canonical_u64(1),
+        F::from_canonical_u64(2),
+        F::from_canonical_u64(3),
+        F::ZERO,
+    ]);
+    assert_eq!(hash, expected);
+}
+
+#[test]
+fn hash_var_len_differs_by_length() {
+    // Both inputs share the same padded form, so only the length tells them apart.
+    let short = run_hash_var_len(&[1, 2, 0, 0], 2).unwrap();
+    let long = run_hash_var_len(&[1, 2, 0, 0], 3).unwrap();
+    assert_ne!(short, long);
+}
+
+#[test]
+fn hash_var_len_ignores_elements_past_len() {
+    let clean = run_hash_var_len(&[1, 2, 0, 0], 2).unwrap();
+    let dirty = run_hash_var_len(&[1, 2, 7, 9], 2).unwrap();
+    assert_eq!(clean, dirty);
+}
+
+#[cfg(test)]
+fn run_is_zero_hash(
+    hash: [u64; 4],
+    expected: bool,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let hash_target = builder.add_virtual_hash();
+    let is_zero = is_zero_hash(&mut builder, hash_target);
+
+    let expected = builder.constant_bool(expected);
+    builder.connect(is_zero.target, expected.target);
+
+    pw.set_hash_target(hash_target, HashOut::from(hash.map(F::from_canonical_u64)))?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn is_zero_hash_all_zero() {
+    run_is_zero_hash([0, 0, 0, 0], true).unwrap();
+}
+
+#[test]
+fn is_zero_hash_non_zero() {
+    run_is_zero_hash([1, 2, 3, 4], false).unwrap();
+    // A single non-zero element is enough.
+    run_is_zero_hash([0, 0, 0, 1], false).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn is_zero_hash_wrong_result_fails() {
+    run_is_zero_hash([0, 0, 0, 0], false).unwrap();
+}
+
+#[cfg(test)]
+fn run_is_equal_hash(
+    a: [u64; 4],
+    b: [u64; 4],
+    expected: bool,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let a_target = builder.add_virtual_hash();
+    let b_target = builder.add_virtual_hash();
+    let is_equal = is_equal_hash(&mut builder, a_target, b_target);
+
+    let expected = builder.constant_bool(expected);
+    builder.connect(is_equal.target, expected.target);
+
+    pw.set_hash_target(a_target, HashOut::from(a.map(F::from_canonical_u64)))?;
+    pw.set_hash_target(b_target, HashOut::from(b.map(F::from_canonical_u64)))?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn is_equal_hash_equal() {
+    run_is_equal_hash([1, 2, 3, 4], [1, 2, 3, 4], true).unwrap();
+}
+
+#[test]
+fn is_equal_hash_unequal() {
+    run_is_equal_hash([1, 2, 3, 4], [4, 3, 2, 1], false).unwrap();
+    // A single differing element is enough.
+    run_is_equal_hash([1, 2, 3, 4], [1, 2, 3, 5], false).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn is_equal_hash_wrong_result_fails() {
+    run_is_equal_hash([1, 2, 3, 4], [1, 2, 3, 4], false).unwrap();
+}
+
+#[test]
+fn canonical_le_bytes_of_felts() {
+    for value in [0, 1, 0x0102030405060708, F::ORDER - 1] {
+        let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+        let felt_target = builder.add_virtual_target();
+        let byte_targets = canonical_le_bytes(&mut builder, felt_target);
+        for (target, byte) in byte_targets.into_iter().zip(value.to_le_bytes()) {
+            let expected = builder.constant(F::from_canonical_u8(byte));
+            builder.connect(target, expected);
+        }
+
+        pw.set_target(felt_target, F::from_canonical_u64(value))
+            .unwrap();
+        crate::circuit_helpers::build_and_prove_test(builder, pw).unwrap();
+    }
+}
+
+#[cfg(test)]
+fn run_connect_hash_constant(
+    storage_proof: StorageProof,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = StorageProofTargets::new(&mut builder);
+    StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+
+    let pinned_root = bytes_to_felts(&default_root_hash()).try_into().unwrap();
+    connect_hash_constant(&mut builder, targets.root_hash, pinned_root);
+
+    storage_proof.fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn connect_hash_constant_accepts_pinned_root() {
+    run_connect_hash_constant(StorageProof::test_inputs()).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn connect_hash_constant_rejects_other_root() {
+    run_connect_hash_constant(fixtures::single_leaf_proof()).unwrap();
+}
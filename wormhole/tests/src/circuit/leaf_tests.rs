@@ -0,0 +1,111 @@
+use plonky2::hash::poseidon::PoseidonHash;
+use test_helpers::storage_proof::{storage_proof_for_leaf, TestInputs};
+use wormhole_circuit::{
+    codec::FieldElementCodec,
+    inputs::CircuitInputs,
+    leaf::{leaf_inputs_num_felts, LeafInputs, LEAF_INPUTS_NUM_FELTS, MAX_FUNDING_ACCOUNTS},
+    storage_proof::{StorageProof, StorageProofTargets},
+    substrate_account::SubstrateAccount,
+};
+use zk_circuits_common::circuit::CircuitFragment;
+
+#[cfg(test)]
+fn pooled_leaf() -> LeafInputs {
+    let mut leaf = LeafInputs::from(&CircuitInputs::test_inputs());
+    leaf.funding_accounts = vec![
+        SubstrateAccount::new(&[11u8; 32]).unwrap(),
+        SubstrateAccount::new(&[12u8; 32]).unwrap(),
+    ];
+    leaf
+}
+
+#[test]
+fn num_felts_matches_field_elements() {
+    let leaf = LeafInputs::from(&CircuitInputs::test_inputs());
+    assert_eq!(leaf.to_field_elements().len(), LEAF_INPUTS_NUM_FELTS);
+    // The nonce, the funding account, the to_account and the funding amount.
+    assert_eq!(LEAF_INPUTS_NUM_FELTS, 1 + 4 + 4 + 2);
+
+    let pooled = pooled_leaf();
+    assert_eq!(pooled.to_field_elements().len(), leaf_inputs_num_felts(2));
+    assert_eq!(leaf_inputs_num_felts(2), LEAF_INPUTS_NUM_FELTS + 2 * 4);
+}
+
+#[test]
+fn field_elements_round_trip() {
+    let leaf = LeafInputs::from(&CircuitInputs::test_inputs());
+    let decoded = LeafInputs::from_field_elements(&leaf.to_field_elements()).unwrap();
+    assert_eq!(decoded, leaf);
+
+    let pooled = pooled_leaf();
+    let decoded = LeafInputs::from_field_elements(&pooled.to_field_elements()).unwrap();
+    assert_eq!(decoded, pooled);
+
+    let err = LeafInputs::from_field_elements(&leaf.to_field_elements()[1..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected 11 leaf input field elements, plus 4 per additional funding account up to 8 accounts, got: 10"
+    );
+    let err = LeafInputs::from_field_elements(&pooled.to_field_elements()[1..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected 11 leaf input field elements, plus 4 per additional funding account up to 8 accounts, got: 18"
+    );
+}
+
+#[test]
+fn from_field_elements_caps_funding_accounts() {
+    let mut leaf = LeafInputs::from(&CircuitInputs::test_inputs());
+    leaf.funding_accounts = vec![leaf.funding_account; MAX_FUNDING_ACCOUNTS + 1];
+    assert!(LeafInputs::from_field_elements(&leaf.to_field_elements()).is_err());
+
+    leaf.funding_accounts.pop();
+    LeafInputs::from_field_elements(&leaf.to_field_elements()).unwrap();
+}
+
+#[test]
+fn funding_accounts_change_leaf_hash() {
+    let single = LeafInputs::from(&CircuitInputs::test_inputs());
+    let pooled = pooled_leaf();
+    assert_ne!(single.hash(), pooled.hash());
+
+    // The accounts are hashed in order, as a list rather than a set.
+    let mut reordered = pooled.clone();
+    reordered.funding_accounts.reverse();
+    assert_ne!(reordered.hash(), pooled.hash());
+}
+
+#[test]
+fn pooled_leaf_proof_passes() {
+    let leaf = pooled_leaf();
+    let (nodes, root_hash) = storage_proof_for_leaf(leaf.hash());
+    let proof = StorageProof::new(&nodes, root_hash, leaf.funding_amount)
+        .unwrap()
+        .with_leaf_inputs(leaf)
+        .unwrap();
+
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = StorageProofTargets::with_funding_accounts(&mut builder, 2);
+    StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+    proof.fill_targets(&mut pw, targets).unwrap();
+    crate::circuit_helpers::build_and_prove_test(builder, pw).unwrap();
+}
+
+#[test]
+fn funding_account_count_must_match_circuit() {
+    let leaf = pooled_leaf();
+    let (nodes, root_hash) = storage_proof_for_leaf(leaf.hash());
+    let proof = StorageProof::new(&nodes, root_hash, leaf.funding_amount)
+        .unwrap()
+        .with_leaf_inputs(leaf)
+        .unwrap();
+
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = StorageProofTargets::with_funding_accounts(&mut builder, 1);
+    StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+    let err = proof.fill_targets(&mut pw, targets).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the circuit binds 1 additional funding accounts, but the leaf has 2"
+    );
+}
@@ -1,10 +1,34 @@
 #[cfg(test)]
+pub mod blake2_tests;
+#[cfg(test)]
+pub mod blinded_key_tests;
+#[cfg(test)]
+pub mod blocklist_tests;
+#[cfg(test)]
+pub mod debug_dump_tests;
+#[cfg(test)]
+pub mod evm_account_tests;
+#[cfg(test)]
+pub mod fragment_tests;
+#[cfg(test)]
+pub mod gadgets_tests;
+#[cfg(test)]
+pub mod leaf_tests;
+#[cfg(test)]
+pub mod nested_storage_proof_tests;
+#[cfg(test)]
 pub mod nullifier_tests;
 #[cfg(test)]
+pub mod storage_key_tests;
+#[cfg(test)]
 pub mod storage_proof_tests;
 #[cfg(test)]
 pub mod substrate_account_tests;
 #[cfg(test)]
+pub mod system_account_tests;
+#[cfg(test)]
+pub mod timestamp_tests;
+#[cfg(test)]
 pub mod unspendable_account_tests;
 #[cfg(test)]
 pub mod utils_tests;
@@ -0,0 +1,76 @@
+use plonky2::{
+    field::types::Field,
+    hash::poseidon::PoseidonHash,
+    iop::witness::WitnessWrite,
+    plonk::{config::Hasher, proof::ProofWithPublicInputs},
+};
+use wormhole_circuit::nested_storage_proof::{NestedStorageProof, NestedStorageProofTargets};
+use wormhole_circuit::storage_proof::{StorageProof, PROOF_NODE_MAX_SIZE_F};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, u128_to_felts};
+
+use test_helpers::storage_proof::{default_root_hash, TestInputs, DEFAULT_FUNDING_AMOUNT};
+
+#[cfg(test)]
+fn run_test(nested: &NestedStorageProof) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = NestedStorageProofTargets::new(&mut builder);
+    NestedStorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+
+    nested.fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+/// A single node outer proof, whose leaf node holds `value` after an 8-byte prefix.
+fn outer_proof_with_value(value: [u8; 32]) -> StorageProof {
+    let mut leaf = vec![0x42u8; 8];
+    leaf.extend_from_slice(&value);
+
+    let mut leaf_felts = bytes_to_felts(&leaf);
+    leaf_felts.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
+    let root_hash: [u8; 32] = felts_to_bytes(&PoseidonHash::hash_no_pad(&leaf_felts).elements)
+        .try_into()
+        .unwrap();
+
+    StorageProof::from_raw_nodes(&[leaf], root_hash, 0).unwrap()
+}
+
+#[test]
+fn outer_leaf_value_is_inner_root() {
+    let outer = outer_proof_with_value(default_root_hash());
+    let outer_root = outer.root_hash;
+    let nested = NestedStorageProof::new(outer, StorageProof::test_inputs()).unwrap();
+
+    let proof = run_test(&nested).unwrap();
+    let mut expected_public_inputs = outer_root.to_vec();
+    expected_public_inputs.extend(u128_to_felts(DEFAULT_FUNDING_AMOUNT));
+    assert_eq!(proof.public_inputs, expected_public_inputs);
+}
+
+#[test]
+fn outer_leaf_without_inner_root_is_rejected() {
+    let outer = outer_proof_with_value([7u8; 32]);
+    let result = NestedStorageProof::new(outer, StorageProof::test_inputs());
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "outer leaf does not contain the inner root"
+    );
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn outer_leaf_value_must_match_inner_root() {
+    // Fill the fragments directly, bypassing the off-circuit check of `NestedStorageProof::new`.
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = NestedStorageProofTargets::new(&mut builder);
+    NestedStorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+
+    outer_proof_with_value([7u8; 32])
+        .fill_targets(&mut pw, targets.outer)
+        .unwrap();
+    StorageProof::test_inputs()
+        .fill_targets(&mut pw, targets.inner)
+        .unwrap();
+    pw.set_target(targets.value_offset, F::ONE).unwrap();
+    crate::circuit_helpers::build_and_prove_test(builder, pw).unwrap();
+}
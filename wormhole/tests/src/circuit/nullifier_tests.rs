@@ -93,3 +93,29 @@ fn codec_empty_elements() {
         "Expected 13 field elements for Nullifier, got: 0"
     );
 }
+
+#[test]
+fn other_funding_account_fails_proof() {
+    let nullifier = Nullifier::test_inputs();
+
+    // Keep the public nullifier hash, but claim another funding account for it.
+    let mut elements = nullifier.to_field_elements();
+    elements[9] += F::ONE;
+    let tampered = Nullifier::from_field_elements(&elements).unwrap();
+
+    let res = run_test(&tampered);
+    assert!(res.is_err());
+}
+
+#[test]
+fn other_funding_nonce_fails_proof() {
+    let nullifier = Nullifier::test_inputs();
+
+    // Keep the public nullifier hash, but claim another funding nonce for it.
+    let mut elements = nullifier.to_field_elements();
+    elements[8] += F::ONE;
+    let tampered = Nullifier::from_field_elements(&elements).unwrap();
+
+    let res = run_test(&tampered);
+    assert!(res.is_err());
+}
@@ -0,0 +1,63 @@
+use wormhole_circuit::{
+    storage_key::{compute_storage_key, StorageHasher},
+    system_account::SYSTEM_ACCOUNT_KEY_PREFIX,
+};
+
+/// The account id of the well-known `//Alice` development account.
+#[cfg(test)]
+const ALICE: &str = "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+#[test]
+fn plain_storage_value_key() {
+    let key = compute_storage_key("Timestamp", "Now", &[], StorageHasher::Identity);
+    assert_eq!(
+        key,
+        "0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb"
+    );
+}
+
+#[test]
+fn system_account_key() {
+    let alice = hex::decode(ALICE).unwrap();
+    let key = compute_storage_key("System", "Account", &alice, StorageHasher::Blake2_128Concat);
+    assert_eq!(
+        key,
+        "0x26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9\
+         de1e86a9a8c739864cf3cc5ec2bea59fd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+    );
+    assert!(key.starts_with(&format!("0x{}", hex::encode(SYSTEM_ACCOUNT_KEY_PREFIX))));
+}
+
+#[test]
+fn twox_64_concat_map_key() {
+    // `System::BlockHash` of the genesis block, keyed by the SCALE encoded block number.
+    let key = compute_storage_key(
+        "System",
+        "BlockHash",
+        &0u32.to_le_bytes(),
+        StorageHasher::Twox64Concat,
+    );
+    assert_eq!(
+        key,
+        "0x26aa394eea5630e07c48ae0c9558cef7a44704b568d21667356a5a050c118746b4def25cfda6ef3a00000000"
+    );
+}
+
+#[test]
+fn concat_hashers_keep_the_key() {
+    let alice = hex::decode(ALICE).unwrap();
+    assert_eq!(
+        StorageHasher::Blake2_128Concat.hash(&alice)[..16],
+        StorageHasher::Blake2_128.hash(&alice)[..]
+    );
+    assert_eq!(
+        StorageHasher::Blake2_128Concat.hash(&alice)[16..],
+        alice[..]
+    );
+    assert_eq!(StorageHasher::Blake2_256.hash(&alice).len(), 32);
+    assert_eq!(StorageHasher::Twox256.hash(&alice).len(), 32);
+    assert_eq!(
+        StorageHasher::Twox256.hash(&alice)[..16],
+        StorageHasher::Twox128.hash(&alice)[..]
+    );
+}
@@ -1,6 +1,8 @@
+use std::collections::{BTreeMap, HashSet};
+
 use plonky2::{field::types::Field, plonk::proof::ProofWithPublicInputs};
 use wormhole_circuit::{
-    codec::FieldElementCodec,
+    codec::{ByteCodec, FieldElementCodec},
     substrate_account::{ExitAccountTargets, SubstrateAccount},
 };
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
@@ -189,3 +191,44 @@ fn codec_different_byte_patterns() {
     let recovered_varied = SubstrateAccount::from_field_elements(&field_elements_varied).unwrap();
     assert_eq!(account_varied, recovered_varied);
 }
+
+#[test]
+fn from_byte_array_round_trip() {
+    let address = [7u8; 32];
+    let account = SubstrateAccount::from(address);
+    assert_eq!(account.as_ref(), &address);
+    assert_eq!(account, SubstrateAccount::new(&address).unwrap());
+    assert_eq!(account.to_bytes(), address.to_vec());
+
+    let recovered: [u8; 32] = account.as_ref().try_into().unwrap();
+    assert_eq!(recovered, address);
+}
+
+#[test]
+fn accounts_as_map_keys() {
+    let accounts: Vec<SubstrateAccount> = [3u8, 1, 2]
+        .map(|byte| SubstrateAccount::from([byte; 32]))
+        .to_vec();
+
+    let map: BTreeMap<SubstrateAccount, usize> = accounts.iter().copied().zip(0..).collect();
+    for (i, account) in accounts.iter().enumerate() {
+        assert_eq!(map.get(account), Some(&i));
+    }
+
+    // Accounts are ordered by their bytes.
+    let sorted: Vec<_> = map.keys().map(|account| account.as_ref()[0]).collect();
+    assert_eq!(sorted, vec![1, 2, 3]);
+
+    let set: HashSet<SubstrateAccount> = accounts.iter().copied().collect();
+    assert!(set.contains(&SubstrateAccount::from([2u8; 32])));
+    assert!(!set.contains(&SubstrateAccount::from([4u8; 32])));
+}
+
+#[test]
+fn num_felts_matches_field_elements() {
+    let account = SubstrateAccount::from([7u8; 32]);
+    assert_eq!(
+        account.to_field_elements().len(),
+        SubstrateAccount::NUM_FELTS
+    );
+}
@@ -0,0 +1,249 @@
+use plonky2::{
+    field::types::Field,
+    hash::poseidon::PoseidonHash,
+    iop::witness::WitnessWrite,
+    plonk::{config::Hasher, proof::ProofWithPublicInputs},
+};
+use test_helpers::storage_proof::{
+    default_root_hash, default_storage_proof, storage_proof_ending_with,
+};
+use wormhole_circuit::{
+    codec::{ByteCodec, FieldElementCodec},
+    storage_proof::{StorageProof, StorageProofTargets},
+    system_account::{
+        bind_system_account_key, system_account_key, SystemAccountLeaf,
+        SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES, SYSTEM_ACCOUNT_LEAF_NUM_FELTS,
+    },
+};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, u128_to_felts};
+
+/// The `System::Account` value of a development account at genesis of a `--dev` node-template
+/// chain. Its decoded values are fixed by the chain spec rather than by this encoding: the genesis
+/// build of `pallet_balances` endows the account with `1 << 60`, adds a single provider and
+/// leaves the default "new logic" flag (`1 << 127`) set, while the nonce is still 0.
+#[cfg(test)]
+const ACCOUNT_INFO: &str = "0000000000000000010000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000080";
+
+/// The genesis endowment of the development accounts of the node-template chain spec.
+#[cfg(test)]
+const DEV_ENDOWMENT: u128 = 1 << 60;
+
+#[test]
+fn decodes_account_info() {
+    let bytes = hex::decode(ACCOUNT_INFO).unwrap();
+    let leaf = SystemAccountLeaf::from_bytes(&bytes).unwrap();
+    assert_eq!(leaf.nonce, 0);
+    assert_eq!(leaf.consumers, 0);
+    assert_eq!(leaf.providers, 1);
+    assert_eq!(leaf.sufficients, 0);
+    assert_eq!(leaf.free, DEV_ENDOWMENT);
+    assert_eq!(leaf.reserved, 0);
+    assert_eq!(leaf.frozen, 0);
+    assert_eq!(leaf.flags, 1 << 127);
+}
+
+#[test]
+fn encodes_account_info() {
+    let leaf = SystemAccountLeaf {
+        providers: 1,
+        free: DEV_ENDOWMENT,
+        flags: 1 << 127,
+        ..Default::default()
+    };
+    assert_eq!(hex::encode(leaf.to_bytes()), ACCOUNT_INFO);
+}
+
+#[test]
+fn leaf_hash_matches_raw_storage_value() {
+    let bytes = hex::decode(ACCOUNT_INFO).unwrap();
+    let leaf = SystemAccountLeaf::from_bytes(&bytes).unwrap();
+
+    let felts = leaf.to_field_elements();
+    assert_eq!(felts.len(), SYSTEM_ACCOUNT_LEAF_NUM_FELTS);
+    assert_eq!(felts, bytes_to_felts(&bytes));
+    assert_eq!(
+        leaf.hash(),
+        PoseidonHash::hash_no_pad(&bytes_to_felts(&bytes)).elements
+    );
+}
+
+/// The padded and unpadded hashes of `ACCOUNT_INFO`, computed with an independent Poseidon
+/// implementation checked against the plonky2 permutation test vectors.
+#[cfg(test)]
+const ACCOUNT_INFO_PADDED_HASH: [u64; 4] = [
+    14847353722349924575,
+    10252694749644394735,
+    4005544165996815360,
+    9898354293671286298,
+];
+#[cfg(test)]
+const ACCOUNT_INFO_HASH: [u64; 4] = [
+    5876200993594067102,
+    18224565241962934868,
+    5079410396710381091,
+    4826399134271792634,
+];
+
+#[test]
+fn padded_leaf_hash_matches_known_answer() {
+    let bytes = hex::decode(ACCOUNT_INFO).unwrap();
+    let leaf = SystemAccountLeaf::from_bytes(&bytes).unwrap();
+
+    assert_eq!(
+        leaf.hash_with_padding(true),
+        ACCOUNT_INFO_PADDED_HASH.map(F::from_canonical_u64)
+    );
+    assert_eq!(leaf.hash(), ACCOUNT_INFO_HASH.map(F::from_canonical_u64));
+    assert_eq!(leaf.hash_with_padding(false), leaf.hash());
+}
+
+#[test]
+fn field_elements_round_trip() {
+    let bytes = hex::decode(ACCOUNT_INFO).unwrap();
+    let leaf = SystemAccountLeaf::from_bytes(&bytes).unwrap();
+    let recovered = SystemAccountLeaf::from_field_elements(&leaf.to_field_elements()).unwrap();
+    assert_eq!(leaf, recovered);
+}
+
+#[test]
+fn codec_invalid_length() {
+    let result = SystemAccountLeaf::from_bytes(&[0u8; 79]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Expected 80 bytes for SystemAccountLeaf, got: 79"
+    );
+}
+
+/// The `System::Account` storage key of Alice's well-known development account.
+#[cfg(test)]
+const ALICE_ACCOUNT_KEY: &str = "26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9de1e86a9a8c739864cf3cc5ec2bea59fd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+/// Alice's well-known development account id.
+#[cfg(test)]
+const ALICE: &str = "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+#[test]
+fn reconstructs_system_account_key() {
+    let alice = hex::decode(ALICE).unwrap();
+    let expected = hex::decode(ALICE_ACCOUNT_KEY).unwrap();
+
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let account = builder.add_virtual_targets(32);
+    let key = system_account_key(&mut builder, &account);
+    assert_eq!(key.len(), expected.len());
+    for (&target, &byte) in key.iter().zip(expected.iter()) {
+        let byte = builder.constant(F::from_canonical_u8(byte));
+        builder.connect(target, byte);
+    }
+
+    let felts: Vec<F> = alice.iter().map(|&b| F::from_canonical_u8(b)).collect();
+    pw.set_target_arr(&account, &felts).unwrap();
+    crate::circuit_helpers::build_and_prove_test(builder, pw).unwrap();
+}
+
+#[test]
+fn storage_proof_from_account_info() {
+    let bytes = hex::decode(ACCOUNT_INFO).unwrap();
+    let leaf = SystemAccountLeaf::from_bytes(&bytes).unwrap();
+    assert_eq!(leaf.nonce, 0);
+    assert_eq!(leaf.free, DEV_ENDOWMENT);
+
+    let root_hash = bytes_to_felts(&default_root_hash()).try_into().unwrap();
+    let proof =
+        StorageProof::from_account_info_scale(&default_storage_proof(), root_hash, &bytes).unwrap();
+    let expected =
+        StorageProof::from_parts(&default_storage_proof(), root_hash, leaf.free).unwrap();
+    assert_eq!(proof, expected);
+    assert_eq!(proof.nonce(), 0);
+    assert_eq!(
+        proof.expected_public_inputs()[..2],
+        u128_to_felts(DEV_ENDOWMENT)
+    );
+
+    // The same account after it sent 5 transactions.
+    let mut used_bytes = bytes.clone();
+    used_bytes[..4].copy_from_slice(&5u32.to_le_bytes());
+    let proof =
+        StorageProof::from_account_info_scale(&default_storage_proof(), root_hash, &used_bytes)
+            .unwrap();
+    assert_eq!(proof.nonce(), 5);
+    assert_eq!(
+        proof.expected_public_inputs()[..2],
+        u128_to_felts(DEV_ENDOWMENT)
+    );
+
+    let err =
+        StorageProof::from_account_info_scale(&default_storage_proof(), root_hash, &bytes[1..])
+            .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected 80 bytes for SystemAccountLeaf, got: 79"
+    );
+}
+
+/// A leaf node holding Alice's `System::Account` value, 69 nibbles below the root: the header of
+/// a leaf with a hashed value and 91 partial key nibbles, the odd leading nibble, the remaining key
+/// bytes, then the hash of the value, which is where the node is split.
+#[cfg(test)]
+fn alice_leaf_node() -> (Vec<u8>, Vec<u8>) {
+    let key = hex::decode(ALICE_ACCOUNT_KEY).unwrap();
+    let value = SystemAccountLeaf::from_bytes(&hex::decode(ACCOUNT_INFO).unwrap()).unwrap();
+
+    let mut left = vec![0x3f, 91 - 31, key[34] & 0x0f];
+    left.extend_from_slice(&key[35..]);
+    (left, felts_to_bytes(&value.hash()))
+}
+
+#[cfg(test)]
+fn run_key_binding_test(
+    proof: &StorageProof,
+    account: &[u8],
+    offset: usize,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let targets = StorageProofTargets::new(&mut builder);
+    StorageProof::<PoseidonHash>::circuit(&targets, &mut builder);
+    let account_targets = builder.add_virtual_targets(32);
+    let offset_target = builder.add_virtual_target();
+    bind_system_account_key(&mut builder, &targets, &account_targets, offset_target);
+
+    let felts: Vec<F> = account.iter().map(|&b| F::from_canonical_u8(b)).collect();
+    pw.set_target_arr(&account_targets, &felts)?;
+    pw.set_target(offset_target, F::from_canonical_usize(offset))?;
+    proof.fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[cfg(test)]
+fn alice_leaf_proof() -> (StorageProof, usize) {
+    let key = hex::decode(ALICE_ACCOUNT_KEY).unwrap();
+    let (nodes, root_hash) = storage_proof_ending_with(alice_leaf_node());
+    let proof = StorageProof::new(&nodes, root_hash, 0).unwrap();
+    let offset = proof
+        .last_node_offset(&key[key.len() - SYSTEM_ACCOUNT_KEY_SUFFIX_NUM_BYTES..])
+        .unwrap();
+    (proof, offset)
+}
+
+#[test]
+fn system_account_key_bound_to_leaf_node() {
+    let (proof, offset) = alice_leaf_proof();
+    // The suffix is the end of the partial key, after the header, the odd nibble, and the key
+    // bytes 35 to 40.
+    assert_eq!(offset, 3 + 5);
+    run_key_binding_test(&proof, &hex::decode(ALICE).unwrap(), offset).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn other_account_key_not_bound_to_leaf_node() {
+    let (proof, offset) = alice_leaf_proof();
+    run_key_binding_test(&proof, &[1u8; 32], offset).unwrap();
+}
+
+#[test]
+fn last_node_offset_of_missing_bytes() {
+    let (proof, _) = alice_leaf_proof();
+    assert_eq!(proof.last_node_offset(&[1u8; 32]), None);
+}
@@ -0,0 +1,170 @@
+use std::panic;
+
+use plonky2::{
+    field::types::Field, hash::poseidon::PoseidonHash, iop::witness::WitnessWrite,
+    plonk::proof::ProofWithPublicInputs,
+};
+use test_helpers::storage_proof::storage_proof_ending_with;
+use wormhole_circuit::{
+    storage_proof::{StorageProof, StorageProofTargets},
+    timestamp::{TimestampRange, TimestampRangeTargets},
+};
+use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+
+/// 2024-01-01T00:00:00Z in milliseconds.
+#[cfg(test)]
+const MIN: u64 = 1_704_067_200_000;
+/// 2025-01-01T00:00:00Z in milliseconds.
+#[cfg(test)]
+const MAX: u64 = 1_735_689_600_000;
+
+#[cfg(test)]
+fn run_test(range: TimestampRange) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let timestamp = builder.add_virtual_target();
+    let targets = TimestampRangeTargets::new(&mut builder, timestamp);
+    TimestampRange::circuit(&targets, &mut builder);
+
+    range.fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn timestamp_inside_range() {
+    // 2024-06-01T00:00:00Z, as stored by `Timestamp::Now`.
+    let leaf_value = 1_717_200_000_000u64.to_le_bytes();
+    let range = TimestampRange::from_leaf(&leaf_value, MIN, MAX).unwrap();
+    let proof = run_test(range).unwrap();
+    assert_eq!(
+        proof.public_inputs,
+        vec![F::from_canonical_u64(MIN), F::from_canonical_u64(MAX)]
+    );
+
+    // The bounds are inclusive.
+    run_test(TimestampRange::new(MIN, MIN, MAX)).unwrap();
+    run_test(TimestampRange::new(MAX, MIN, MAX)).unwrap();
+}
+
+#[test]
+fn timestamp_outside_range() {
+    for timestamp in [MIN - 1, MAX + 1] {
+        let result =
+            panic::catch_unwind(|| run_test(TimestampRange::new(timestamp, MIN, MAX)).unwrap());
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn timestamp_differs_from_bound_target() {
+    // The timestamp target belongs to another fragment, which sets it to the proven value.
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let timestamp = builder.add_virtual_target();
+    let targets = TimestampRangeTargets::new(&mut builder, timestamp);
+    TimestampRange::circuit(&targets, &mut builder);
+
+    pw.set_target(timestamp, F::from_canonical_u64(MAX + 1))
+        .unwrap();
+    TimestampRange::new(MIN, MIN, MAX)
+        .fill_targets(&mut pw, targets)
+        .unwrap();
+}
+
+#[test]
+fn from_leaf_invalid_length() {
+    let result = TimestampRange::from_leaf(&[0u8; 7], MIN, MAX);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Expected 8 bytes for a timestamp leaf, got: 7"
+    );
+}
+
+/// The storage key of `Timestamp::Now`, i.e. `twox_128("Timestamp") ++ twox_128("Now")`.
+#[cfg(test)]
+const TIMESTAMP_NOW_KEY: &str = "f0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb";
+
+/// A storage proof ending with the leaf node of `Timestamp::Now` holding `timestamp`, 32 nibbles
+/// below the root: the header of a leaf with an inline value and 32 partial key nibbles, the
+/// partial key, then the compact length prefix and the bytes of the value.
+#[cfg(test)]
+fn timestamp_storage_proof(timestamp: u64) -> StorageProof {
+    let key = hex::decode(TIMESTAMP_NOW_KEY).unwrap();
+    let mut right = key[16..].to_vec();
+    right.push(8 << 2);
+    right.extend(timestamp.to_le_bytes());
+    let (nodes, root_hash) = storage_proof_ending_with((vec![0x40 | 32], right));
+    StorageProof::new(&nodes, root_hash, 0).unwrap()
+}
+
+#[cfg(test)]
+fn run_storage_proof_test(
+    storage_proof: &StorageProof,
+    range: TimestampRange,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let (mut builder, mut pw) = crate::circuit_helpers::setup_test_builder_and_witness(false);
+    let storage_proof_targets = StorageProofTargets::new(&mut builder);
+    StorageProof::<PoseidonHash>::circuit(&storage_proof_targets, &mut builder);
+    let targets = TimestampRangeTargets::from_storage_proof(&mut builder, &storage_proof_targets);
+    TimestampRange::circuit(&targets, &mut builder);
+
+    storage_proof.fill_targets(&mut pw, storage_proof_targets)?;
+    range.fill_targets(&mut pw, targets)?;
+    crate::circuit_helpers::build_and_prove_test(builder, pw)
+}
+
+#[test]
+fn timestamp_read_from_storage_proof() {
+    let timestamp = 1_717_200_000_000;
+    let storage_proof = timestamp_storage_proof(timestamp);
+    let range = TimestampRange::new(timestamp, MIN, MAX)
+        .with_storage_proof(&storage_proof)
+        .unwrap();
+    // The prefix of the value follows the header and the 16 partial key bytes.
+    assert_eq!(range.value_offset, Some(1 + 16));
+    run_storage_proof_test(&storage_proof, range).unwrap();
+}
+
+#[test]
+fn timestamp_from_storage_proof_outside_range() {
+    let storage_proof = timestamp_storage_proof(MAX + 1);
+    let range = TimestampRange::new(MAX + 1, MIN, MAX)
+        .with_storage_proof(&storage_proof)
+        .unwrap();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        run_storage_proof_test(&storage_proof, range).unwrap()
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn timestamp_differs_from_storage_proof() {
+    // Claim a timestamp within the range at the offset of the one the proof holds.
+    let storage_proof = timestamp_storage_proof(MAX + 1);
+    let mut range = TimestampRange::new(MAX, MIN, MAX);
+    range.value_offset = TimestampRange::new(MAX + 1, MIN, MAX)
+        .with_storage_proof(&storage_proof)
+        .unwrap()
+        .value_offset;
+    run_storage_proof_test(&storage_proof, range).unwrap();
+}
+
+#[test]
+fn with_storage_proof_of_another_timestamp() {
+    let storage_proof = timestamp_storage_proof(MAX);
+    let result = TimestampRange::new(MIN, MIN, MAX).with_storage_proof(&storage_proof);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        format!("last proof node does not hold the timestamp {MIN}")
+    );
+}
+
+#[test]
+fn storage_proof_circuit_requires_timestamp_offset() {
+    let storage_proof = timestamp_storage_proof(MAX);
+    let result = run_storage_proof_test(&storage_proof, TimestampRange::new(MAX, MIN, MAX));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "the circuit reads the timestamp from the storage proof, but it wasn't located"
+    );
+}
@@ -1,9 +1,12 @@
+use std::collections::{BTreeMap, HashSet};
+
 use plonky2::{field::types::Field, plonk::proof::ProofWithPublicInputs};
 use wormhole_circuit::{
     codec::FieldElementCodec,
     unspendable_account::{UnspendableAccount, UnspendableAccountTargets},
 };
 use zk_circuits_common::circuit::{CircuitFragment, C, D, F};
+use zk_circuits_common::utils::felts_to_bytes;
 
 #[cfg(test)]
 const SECRETS: [&str; 5] = [
@@ -110,3 +113,80 @@ fn unspendable_account_codec() {
     let recovered_account = UnspendableAccount::from_field_elements(&field_elements).unwrap();
     assert_eq!(account, recovered_account);
 }
+
+#[test]
+fn from_byte_array_matches_new() {
+    for secret in SECRETS {
+        let secret: [u8; 32] = hex::decode(secret).unwrap().try_into().unwrap();
+        let account = UnspendableAccount::from(secret);
+        assert_eq!(account, UnspendableAccount::new(&secret));
+
+        // The secret can be recovered from the account's field elements.
+        assert_eq!(felts_to_bytes(&account.secret), secret.to_vec());
+    }
+}
+
+#[test]
+fn try_from_hex_address() {
+    let expected = UnspendableAccount::new(&hex::decode(SECRETS[0]).unwrap());
+
+    let bare = UnspendableAccount::try_from(ADDRESSES[0]).unwrap();
+    assert_eq!(bare.account_id, expected.account_id);
+    assert!(bare.secret.is_empty());
+
+    let prefixed = UnspendableAccount::try_from(format!("0x{}", ADDRESSES[0]).as_str()).unwrap();
+    assert_eq!(prefixed, bare);
+}
+
+#[test]
+fn try_from_ss58_address() {
+    let ss58 =
+        UnspendableAccount::try_from("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+    let hex = UnspendableAccount::try_from(
+        "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+    )
+    .unwrap();
+    assert_eq!(ss58, hex);
+}
+
+#[test]
+fn try_from_invalid_address() {
+    // Flipping the last character breaks the SS58 checksum.
+    let result = UnspendableAccount::try_from("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "unspendable address is neither hex nor a valid SS58 address: invalid SS58 address checksum"
+    );
+
+    assert!(UnspendableAccount::try_from("0xnot-hex").is_err());
+    assert!(UnspendableAccount::try_from("not an address").is_err());
+}
+
+#[test]
+fn accounts_as_map_keys() {
+    let accounts: Vec<UnspendableAccount> = SECRETS
+        .iter()
+        .map(|secret| UnspendableAccount::new(&hex::decode(secret).unwrap()))
+        .collect();
+
+    let map: BTreeMap<UnspendableAccount, usize> = accounts.iter().cloned().zip(0..).collect();
+    assert_eq!(map.len(), SECRETS.len());
+    for (i, account) in accounts.iter().enumerate() {
+        assert_eq!(map.get(account), Some(&i));
+    }
+
+    let set: HashSet<UnspendableAccount> = accounts.iter().cloned().collect();
+    let secret = hex::decode(SECRETS[0]).unwrap();
+    assert!(set.contains(&UnspendableAccount::new(&secret)));
+}
+
+#[test]
+fn num_felts_matches_field_elements() {
+    for secret in SECRETS {
+        let account = UnspendableAccount::new(&hex::decode(secret).unwrap());
+        assert_eq!(
+            account.to_field_elements().len(),
+            UnspendableAccount::NUM_FELTS
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use plonky2::field::types::{Field, Field64};
 use zk_circuits_common::circuit::F;
-use zk_circuits_common::utils::{felts_to_u128, u128_to_felts};
+use zk_circuits_common::utils::{
+    decode_compact, felts_to_u128, felts_to_u128_be, format_amount, u128_to_felts,
+    u128_to_felts_be, Amount, Nonce,
+};
 
 // Helper to create F from a u64 for concise test cases
 #[cfg(test)]
@@ -87,3 +90,112 @@ fn test_edge_cases() {
     let result = felts_to_u128(felts);
     assert_eq!(result, 0);
 }
+
+#[test]
+fn test_u128_to_felts_be_round_trip() {
+    let test_cases = [
+        0u128,
+        1u128,
+        0x1234567890abcdefu128,
+        u128::MAX,
+        (1u128 << 64) | 0xabcdefu128,
+        0x0102030405060708090a0b0c0d0e0f10u128,
+    ];
+
+    for num in test_cases {
+        let felts = u128_to_felts_be(num);
+        assert_eq!(felts_to_u128_be(felts), num, "Round trip failed for {num}");
+    }
+}
+
+#[test]
+fn test_u128_to_felts_byte_orders_differ() {
+    // An asymmetric value must not encode the same way in both byte orders.
+    let num = 0x0102030405060708090a0b0c0d0e0f10u128;
+    let felts = u128_to_felts(num);
+    let felts_be = u128_to_felts_be(num);
+    assert_ne!(felts, felts_be);
+
+    assert_eq!(felts, [f(0x0102030405060708), f(0x090a0b0c0d0e0f10)]);
+    assert_eq!(felts_be, [f(0x0807060504030201), f(0x100f0e0d0c0b0a09)]);
+
+    // Decoding with the wrong byte order silently yields a different amount.
+    assert_ne!(felts_to_u128_be(felts), num);
+}
+
+#[test]
+fn decode_compact_modes() {
+    assert_eq!(decode_compact(&[0x00]).unwrap(), (0, 1));
+    assert_eq!(decode_compact(&[0xfc]).unwrap(), (63, 1));
+    assert_eq!(decode_compact(&[0x01, 0x01]).unwrap(), (64, 2));
+    assert_eq!(
+        decode_compact(&[0xfe, 0xff, 0x03, 0x00]).unwrap(),
+        (65535, 4)
+    );
+    assert_eq!(
+        decode_compact(&[0x03, 0x00, 0x00, 0x00, 0x40]).unwrap(),
+        (1 << 30, 5)
+    );
+    assert_eq!(
+        decode_compact(&[0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(),
+        (u64::MAX, 9)
+    );
+    // Trailing bytes are left to the caller.
+    assert_eq!(decode_compact(&[0x04, 0xaa]).unwrap(), (1, 1));
+}
+
+#[test]
+fn decode_compact_invalid() {
+    assert_eq!(
+        decode_compact(&[]).unwrap_err().to_string(),
+        "SCALE compact integer is empty"
+    );
+    assert_eq!(
+        decode_compact(&[0x01]).unwrap_err().to_string(),
+        "SCALE compact integer is truncated"
+    );
+    assert_eq!(
+        decode_compact(&[0x17]).unwrap_err().to_string(),
+        "SCALE compact integer of 9 bytes does not fit in a u64"
+    );
+}
+
+#[test]
+fn format_amount_zero() {
+    assert_eq!(format_amount(0, 12), "0.000000000000");
+    assert_eq!(format_amount(0, 0), "0");
+}
+
+#[test]
+fn format_amount_whole() {
+    assert_eq!(format_amount(3_000_000_000_000, 12), "3.000000000000");
+    assert_eq!(format_amount(42, 0), "42");
+}
+
+#[test]
+fn format_amount_fractional() {
+    assert_eq!(format_amount(1_250_000_000_000, 12), "1.250000000000");
+    assert_eq!(format_amount(5, 3), "0.005");
+    assert_eq!(
+        format_amount(u128::MAX, 18),
+        "340282366920938463463.374607431768211455"
+    );
+    // Decimals beyond the digits of a u128 are padded with zeros.
+    assert_eq!(format_amount(1, 40), format!("0.{}1", "0".repeat(39)));
+}
+
+#[test]
+fn nonce_from_u32_matches_canonical_felt() {
+    for nonce in [0u32, 1, 0xdead_beef, u32::MAX] {
+        assert_eq!(Nonce::from(nonce), Nonce(F::from_canonical_u32(nonce)));
+    }
+}
+
+#[test]
+fn amount_from_u128_matches_u128_to_felts() {
+    for amount in [0u128, 1, 1_000_000_000_000, u64::MAX as u128 + 1, u128::MAX] {
+        let converted = Amount::from(amount);
+        assert_eq!(converted, Amount(u128_to_felts(amount)));
+        assert_eq!(u128::from(converted), felts_to_u128(u128_to_felts(amount)));
+    }
+}
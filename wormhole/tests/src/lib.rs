@@ -1,5 +1,9 @@
 pub mod aggregator;
 pub mod circuit;
 pub mod circuit_helpers;
+#[cfg(feature = "metrics")]
+pub mod metrics_helpers;
 pub mod prover;
+#[cfg(feature = "tracing")]
+pub mod tracing_helpers;
 pub mod verifier;
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+/// Runs `f` with a debugging recorder installed on the current thread and returns the value of
+/// every counter it incremented, by name.
+pub fn record_counters<T>(f: impl FnOnce() -> T) -> (T, HashMap<String, u64>) {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let result = metrics::with_local_recorder(&recorder, f);
+
+    let counters = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter_map(|(key, _, _, value)| match value {
+            DebugValue::Counter(count) => Some((key.key().name().to_string(), count)),
+            _ => None,
+        })
+        .collect();
+    (result, counters)
+}
@@ -1,9 +1,20 @@
 use std::fs;
+use std::sync::Arc;
 
 use plonky2::plonk::circuit_data::CircuitConfig;
-use test_helpers::storage_proof::TestInputs;
-use wormhole_circuit::inputs::{CircuitInputs, PublicCircuitInputs};
+use test_helpers::storage_proof::{storage_proof_for_leaf, TestInputs, DEFAULT_FUNDING_AMOUNT};
+use test_helpers::{fixtures, test_inputs_on_chain};
+use wormhole_circuit::codec::FieldElementCodec;
+use wormhole_circuit::inputs::{
+    decode_public_inputs, extract_chain_id, extract_leaf_public, public_input_layout,
+    CircuitInputs, PublicCircuitInputs, PUBLIC_INPUTS_WITH_TO_ACCOUNT_FELTS_LEN,
+};
+use wormhole_circuit::leaf::LeafInputs;
+use wormhole_circuit::storage_proof::MAX_PROOF_LEN;
+use wormhole_circuit::unspendable_account::UnspendableAccount;
 use wormhole_prover::WormholeProver;
+use wormhole_verifier::WormholeVerifier;
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes, u128_to_felts};
 
 #[cfg(test)]
 const CIRCUIT_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
@@ -15,6 +26,92 @@ fn commit_and_prove() {
     prover.commit(&inputs).unwrap().prove().unwrap();
 }
 
+#[test]
+fn commit_and_prove_with_blocklist() {
+    let forbidden = [UnspendableAccount::new(&[1u8; 32]).account_id];
+    let prover = WormholeProver::with_blocklist(CIRCUIT_CONFIG, &forbidden);
+    let inputs = CircuitInputs::test_inputs();
+    prover.commit(&inputs).unwrap().prove().unwrap();
+}
+
+#[test]
+fn transfer_to_blocklisted_account_fails() {
+    let inputs = CircuitInputs::test_inputs();
+    let forbidden = [
+        UnspendableAccount::new(&[1u8; 32]).account_id,
+        inputs.private.unspendable_account.account_id,
+    ];
+    let prover = WormholeProver::with_blocklist(CIRCUIT_CONFIG, &forbidden);
+
+    let err = prover
+        .commit(&inputs)
+        .unwrap()
+        .prove_catching()
+        .unwrap_err();
+    assert!(err.to_string().contains("set twice with different values"));
+}
+
+#[cfg(test)]
+fn prove_fixture(inputs: CircuitInputs) {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let public_inputs = PublicCircuitInputs::try_from(proof).unwrap();
+    assert_eq!(public_inputs.root_hash, inputs.public.root_hash);
+}
+
+#[test]
+fn deep_inputs_fixture() {
+    let inputs = fixtures::deep_inputs();
+    assert_eq!(inputs.private.storage_proof.len(), MAX_PROOF_LEN);
+    prove_fixture(inputs);
+}
+
+#[test]
+fn single_leaf_inputs_fixture() {
+    let inputs = fixtures::single_leaf_inputs();
+    assert_eq!(inputs.private.storage_proof.len(), 1);
+    prove_fixture(inputs);
+}
+
+#[test]
+fn branch_heavy_inputs_fixture() {
+    let inputs = fixtures::branch_heavy_inputs();
+    assert_eq!(inputs.private.storage_proof.len(), 4);
+    prove_fixture(inputs);
+}
+
+#[test]
+fn chain_id_is_public_input() {
+    let prover = WormholeProver::with_chain_id(CIRCUIT_CONFIG);
+    let inputs = test_inputs_on_chain(7);
+    let proof = prover.commit_on_chain(&inputs, 7).unwrap().prove().unwrap();
+    assert_eq!(extract_chain_id(&proof).unwrap(), 7);
+    WormholeVerifier::with_chain_id(CIRCUIT_CONFIG)
+        .verify(proof)
+        .unwrap();
+}
+
+#[test]
+fn commit_on_other_chain_fails() {
+    let prover = WormholeProver::with_chain_id(CIRCUIT_CONFIG);
+    let inputs = test_inputs_on_chain(7);
+    let err = prover.commit_on_chain(&inputs, 8).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "last proof node does not contain the hash of the leaf inputs"
+    );
+}
+
+#[test]
+fn commit_without_chain_id_fails() {
+    let prover = WormholeProver::with_chain_id(CIRCUIT_CONFIG);
+    let err = prover.commit(&CircuitInputs::test_inputs()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the circuit hashes the leaf inputs in a domain, but the proof has none"
+    );
+}
+
 #[test]
 fn proof_can_be_deserialized() {
     let prover = WormholeProver::new(CIRCUIT_CONFIG);
@@ -24,6 +121,188 @@ fn proof_can_be_deserialized() {
     println!("{:?}", public_inputs);
 }
 
+#[test]
+fn prove_concurrently_with_shared_circuit_data() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+
+    let forks = [prover.fork().unwrap(), prover.fork().unwrap()];
+    // The forks share the circuit data instance of the original prover.
+    assert_eq!(Arc::strong_count(&prover.circuit_data), 3);
+
+    let inputs = &inputs;
+    let proofs: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = forks
+            .into_iter()
+            .map(|fork| scope.spawn(move || fork.commit(inputs).unwrap().prove().unwrap()))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    for proof in proofs {
+        verifier.verify(proof).unwrap();
+    }
+}
+
+#[test]
+fn fork_after_commit_fails() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let prover = prover.commit(&inputs).unwrap();
+    assert_eq!(
+        prover.fork().unwrap_err().to_string(),
+        "prover has already commited to inputs"
+    );
+}
+
+#[test]
+fn prove_with_witness_from_another_prover() {
+    // The witness is generated by one prover, and proven by another one with its own circuit.
+    let witness_generator = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let bytes = witness_generator
+        .commit(&inputs)
+        .unwrap()
+        .witness_bytes()
+        .unwrap();
+
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let proof = prover
+        .commit_witness_bytes(&bytes)
+        .unwrap()
+        .prove()
+        .unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    verifier.verify(proof).unwrap();
+}
+
+#[test]
+fn invalid_witness_bytes_are_rejected() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    assert_eq!(
+        prover.witness_bytes().unwrap_err().to_string(),
+        "prover has not commited to any inputs"
+    );
+
+    let result = prover.fork().unwrap().commit_witness_bytes(&[0u8; 24]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "serialized witness length 24 is not a multiple of 25"
+    );
+
+    let result = prover.commit_witness_bytes(&[2u8; 25]);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "invalid target tag in serialized witness: 2"
+    );
+}
+
+#[test]
+fn public_view_matches_proof_public_inputs() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let expected = inputs.public_view().to_field_elements();
+    assert_eq!(expected, proof.public_inputs);
+}
+
+#[test]
+fn leaf_fields_can_be_extracted_from_proof() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let leaf = extract_leaf_public(&proof).unwrap();
+    assert_eq!(leaf.funding_amount, DEFAULT_FUNDING_AMOUNT);
+    assert_eq!(leaf.exit_account, inputs.public.exit_account);
+    assert_eq!(leaf.to_account, None);
+}
+
+#[test]
+fn public_to_account_can_be_extracted_from_proof() {
+    let prover = WormholeProver::with_public_to_account(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    assert_eq!(
+        proof.public_inputs.len(),
+        PUBLIC_INPUTS_WITH_TO_ACCOUNT_FELTS_LEN
+    );
+
+    let leaf = extract_leaf_public(&proof).unwrap();
+    assert_eq!(leaf.funding_amount, DEFAULT_FUNDING_AMOUNT);
+    assert_eq!(
+        leaf.to_account,
+        Some(inputs.private.unspendable_account.account_id)
+    );
+}
+
+#[test]
+#[should_panic(expected = "set twice with different values")]
+fn leaf_of_another_transfer_fails() {
+    // The storage proof proves a leaf whose funding nonce isn't the one the nullifier spends.
+    let mut inputs = CircuitInputs::test_inputs();
+    inputs.private.funding_nonce += 1;
+    let (storage_proof, root_hash) = storage_proof_for_leaf(LeafInputs::from(&inputs).hash());
+    inputs.private.storage_proof = storage_proof;
+    inputs.public.root_hash = root_hash;
+
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    prover.commit(&inputs).unwrap().prove().unwrap();
+}
+
+#[test]
+fn public_inputs_can_be_decoded_by_name() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let decoded = decode_public_inputs(&proof).unwrap();
+    let names: Vec<&str> = decoded.iter().map(|(name, _)| name.as_str()).collect();
+    let layout_names: Vec<&str> = public_input_layout()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect();
+    assert_eq!(names, layout_names);
+
+    let public = &inputs.public;
+    let expected = [
+        public.nullifier.hash.to_vec(),
+        u128_to_felts(public.funding_amount).to_vec(),
+        bytes_to_felts(&public.root_hash),
+        public.exit_account.to_field_elements(),
+    ];
+    for ((_, felts), expected) in decoded.iter().zip(expected) {
+        assert_eq!(*felts, expected);
+    }
+
+    let mut truncated = proof;
+    truncated.public_inputs.pop();
+    assert!(decode_public_inputs(&truncated).is_err());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn prove_emits_spans() {
+    let inputs = CircuitInputs::test_inputs();
+    let (proof, spans) = crate::tracing_helpers::record_spans(|| {
+        let prover = WormholeProver::new(CIRCUIT_CONFIG);
+        prover.commit(&inputs).unwrap().prove()
+    });
+    proof.unwrap();
+
+    assert_eq!(
+        spans,
+        [
+            "wormhole_prover::build_circuit",
+            "wormhole_prover::fill_witness",
+            "wormhole_prover::prove",
+        ]
+    );
+}
+
 #[test]
 #[ignore = "debug"]
 fn get_public_inputs() {
@@ -61,3 +340,104 @@ fn export_test_proof_zk() {
     let proof_bytes = proof.to_bytes();
     let _ = fs::write(FILE_PATH, proof_bytes);
 }
+
+#[cfg(test)]
+fn split_test_inputs_json() -> (serde_json::Value, serde_json::Value) {
+    let inputs = CircuitInputs::test_inputs();
+    let nullifier = felts_to_bytes(&inputs.public.nullifier.hash);
+    let public = serde_json::json!({
+        "funding_amount": inputs.public.funding_amount.to_string(),
+        "root_hash": format!("0x{}", hex::encode(inputs.public.root_hash)),
+        "exit_account": format!("0x{}", hex::encode(inputs.public.exit_account)),
+        "nullifier": format!("0x{}", hex::encode(nullifier)),
+    });
+    let storage_proof: Vec<[String; 2]> = inputs
+        .private
+        .storage_proof
+        .iter()
+        .map(|(left, right)| [hex::encode(left), hex::encode(right)])
+        .collect();
+    let private = serde_json::json!({
+        "secret": format!("0x{}", hex::encode(&inputs.private.secret)),
+        "funding_nonce": inputs.private.funding_nonce,
+        "funding_account": format!("0x{}", hex::encode(inputs.private.funding_account)),
+        "storage_proof": storage_proof,
+    });
+    (public, private)
+}
+
+#[test]
+fn circuit_inputs_from_public_and_private_json() {
+    let (public, private) = split_test_inputs_json();
+    let inputs = CircuitInputs::from_parts(&public.to_string(), &private.to_string()).unwrap();
+
+    let expected = CircuitInputs::test_inputs();
+    assert_eq!(
+        inputs.public.to_field_elements(),
+        expected.public.to_field_elements()
+    );
+    assert_eq!(inputs.private.secret, expected.private.secret);
+    assert_eq!(inputs.private.storage_proof, expected.private.storage_proof);
+    assert_eq!(
+        inputs.private.unspendable_account,
+        expected.private.unspendable_account
+    );
+
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    prover.commit(&inputs).unwrap().prove().unwrap();
+}
+
+#[test]
+fn circuit_inputs_from_parts_checks_nullifier() {
+    let (mut public, private) = split_test_inputs_json();
+    public["nullifier"] = format!("0x{}", hex::encode([0u8; 32])).into();
+    let err = CircuitInputs::from_parts(&public.to_string(), &private.to_string()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "nullifier doesn't match the private inputs"
+    );
+
+    // The nullifier is optional, as it is derived from the private inputs.
+    public.as_object_mut().unwrap().remove("nullifier");
+    CircuitInputs::from_parts(&public.to_string(), &private.to_string()).unwrap();
+}
+
+#[test]
+fn prove_catching_turns_panics_into_errors() {
+    let mut inputs = CircuitInputs::test_inputs();
+    inputs.public.root_hash = [0u8; 32];
+
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let err = prover
+        .commit(&inputs)
+        .unwrap()
+        .prove_catching()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("set twice with different values"),
+        "unexpected error: {err}"
+    );
+
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    prover.commit(&inputs).unwrap().prove_catching().unwrap();
+}
+
+#[cfg(feature = "timing")]
+#[test]
+fn build_and_prove_durations_are_recorded() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    assert!(!prover.last_build_duration().is_zero());
+    assert_eq!(prover.last_prove_duration(), None);
+
+    let inputs = CircuitInputs::test_inputs();
+    prover
+        .fork()
+        .unwrap()
+        .commit(&inputs)
+        .unwrap()
+        .prove()
+        .unwrap();
+    let prove_duration = prover.last_prove_duration().unwrap();
+    assert!(!prove_duration.is_zero());
+}
@@ -0,0 +1,27 @@
+use std::sync::{Arc, Mutex};
+
+use tracing::{span, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+/// A [`Layer`] that records the `target::name` of every span that is created.
+struct SpanRecorder(Arc<Mutex<Vec<String>>>);
+
+impl<S: Subscriber> Layer<S> for SpanRecorder {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{}::{}", metadata.target(), metadata.name()));
+    }
+}
+
+/// Runs `f` with a subscriber installed and returns the spans it created, in creation order.
+pub fn record_spans<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    let subscriber = Registry::default().with(SpanRecorder(spans.clone()));
+    let result = tracing::subscriber::with_default(subscriber, f);
+    let spans = spans.lock().unwrap().clone();
+    (result, spans)
+}
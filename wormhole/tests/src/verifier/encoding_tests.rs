@@ -0,0 +1,45 @@
+use plonky2::plonk::circuit_data::CircuitConfig;
+use test_helpers::storage_proof::TestInputs;
+use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_prover::WormholeProver;
+use wormhole_verifier::encoding::{
+    proof_from_base64, proof_from_hex, proof_to_base64, proof_to_hex,
+};
+use wormhole_verifier::WormholeVerifier;
+
+#[cfg(test)]
+const CIRCUIT_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
+
+#[test]
+fn base64_round_trip() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+
+    let encoded = proof_to_base64(&proof);
+    assert!(encoded.len() < proof_to_hex(&proof).len());
+
+    let decoded = proof_from_base64(&encoded, &verifier.circuit_data.common).unwrap();
+    assert_eq!(decoded, proof);
+    verifier.verify(decoded).unwrap();
+}
+
+#[test]
+fn hex_round_trip() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+
+    let encoded = format!("0x{}", proof_to_hex(&proof));
+    let decoded = proof_from_hex(&encoded, &verifier.circuit_data.common).unwrap();
+    assert_eq!(decoded, proof);
+}
+
+#[test]
+fn invalid_base64_fails() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let result = proof_from_base64("not base64!", &verifier.circuit_data.common);
+    assert!(result.is_err());
+}
@@ -0,0 +1,98 @@
+use plonky2::plonk::circuit_data::CircuitConfig;
+use test_helpers::storage_proof::TestInputs;
+use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_prover::WormholeProver;
+use wormhole_verifier::header::{
+    deserialize_proof_with_header, serialize_proof_with_header, PROOF_HEADER_LEN,
+};
+use wormhole_verifier::WormholeVerifier;
+
+#[cfg(test)]
+const CIRCUIT_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
+
+#[test]
+fn header_round_trip() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+
+    let bytes = serialize_proof_with_header(&proof, &verifier.circuit_data);
+    assert_eq!(bytes.len(), PROOF_HEADER_LEN + proof.to_bytes().len());
+
+    let decoded = deserialize_proof_with_header(&bytes, &verifier.circuit_data).unwrap();
+    assert_eq!(decoded, proof);
+    verifier.verify(decoded).unwrap();
+}
+
+#[test]
+fn header_digest_mismatch_fails() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+
+    let mut bytes = serialize_proof_with_header(&proof, &verifier.circuit_data);
+    bytes[PROOF_HEADER_LEN - 1] ^= 0xFF;
+
+    let result = deserialize_proof_with_header(&bytes, &verifier.circuit_data);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "proof circuit digest does not match the circuit"
+    );
+}
+
+#[test]
+fn header_mode_mismatch_fails() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let zk_verifier = WormholeVerifier::new(CircuitConfig::standard_recursion_zk_config(), None);
+
+    let bytes = serialize_proof_with_header(&proof, &verifier.circuit_data);
+    let result = deserialize_proof_with_header(&bytes, &zk_verifier.circuit_data);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "proof zero-knowledge mode does not match the circuit"
+    );
+}
+
+#[test]
+fn header_invalid_magic_fails() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let bytes = vec![0u8; PROOF_HEADER_LEN];
+
+    let result = deserialize_proof_with_header(&bytes, &verifier.circuit_data);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "proof header has an invalid magic"
+    );
+}
+
+#[test]
+fn verify_with_header_routes_to_previous_circuit_version() {
+    let inputs = CircuitInputs::test_inputs();
+    let zk_config = CircuitConfig::standard_recursion_zk_config();
+
+    let old_prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let old_proof = old_prover.commit(&inputs).unwrap().prove().unwrap();
+    let old_verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let old_bytes = serialize_proof_with_header(&old_proof, &old_verifier.circuit_data);
+
+    let new_prover = WormholeProver::new(zk_config.clone());
+    let new_proof = new_prover.commit(&inputs).unwrap().prove().unwrap();
+    let verifier = WormholeVerifier::new(zk_config, None);
+    let new_bytes = serialize_proof_with_header(&new_proof, &verifier.circuit_data);
+
+    // Without the previous version, old proofs are rejected.
+    let result = verifier.verify_with_header(&old_bytes);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "no known circuit version matches the proof circuit digest"
+    );
+
+    let verifier = verifier.with_previous_circuit_data(old_verifier.circuit_data);
+    verifier.verify_with_header(&old_bytes).unwrap();
+    verifier.verify_with_header(&new_bytes).unwrap();
+}
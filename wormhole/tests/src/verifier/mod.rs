@@ -1,2 +1,6 @@
 #[cfg(test)]
+pub mod encoding_tests;
+#[cfg(test)]
+pub mod header_tests;
+#[cfg(test)]
 pub mod verifier_tests;
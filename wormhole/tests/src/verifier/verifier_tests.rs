@@ -1,11 +1,16 @@
 use plonky2::plonk::circuit_data::CircuitConfig;
 use plonky2::plonk::proof::ProofWithPublicInputs;
+use std::time::Instant;
 use test_helpers::storage_proof::TestInputs;
+use wormhole_aggregator::{aggregator::WormholeProofAggregator, DEFAULT_NUM_PROOFS_TO_AGGREGATE};
+use wormhole_circuit::circuit::zk_config_with_query_rounds;
 use wormhole_circuit::codec::FieldElementCodec;
-use wormhole_circuit::inputs::CircuitInputs;
+use wormhole_circuit::inputs::{CircuitInputs, PublicCircuitInputs};
 use wormhole_circuit::substrate_account::SubstrateAccount;
+use wormhole_circuit::unspendable_account::UnspendableAccount;
 use wormhole_prover::WormholeProver;
-use wormhole_verifier::WormholeVerifier;
+use wormhole_verifier::{peek_root_hash, ProofKind, ShapeError, WormholeVerifier};
+use zk_circuits_common::utils::felts_to_bytes;
 
 #[cfg(test)]
 const CIRCUIT_CONFIG: CircuitConfig = CircuitConfig::standard_recursion_config();
@@ -20,6 +25,213 @@ fn verify_simple_proof() {
     verifier.verify(proof).unwrap();
 }
 
+#[test]
+fn verify_with_verifier_from_circuit_data() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let shared = WormholeVerifier::from_circuit_data(verifier.circuit_data.clone());
+    assert_eq!(shared.circuit_data, verifier.circuit_data);
+    shared.verify(proof).unwrap();
+}
+
+#[test]
+fn verify_aggregate_proof() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let mut aggregator =
+        WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(CIRCUIT_CONFIG);
+    aggregator.push_proof(proof).unwrap();
+    aggregator.aggregate().unwrap();
+    let aggregate_circuit_data = aggregator.verifier_data();
+    let aggregate_proof = aggregator.prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None)
+        .with_aggregate_circuit_data(aggregate_circuit_data);
+    verifier.verify_aggregate(aggregate_proof).unwrap();
+}
+
+#[test]
+fn classify_single_and_aggregate_proofs() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let mut aggregator =
+        WormholeProofAggregator::<{ DEFAULT_NUM_PROOFS_TO_AGGREGATE }>::new(CIRCUIT_CONFIG);
+    aggregator.push_proof(proof.clone()).unwrap();
+    aggregator.aggregate().unwrap();
+    let aggregate_circuit_data = aggregator.verifier_data();
+    let aggregate_proof = aggregator.prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    assert!(!verifier.is_aggregate_proof(&aggregate_proof));
+
+    let verifier = verifier.with_aggregate_circuit_data(aggregate_circuit_data);
+    assert!(!verifier.is_aggregate_proof(&proof));
+    assert!(verifier.is_aggregate_proof(&aggregate_proof));
+
+    verifier
+        .verify_kind(proof.clone(), ProofKind::Single)
+        .unwrap();
+    verifier
+        .verify_kind(aggregate_proof.clone(), ProofKind::Aggregate)
+        .unwrap();
+    assert_eq!(
+        verifier
+            .verify_kind(proof, ProofKind::Aggregate)
+            .unwrap_err()
+            .to_string(),
+        "expected an aggregate proof, got a single proof"
+    );
+    assert_eq!(
+        verifier
+            .verify_kind(aggregate_proof, ProofKind::Single)
+            .unwrap_err()
+            .to_string(),
+        "expected a single proof, got an aggregate proof"
+    );
+}
+
+#[test]
+fn verify_aggregate_requires_aggregate_circuit_data() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let result = verifier.verify_aggregate(proof);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "verifier has no aggregate circuit data"
+    );
+}
+
+#[test]
+fn verify_with_and_without_randomness() {
+    let inputs = CircuitInputs::test_inputs();
+
+    let deterministic_prover = WormholeProver::new(CIRCUIT_CONFIG).with_randomness(false);
+    let deterministic_proof = deterministic_prover
+        .commit(&inputs)
+        .unwrap()
+        .prove()
+        .unwrap();
+    let randomized_prover = WormholeProver::new(CIRCUIT_CONFIG).with_randomness(true);
+    let randomized_proof = randomized_prover.commit(&inputs).unwrap().prove().unwrap();
+    assert_ne!(deterministic_proof.to_bytes(), randomized_proof.to_bytes());
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None).with_randomness(false);
+    verifier.verify(deterministic_proof).unwrap();
+    let verifier = verifier.with_randomness(true);
+    verifier.verify(randomized_proof).unwrap();
+}
+
+#[test]
+fn estimate_prove_ms_follows_calibration() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+
+    // Calibrate against a proof of the same circuit on this machine.
+    let start = Instant::now();
+    prover.commit(&inputs).unwrap().prove().unwrap();
+    let elapsed = start.elapsed();
+    let ns_per_lde_row_layer = (elapsed.as_nanos() as u64).div_ceil(verifier.lde_row_layers());
+
+    let estimate = verifier.estimate_prove_ms(ns_per_lde_row_layer);
+    assert!(estimate >= elapsed.as_millis() as u64);
+    assert_eq!(verifier.estimate_prove_ms(0), 0);
+    assert_eq!(
+        verifier.estimate_prove_ms(2 * 1_000_000),
+        2 * verifier.lde_row_layers()
+    );
+}
+
+#[test]
+fn security_bits_follow_fri_config() {
+    const MIN_SECURITY_BITS: u32 = 100;
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let security_bits = verifier.security_bits();
+    assert!(
+        (MIN_SECURITY_BITS..=128).contains(&security_bits),
+        "default circuit reports {security_bits} bits"
+    );
+
+    let mut weaker_config = CIRCUIT_CONFIG;
+    weaker_config.fri_config.num_query_rounds -= 4;
+    let weaker = WormholeVerifier::new(weaker_config, None);
+    assert_eq!(
+        weaker.security_bits(),
+        security_bits - 4 * CIRCUIT_CONFIG.fri_config.rate_bits as u32
+    );
+    assert!(weaker.security_bits() < MIN_SECURITY_BITS);
+}
+
+#[test]
+fn more_query_rounds_still_verify() {
+    let default_config = CircuitConfig::standard_recursion_zk_config();
+    let num_query_rounds = default_config.fri_config.num_query_rounds + 12;
+    let config = zk_config_with_query_rounds(num_query_rounds);
+    assert!(config.zero_knowledge);
+
+    let prover = WormholeProver::new(config.clone());
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    assert_eq!(
+        proof.proof.opening_proof.query_round_proofs.len(),
+        num_query_rounds
+    );
+
+    let verifier = WormholeVerifier::new(config, None);
+    verifier.verify(proof).unwrap();
+
+    let default_verifier = WormholeVerifier::new(default_config, None);
+    assert_ne!(
+        verifier.circuit_data.verifier_only.circuit_digest,
+        default_verifier.circuit_data.verifier_only.circuit_digest
+    );
+}
+
+#[test]
+fn verify_to_json_valid_proof() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let output: serde_json::Value =
+        serde_json::from_str(&verifier.verify_to_json(&proof.to_bytes())).unwrap();
+
+    assert_eq!(output["valid"], true);
+    assert_eq!(
+        output["root_hash"],
+        format!("0x{}", hex::encode(inputs.public.root_hash))
+    );
+    assert!(output["error"].is_null());
+}
+
+#[test]
+fn verify_to_json_corrupt_proof() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let mut proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    proof.public_inputs[0].0 ^= 1;
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let output: serde_json::Value =
+        serde_json::from_str(&verifier.verify_to_json(&proof.to_bytes())).unwrap();
+
+    assert_eq!(output["valid"], false);
+    assert!(output["root_hash"].is_null());
+    assert!(output["error"].is_string());
+}
+
 #[test]
 fn cannot_verify_with_modified_exit_account() {
     let prover = WormholeProver::new(CIRCUIT_CONFIG);
@@ -85,3 +297,165 @@ fn cannot_verify_with_modified_proof() {
         }
     }
 }
+
+#[test]
+fn validate_shape_accepts_well_formed_proof() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    verifier.validate_shape(&proof).unwrap();
+}
+
+#[test]
+fn validate_shape_rejects_truncated_public_inputs() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let mut proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    proof.public_inputs.pop();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    assert_eq!(
+        verifier.validate_shape(&proof),
+        Err(ShapeError::PublicInputsLen {
+            expected: 14,
+            got: 13
+        })
+    );
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn prove_and_verify_increment_counters() {
+    use plonky2::field::types::Field;
+    use wormhole_prover::PROOFS_BUILT_COUNTER;
+    use wormhole_verifier::{PROOFS_VERIFIED_COUNTER, VERIFICATION_FAILURES_COUNTER};
+    use zk_circuits_common::circuit::F;
+
+    let inputs = CircuitInputs::test_inputs();
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let (_, counters) = crate::metrics_helpers::record_counters(|| {
+        let prover = WormholeProver::new(CIRCUIT_CONFIG);
+        let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+        verifier.verify(proof.clone()).unwrap();
+
+        let mut tampered = proof;
+        tampered.public_inputs[0] += F::ONE;
+        verifier.verify(tampered).unwrap_err();
+    });
+
+    assert_eq!(counters.get(PROOFS_BUILT_COUNTER), Some(&1));
+    assert_eq!(counters.get(PROOFS_VERIFIED_COUNTER), Some(&1));
+    assert_eq!(counters.get(VERIFICATION_FAILURES_COUNTER), Some(&1));
+}
+
+#[test]
+fn peek_root_hash_matches_full_deserialize() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+    let bytes = proof.to_bytes();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let common = &verifier.circuit_data.common;
+    let root_hash = peek_root_hash(&bytes, common).unwrap();
+
+    let deserialized = ProofWithPublicInputs::from_bytes(bytes, common).unwrap();
+    let public_inputs = PublicCircuitInputs::try_from(deserialized).unwrap();
+    assert_eq!(root_hash, public_inputs.root_hash);
+    assert_eq!(root_hash, inputs.public.root_hash);
+}
+
+#[test]
+fn peek_root_hash_rejects_truncated_proof() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let err = peek_root_hash(&[0u8; 16], &verifier.circuit_data.common).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "proof is too short to contain 14 public inputs: 16 bytes"
+    );
+}
+
+#[test]
+fn verifier_compatibility() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    assert!(verifier.is_compatible_with(&verifier.circuit_data));
+
+    let mut config = CIRCUIT_CONFIG;
+    config.fri_config.cap_height += 1;
+    let other = WormholeVerifier::new(config, None);
+    assert!(!verifier.is_compatible_with(&other.circuit_data));
+    assert!(!other.is_compatible_with(&verifier.circuit_data));
+}
+
+#[test]
+fn verifier_compatibility_checks_fri_params() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+
+    // The proof of work bits don't change the circuit digest, only how proofs are verified.
+    let mut config = CIRCUIT_CONFIG;
+    config.fri_config.proof_of_work_bits += 1;
+    let other = WormholeVerifier::new(config, None);
+    assert_eq!(
+        verifier.circuit_data.verifier_only.circuit_digest,
+        other.circuit_data.verifier_only.circuit_digest
+    );
+    assert!(!verifier.is_compatible_with(&other.circuit_data));
+
+    let mut config = CIRCUIT_CONFIG;
+    config.fri_config.num_query_rounds -= 1;
+    let other = WormholeVerifier::new(config, None);
+    assert!(!verifier.is_compatible_with(&other.circuit_data));
+}
+
+#[cfg(feature = "timing")]
+#[test]
+fn build_duration_is_recorded() {
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let build_duration = verifier.last_build_duration().unwrap();
+    assert!(!build_duration.is_zero());
+
+    let shared = WormholeVerifier::from_circuit_data(verifier.circuit_data.clone());
+    assert_eq!(shared.last_build_duration(), None);
+}
+
+#[test]
+fn verify_for_account_checks_to_account() {
+    let prover = WormholeProver::with_public_to_account(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::with_public_to_account(CIRCUIT_CONFIG);
+    verifier
+        .verify_for_account(proof.clone(), &inputs.private.unspendable_account)
+        .unwrap();
+
+    let other_account = UnspendableAccount::new(&[8u8; 32]);
+    let err = verifier
+        .verify_for_account(proof, &other_account)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "proof funds 0x{}, expected 0x{}",
+            hex::encode(felts_to_bytes(
+                &inputs.private.unspendable_account.account_id
+            )),
+            hex::encode(felts_to_bytes(&other_account.account_id))
+        )
+    );
+}
+
+#[test]
+fn verify_for_account_requires_public_to_account() {
+    let prover = WormholeProver::new(CIRCUIT_CONFIG);
+    let inputs = CircuitInputs::test_inputs();
+    let proof = prover.commit(&inputs).unwrap().prove().unwrap();
+
+    let verifier = WormholeVerifier::new(CIRCUIT_CONFIG, None);
+    let err = verifier
+        .verify_for_account(proof, &inputs.private.unspendable_account)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "proof doesn't reveal its to_account");
+}
@@ -0,0 +1,111 @@
+//! Synthetic storage proof fixtures covering shapes the default proof doesn't.
+//!
+//! Each proof is built leaf first: every parent node embeds the Poseidon hash of its child right
+//! at the start of its right part, and the root hash is the hash of the first node, so the
+//! fixtures verify exactly like a proof fetched from a node would.
+//!
+//! Each shape also comes as [`CircuitInputs`] of the default transfer, see
+//! [`TestInputs::test_inputs`], whose leaf node embeds the hash of the transfer's leaf inputs, so
+//! that the inputs prove in the wormhole circuit as they are.
+use plonky2::{field::types::Field, hash::poseidon::PoseidonHash, plonk::config::Hasher};
+use wormhole_circuit::{
+    inputs::CircuitInputs,
+    leaf::LeafInputs,
+    storage_proof::{MAX_PROOF_LEN, PROOF_NODE_MAX_SIZE_F, StorageProof},
+};
+use zk_circuits_common::circuit::F;
+use zk_circuits_common::utils::{bytes_to_felts, felts_to_bytes};
+
+use crate::storage_proof::{DEFAULT_FUNDING_AMOUNT, TestInputs};
+
+/// The partial key of the leaf node of every fixture.
+const LEAF_PARTIAL_KEY: [u8; 4] = [0x5f, 0x01, 0x02, 0x03];
+
+/// A proof using all [`MAX_PROOF_LEN`] node slots.
+pub fn deep_proof() -> StorageProof {
+    build_proof(deep_branches())
+}
+
+/// [`deep_proof`] as circuit inputs.
+pub fn deep_inputs() -> CircuitInputs {
+    build_inputs(deep_branches())
+}
+
+/// A proof made of a single leaf node, whose hash is the root.
+pub fn single_leaf_proof() -> StorageProof {
+    build_proof(vec![])
+}
+
+/// [`single_leaf_proof`] as circuit inputs.
+pub fn single_leaf_inputs() -> CircuitInputs {
+    build_inputs(vec![])
+}
+
+/// A proof whose branch nodes hold all sixteen sibling hashes, so that each node is close to the
+/// maximum node size.
+pub fn branch_heavy_proof() -> StorageProof {
+    build_proof(heavy_branches())
+}
+
+/// [`branch_heavy_proof`] as circuit inputs.
+pub fn branch_heavy_inputs() -> CircuitInputs {
+    build_inputs(heavy_branches())
+}
+
+fn deep_branches() -> Vec<Vec<u8>> {
+    (1..MAX_PROOF_LEN).map(|i| vec![i as u8; 40]).collect()
+}
+
+fn heavy_branches() -> Vec<Vec<u8>> {
+    (1..4u8)
+        .map(|i| {
+            let mut left = vec![0x80, 0xff, 0xff];
+            left.extend((0..16u8).flat_map(|sibling| [i ^ sibling; 32]));
+            left
+        })
+        .collect()
+}
+
+/// Builds a proof from the left parts of its branch nodes, ordered from the root down, followed
+/// by a fixed leaf node.
+fn build_proof(branch_lefts: Vec<Vec<u8>>) -> StorageProof {
+    let leaf = (LEAF_PARTIAL_KEY.to_vec(), vec![0x42; 32]);
+    let (nodes, root_hash) = build_nodes(branch_lefts, leaf);
+    StorageProof::new(&nodes, root_hash, DEFAULT_FUNDING_AMOUNT).unwrap()
+}
+
+/// Same as [`build_proof`], but the leaf node embeds the hash of the leaf inputs of the default
+/// transfer, whose storage proof and root hash are replaced.
+fn build_inputs(branch_lefts: Vec<Vec<u8>>) -> CircuitInputs {
+    let mut inputs = CircuitInputs::test_inputs();
+    let leaf_hash = LeafInputs::from(&inputs).hash();
+    let leaf = (LEAF_PARTIAL_KEY.to_vec(), felts_to_bytes(&leaf_hash));
+    let (nodes, root_hash) = build_nodes(branch_lefts, leaf);
+    inputs.private.storage_proof = nodes;
+    inputs.public.root_hash = root_hash;
+    inputs
+}
+
+/// Chains the branch nodes above `leaf`, each embedding the hash of its child at the start of its
+/// right part, and returns the nodes along with the root hash.
+fn build_nodes(
+    branch_lefts: Vec<Vec<u8>>,
+    leaf: (Vec<u8>, Vec<u8>),
+) -> (Vec<(Vec<u8>, Vec<u8>)>, [u8; 32]) {
+    let mut nodes = vec![leaf];
+    for left in branch_lefts.into_iter().rev() {
+        let child_hash = node_hash(&nodes[0]);
+        nodes.insert(0, (left, child_hash.to_vec()));
+    }
+    let root_hash = node_hash(&nodes[0]);
+    (nodes, root_hash)
+}
+
+/// The Poseidon hash of a proof node, as embedded in its parent.
+pub(crate) fn node_hash((left, right): &(Vec<u8>, Vec<u8>)) -> [u8; 32] {
+    let mut felts = bytes_to_felts(&[left.as_slice(), right.as_slice()].concat());
+    felts.resize(PROOF_NODE_MAX_SIZE_F, F::ZERO);
+    felts_to_bytes(&PoseidonHash::hash_no_pad(&felts).elements)
+        .try_into()
+        .unwrap()
+}
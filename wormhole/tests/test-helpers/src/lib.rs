@@ -1,10 +1,15 @@
-use crate::storage_proof::{DEFAULT_ROOT_HASH, TestInputs, default_storage_proof};
+pub mod fixtures;
+
+use crate::storage_proof::{DEFAULT_FUNDING_AMOUNT, TestInputs, storage_proof_for_leaf};
+use plonky2::field::types::Field;
 use wormhole_circuit::{
     inputs::{CircuitInputs, PrivateCircuitInputs, PublicCircuitInputs},
+    leaf::LeafInputs,
     nullifier::Nullifier,
     substrate_account::SubstrateAccount,
     unspendable_account::UnspendableAccount,
 };
+use zk_circuits_common::circuit::F;
 
 pub const DEFAULT_SECRET: &str = "9aa84f99ef2de22e3070394176868df41d6a148117a36132d010529e19b018b7";
 pub const DEFAULT_FUNDING_NONCE: u32 = 0;
@@ -13,19 +18,22 @@ pub const DEFAULT_FUNDING_ACCOUNT: &[u8] = &[10u8; 32];
 impl TestInputs for CircuitInputs {
     fn test_inputs() -> Self {
         let secret = hex::decode(DEFAULT_SECRET.trim()).unwrap();
-        let root_hash: [u8; 32] = hex::decode(DEFAULT_ROOT_HASH.trim())
-            .unwrap()
-            .try_into()
-            .unwrap();
 
         let funding_account = SubstrateAccount::new(DEFAULT_FUNDING_ACCOUNT).unwrap();
         let nullifier = Nullifier::new(&secret, DEFAULT_FUNDING_NONCE, DEFAULT_FUNDING_ACCOUNT);
         let unspendable_account = UnspendableAccount::new(&secret);
         let exit_account = SubstrateAccount::new(&[254u8; 32]).unwrap();
-        let storage_proof = default_storage_proof();
+        let leaf = LeafInputs {
+            funding_nonce: DEFAULT_FUNDING_NONCE,
+            funding_account,
+            to_account: unspendable_account.account_id,
+            funding_amount: DEFAULT_FUNDING_AMOUNT,
+            funding_accounts: vec![],
+        };
+        let (storage_proof, root_hash) = storage_proof_for_leaf(leaf.hash());
         Self {
             public: PublicCircuitInputs {
-                funding_amount: 0,
+                funding_amount: DEFAULT_FUNDING_AMOUNT,
                 nullifier,
                 root_hash,
                 exit_account,
@@ -33,21 +41,38 @@ impl TestInputs for CircuitInputs {
             private: PrivateCircuitInputs {
                 secret,
                 storage_proof,
-                funding_nonce: 0,
+                funding_nonce: DEFAULT_FUNDING_NONCE,
                 funding_account,
+                funding_accounts: vec![],
                 unspendable_account,
             },
         }
     }
 }
 
+/// The default inputs, see [`TestInputs::test_inputs`], with the leaf hashed in the domain of
+/// `chain_id`, for a circuit built with `WormholeCircuit::with_chain_id`.
+pub fn test_inputs_on_chain(chain_id: u32) -> CircuitInputs {
+    let mut inputs = CircuitInputs::test_inputs();
+    let leaf_hash = LeafInputs::from(&inputs).hash_with_domain(F::from_canonical_u32(chain_id));
+    let (storage_proof, root_hash) = storage_proof_for_leaf(leaf_hash);
+    inputs.private.storage_proof = storage_proof;
+    inputs.public.root_hash = root_hash;
+    inputs
+}
+
 pub mod storage_proof {
     use wormhole_circuit::storage_proof::StorageProof;
+    use zk_circuits_common::utils::{Digest, felts_to_bytes};
+
+    use crate::fixtures::node_hash;
 
     #[allow(dead_code)]
     pub const DEFAULT_FUNDING_AMOUNT: u128 = 1000;
     pub const DEFAULT_ROOT_HASH: &str =
         "77eb9d80cd12acfd902b459eb3b8876f05f31ef6a17ed5fdb060ee0e86dd8139";
+    /// A storage proof captured from a node. All three nodes are branches: the last one embeds the
+    /// hashes of two leaves, which the proof doesn't include.
     pub const DEFAULT_STORAGE_PROOF: [(&str, &str); 3] = [
         (
             "802cb08072547dce8ca905abf49c9c644951ff048087cc6f4b497fcc6c24e5592da3bc6a80c9f21db91c755ab0e99f00c73c93eb1742e9d8ba3facffa6e5fda8718006e05e80e4faa006b3beae9cb837950c42a2ab760843d05d224dc437b1add4627ddf6b4580",
@@ -74,6 +99,7 @@ pub mod storage_proof {
                 default_root_hash(),
                 DEFAULT_FUNDING_AMOUNT,
             )
+            .unwrap()
         }
     }
 
@@ -90,6 +116,32 @@ pub mod storage_proof {
     pub fn default_root_hash() -> [u8; 32] {
         hex::decode(DEFAULT_ROOT_HASH).unwrap().try_into().unwrap()
     }
+
+    /// The default storage proof, with the first leaf hash embedded by its last node replaced by
+    /// `leaf_hash`, and every node above it rehashed, along with the resulting root hash. The
+    /// captured proof commits to leaves whose inputs aren't known, so this stands in for a proof
+    /// of a leaf whose inputs are. Each node embeds its child hash at the start of its right part.
+    pub fn storage_proof_for_leaf(leaf_hash: Digest) -> (Vec<(Vec<u8>, Vec<u8>)>, [u8; 32]) {
+        let (left, mut right) = default_storage_proof().pop().unwrap();
+        right[..32].copy_from_slice(&felts_to_bytes(&leaf_hash));
+        storage_proof_ending_with((left, right))
+    }
+
+    /// The default storage proof, with its last node replaced by `last_node`, and every node above
+    /// it rehashed, along with the resulting root hash. This stands in for a proof of a leaf node
+    /// of a known layout, e.g. one holding a given storage key.
+    pub fn storage_proof_ending_with(
+        last_node: (Vec<u8>, Vec<u8>),
+    ) -> (Vec<(Vec<u8>, Vec<u8>)>, [u8; 32]) {
+        let mut nodes = default_storage_proof();
+        *nodes.last_mut().unwrap() = last_node;
+        let mut child_hash = node_hash(nodes.last().unwrap());
+        for node in nodes.iter_mut().rev().skip(1) {
+            node.1[..32].copy_from_slice(&child_hash);
+            child_hash = node_hash(node);
+        }
+        (nodes, child_hash)
+    }
 }
 
 pub mod nullifier {
@@ -0,0 +1,48 @@
+//! Text encodings of serialized proofs, for embedding them in JSON payloads.
+//!
+//! Base64 is about two thirds the size of hex, so it should be preferred for API payloads.
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use plonky2::plonk::{circuit_data::CommonCircuitData, proof::ProofWithPublicInputs};
+use zk_circuits_common::circuit::{C, D, F};
+
+/// Encodes `proof` as a hex string, without a `0x` prefix.
+pub fn proof_to_hex(proof: &ProofWithPublicInputs<F, C, D>) -> String {
+    hex::encode(proof.to_bytes())
+}
+
+/// Decodes a proof produced by [`proof_to_hex`]. A `0x` prefix is accepted.
+///
+/// # Errors
+///
+/// Returns an error if the string is not valid hex, or does not contain a proof for the circuit
+/// described by `common`.
+pub fn proof_from_hex(
+    encoded: &str,
+    common: &CommonCircuitData<F, D>,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let encoded = encoded.strip_prefix("0x").unwrap_or(encoded);
+    let bytes = hex::decode(encoded).map_err(|e| anyhow!("invalid hex proof: {}", e))?;
+    ProofWithPublicInputs::from_bytes(bytes, common)
+}
+
+/// Encodes `proof` as a standard, padded base64 string.
+pub fn proof_to_base64(proof: &ProofWithPublicInputs<F, C, D>) -> String {
+    STANDARD.encode(proof.to_bytes())
+}
+
+/// Decodes a proof produced by [`proof_to_base64`].
+///
+/// # Errors
+///
+/// Returns an error if the string is not valid base64, or does not contain a proof for the
+/// circuit described by `common`.
+pub fn proof_from_base64(
+    encoded: &str,
+    common: &CommonCircuitData<F, D>,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|e| anyhow!("invalid base64 proof: {}", e))?;
+    ProofWithPublicInputs::from_bytes(bytes, common)
+}
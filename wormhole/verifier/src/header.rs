@@ -0,0 +1,105 @@
+//! Serialization of proofs with a metadata header.
+//!
+//! The header identifies the circuit a proof was generated for, so that proofs of incompatible
+//! circuits are rejected before they are deserialized. It is laid out as follows:
+//!
+//! ```text
+//! magic: 4 bytes | version: 1 byte | zk mode: 1 byte | circuit digest: 32 bytes
+//! ```
+use anyhow::{bail, ensure};
+use plonky2::plonk::{circuit_data::VerifierCircuitData, proof::ProofWithPublicInputs};
+use zk_circuits_common::circuit::{C, D, F};
+use zk_circuits_common::utils::felts_to_bytes;
+
+/// Identifies a serialized wormhole proof.
+pub const PROOF_HEADER_MAGIC: [u8; 4] = *b"WHPF";
+/// The current version of the header layout.
+pub const PROOF_HEADER_VERSION: u8 = 1;
+/// The size of the header in bytes.
+pub const PROOF_HEADER_LEN: usize = 4 + 1 + 1 + 32;
+
+/// Serializes `proof` prefixed with a header describing `circuit_data`.
+pub fn serialize_proof_with_header(
+    proof: &ProofWithPublicInputs<F, C, D>,
+    circuit_data: &VerifierCircuitData<F, C, D>,
+) -> Vec<u8> {
+    let mut bytes = header(circuit_data);
+    bytes.extend(proof.to_bytes());
+    bytes
+}
+
+/// Deserializes a proof produced by [`serialize_proof_with_header`].
+///
+/// # Errors
+///
+/// Returns an error if the header is malformed, or if it was produced for a circuit other than
+/// the one described by `circuit_data`.
+pub fn deserialize_proof_with_header(
+    bytes: &[u8],
+    circuit_data: &VerifierCircuitData<F, C, D>,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    ensure!(
+        bytes.len() >= PROOF_HEADER_LEN,
+        "proof is too short to contain a header: {} bytes",
+        bytes.len()
+    );
+    let (header_bytes, proof_bytes) = bytes.split_at(PROOF_HEADER_LEN);
+    let expected = header(circuit_data);
+
+    if header_bytes[..4] != PROOF_HEADER_MAGIC {
+        bail!("proof header has an invalid magic")
+    }
+    if header_bytes[4] != PROOF_HEADER_VERSION {
+        bail!("unsupported proof header version: {}", header_bytes[4])
+    }
+    if header_bytes[5] != expected[5] {
+        bail!("proof zero-knowledge mode does not match the circuit")
+    }
+    if header_bytes[6..] != expected[6..] {
+        bail!("proof circuit digest does not match the circuit")
+    }
+
+    ProofWithPublicInputs::from_bytes(proof_bytes.to_vec(), &circuit_data.common)
+}
+
+/// Reads the circuit digest from the header of a proof produced by
+/// [`serialize_proof_with_header`], without deserializing the proof itself.
+///
+/// # Errors
+///
+/// Returns an error if the header is malformed.
+pub fn proof_circuit_digest(bytes: &[u8]) -> anyhow::Result<[u8; 32]> {
+    ensure!(
+        bytes.len() >= PROOF_HEADER_LEN,
+        "proof is too short to contain a header: {} bytes",
+        bytes.len()
+    );
+    if bytes[..4] != PROOF_HEADER_MAGIC {
+        bail!("proof header has an invalid magic")
+    }
+    if bytes[4] != PROOF_HEADER_VERSION {
+        bail!("unsupported proof header version: {}", bytes[4])
+    }
+
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&bytes[6..PROOF_HEADER_LEN]);
+    Ok(digest)
+}
+
+/// The circuit digest of `circuit_data`, as it is written in a proof header.
+pub fn circuit_digest(circuit_data: &VerifierCircuitData<F, C, D>) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&felts_to_bytes(
+        &circuit_data.verifier_only.circuit_digest.elements,
+    ));
+    digest
+}
+
+fn header(circuit_data: &VerifierCircuitData<F, C, D>) -> Vec<u8> {
+    let mut header = Vec::with_capacity(PROOF_HEADER_LEN);
+    header.extend_from_slice(&PROOF_HEADER_MAGIC);
+    header.push(PROOF_HEADER_VERSION);
+    header.push(circuit_data.common.config.zero_knowledge as u8);
+    header.extend(circuit_digest(circuit_data));
+    header
+}
@@ -8,6 +8,11 @@
 //! 2. Generating a [`ProofWithPublicInputs`].
 //! 3. Verifying the proof using [`WormholeVerifier::verify`].
 //!
+//! With the `metrics` feature enabled, every verification increments either the
+//! [`PROOFS_VERIFIED_COUNTER`] or the [`VERIFICATION_FAILURES_COUNTER`] counter. With the
+//! `timing` feature enabled, the verifier records how long building its circuit took, see
+//! [`WormholeVerifier::last_build_duration`].
+//!
 //!# Example
 //!
 //! Create a verifier and verify a proof:
@@ -28,6 +33,7 @@
 //!         secret: vec![1u8; 32],
 //!         funding_nonce: 0,
 //!         funding_account: SubstrateAccount::new(&[2u8; 32])?,
+//!         funding_accounts: vec![],
 //!         storage_proof: vec![],
 //!         unspendable_account: UnspendableAccount::new(&[1u8; 32]),
 //!     },
@@ -50,23 +56,88 @@
 //! # Ok(())
 //! # }
 //! ```
-use plonky2::plonk::circuit_data::{CircuitConfig, VerifierCircuitData};
+pub mod encoding;
+pub mod header;
+
+use core::fmt;
+
+use anyhow::{bail, ensure};
+use plonky2::field::types::{Field, Field64};
+use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData, VerifierCircuitData};
+use serde_json::json;
+#[cfg(feature = "timing")]
+use std::time::{Duration, Instant};
 
 use wormhole_circuit::circuit::WormholeCircuit;
+use wormhole_circuit::inputs::{
+    extract_leaf_public, PublicCircuitInputs, PUBLIC_INPUTS_FELTS_LEN, ROOT_HASH_END_INDEX,
+    ROOT_HASH_START_INDEX,
+};
+use wormhole_circuit::unspendable_account::UnspendableAccount;
 use zk_circuits_common::circuit::{C, D, F};
+use zk_circuits_common::utils::felts_to_bytes;
 
 pub use plonky2::plonk::proof::ProofWithPublicInputs;
 
+/// The name of the counter of proofs that passed verification, incremented with the `metrics`
+/// feature.
+pub const PROOFS_VERIFIED_COUNTER: &str = "wormhole_proofs_verified_total";
+/// The name of the counter of proofs that failed verification, incremented with the `metrics`
+/// feature.
+pub const VERIFICATION_FAILURES_COUNTER: &str = "wormhole_verification_failures_total";
+
+/// The reason a proof was rejected by [`WormholeVerifier::validate_shape`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeError {
+    /// The proof doesn't have the expected number of public inputs.
+    PublicInputsLen { expected: usize, got: usize },
+    /// A root hash element, at the given public input index, is not a canonical field element.
+    NonCanonicalRootHash { index: usize },
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PublicInputsLen { expected, got } => write!(
+                f,
+                "public inputs should contain: {} field elements, got: {}",
+                expected, got
+            ),
+            Self::NonCanonicalRootHash { index } => write!(
+                f,
+                "root hash public input at index {} is not a canonical field element",
+                index
+            ),
+        }
+    }
+}
+
+/// Whether a proof is a single wormhole proof, or an aggregate of wormhole proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofKind {
+    Single,
+    Aggregate,
+}
+
 pub struct WormholeVerifier {
     pub circuit_data: VerifierCircuitData<F, C, D>,
+    /// The verifier data of the aggregation circuit, required by
+    /// [`WormholeVerifier::verify_aggregate`].
+    pub aggregate_circuit_data: Option<VerifierCircuitData<F, C, D>>,
+    /// The verifier data of older circuit versions whose proofs are still accepted by
+    /// [`WormholeVerifier::verify_with_header`].
+    pub previous_circuit_data: Vec<VerifierCircuitData<F, C, D>>,
+    /// The verifier data of each layer of a recursive aggregation, starting with the layer that
+    /// aggregates wormhole proofs. Required by [`WormholeVerifier::verify_layer`].
+    pub layer_circuit_data: Vec<VerifierCircuitData<F, C, D>>,
+    /// How long building the circuit took, recorded with the `timing` feature.
+    #[cfg(feature = "timing")]
+    build_duration: Option<Duration>,
 }
 
 impl Default for WormholeVerifier {
     fn default() -> Self {
-        let wormhole_circuit = WormholeCircuit::default();
-        let circuit_data = wormhole_circuit.build_verifier();
-
-        Self { circuit_data }
+        Self::build(WormholeCircuit::default())
     }
 }
 
@@ -74,13 +145,151 @@ impl WormholeVerifier {
     /// Creates a new [`WormholeVerifier`]. Optionally, a [`VerifierCircuitData`] can be passed in
     /// to skip the circuit compilation step.
     pub fn new(config: CircuitConfig, circuit_data: Option<VerifierCircuitData<F, C, D>>) -> Self {
-        let wormhole_circuit = WormholeCircuit::new(config);
-        let circuit_data = match circuit_data {
-            Some(circuit_data) => circuit_data,
-            None => wormhole_circuit.build_verifier(),
-        };
+        match circuit_data {
+            Some(circuit_data) => Self::from_circuit_data(circuit_data),
+            None => Self::build(WormholeCircuit::new(config)),
+        }
+    }
+
+    /// Creates a new [`WormholeVerifier`] for proofs that reveal the id of the chain of their
+    /// leaf, see [`WormholeCircuit::with_chain_id`].
+    pub fn with_chain_id(config: CircuitConfig) -> Self {
+        Self::build(WormholeCircuit::with_chain_id(config))
+    }
+
+    /// Creates a new [`WormholeVerifier`] for proofs that reveal the to_account of their leaf,
+    /// see [`WormholeCircuit::with_public_to_account`].
+    pub fn with_public_to_account(config: CircuitConfig) -> Self {
+        Self::build(WormholeCircuit::with_public_to_account(config))
+    }
+
+    fn build(wormhole_circuit: WormholeCircuit) -> Self {
+        #[cfg(feature = "timing")]
+        let start = Instant::now();
+
+        let circuit_data = wormhole_circuit.build_verifier();
+        Self {
+            #[cfg(feature = "timing")]
+            build_duration: Some(start.elapsed()),
+            ..Self::from_circuit_data(circuit_data)
+        }
+    }
+
+    /// How long building the circuit of this verifier took. Returns `None` if the verifier was
+    /// created from circuit data that was already built.
+    #[cfg(feature = "timing")]
+    pub fn last_build_duration(&self) -> Option<Duration> {
+        self.build_duration
+    }
 
-        Self { circuit_data }
+    /// Creates a [`WormholeVerifier`] from circuit data that was already built or loaded, e.g. by
+    /// another verifier or the aggregator. The circuit is not rebuilt.
+    pub fn from_circuit_data(circuit_data: VerifierCircuitData<F, C, D>) -> Self {
+        Self {
+            circuit_data,
+            aggregate_circuit_data: None,
+            previous_circuit_data: Vec::new(),
+            layer_circuit_data: Vec::new(),
+            #[cfg(feature = "timing")]
+            build_duration: None,
+        }
+    }
+
+    /// Sets the verifier data of the aggregation circuit, enabling
+    /// [`WormholeVerifier::verify_aggregate`].
+    pub fn with_aggregate_circuit_data(
+        mut self,
+        aggregate_circuit_data: VerifierCircuitData<F, C, D>,
+    ) -> Self {
+        self.aggregate_circuit_data = Some(aggregate_circuit_data);
+        self
+    }
+
+    /// Sets the verifier data of every layer of a recursive aggregation, enabling
+    /// [`WormholeVerifier::verify_layer`]. The number of layers is the length of
+    /// `layer_circuit_data`, and the first layer is the one aggregating wormhole proofs.
+    pub fn with_layer_circuit_data(
+        mut self,
+        layer_circuit_data: Vec<VerifierCircuitData<F, C, D>>,
+    ) -> Self {
+        self.layer_circuit_data = layer_circuit_data;
+        self
+    }
+
+    /// The verifier data of aggregation layer `layer`, if the verifier was configured with that
+    /// many layers.
+    pub fn layer_circuit_data(&self, layer: usize) -> Option<&VerifierCircuitData<F, C, D>> {
+        self.layer_circuit_data.get(layer)
+    }
+
+    /// Toggles blinding of proofs at runtime. Randomized proofs are generated with the circuit's
+    /// zero-knowledge mode enabled, while non-randomized proofs are deterministic, which is useful
+    /// for golden tests. The circuit is rebuilt if the mode changes, so the verifier must be
+    /// configured the same way as the prover that generated the proofs.
+    pub fn with_randomness(mut self, randomized: bool) -> Self {
+        let mut config = self.circuit_data.common.config.clone();
+        if config.zero_knowledge != randomized {
+            config.zero_knowledge = randomized;
+            let rebuilt = Self::build(WormholeCircuit::new(config));
+            self.circuit_data = rebuilt.circuit_data;
+            #[cfg(feature = "timing")]
+            {
+                self.build_duration = rebuilt.build_duration;
+            }
+        }
+        self
+    }
+
+    /// Adds the verifier data of an older circuit version, so that proofs generated before a
+    /// circuit upgrade can still be verified with [`WormholeVerifier::verify_with_header`].
+    pub fn with_previous_circuit_data(
+        mut self,
+        previous_circuit_data: VerifierCircuitData<F, C, D>,
+    ) -> Self {
+        self.previous_circuit_data.push(previous_circuit_data);
+        self
+    }
+
+    /// Checks whether proofs of the circuit described by `other` can be verified by this verifier,
+    /// e.g. for circuit data loaded from disk. The circuit digests must match, which commits to
+    /// the circuit's constants, and so must the merkle cap heights and the FRI parameters, which
+    /// the digest doesn't commit to but the proofs are verified against.
+    pub fn is_compatible_with(&self, other: &VerifierCircuitData<F, C, D>) -> bool {
+        let verifier_only = &self.circuit_data.verifier_only;
+        let fri_config = &self.circuit_data.common.config.fri_config;
+        let other_fri_config = &other.common.config.fri_config;
+        verifier_only.circuit_digest == other.verifier_only.circuit_digest
+            && verifier_only.constants_sigmas_cap.height()
+                == other.verifier_only.constants_sigmas_cap.height()
+            && fri_config.rate_bits == other_fri_config.rate_bits
+            && fri_config.num_query_rounds == other_fri_config.num_query_rounds
+            && fri_config.proof_of_work_bits == other_fri_config.proof_of_work_bits
+    }
+
+    /// Returns the size of the circuit's low-degree extension times its number of FFT layers,
+    /// which dominates proving time. Dividing a measured proving time, e.g. from the prover
+    /// benchmark, by this gives the per-row cost to pass to [`Self::estimate_prove_ms`].
+    pub fn lde_row_layers(&self) -> u64 {
+        let common = &self.circuit_data.common;
+        let lde_bits = (common.degree_bits() + common.config.fri_config.rate_bits) as u64;
+        (1u64 << lde_bits) * lde_bits
+    }
+
+    /// Returns an estimate of the time it takes to prove the circuit, in milliseconds, given the
+    /// proving cost in nanoseconds per row of the low-degree extension and per FFT layer. That
+    /// cost depends on the machine, so it must be calibrated there, see [`Self::lde_row_layers`].
+    pub fn estimate_prove_ms(&self, ns_per_lde_row_layer: u64) -> u64 {
+        (self.lde_row_layers() * ns_per_lde_row_layer).div_ceil(1_000_000)
+    }
+
+    /// Returns the conjectured soundness of the circuit's proofs, in bits. Each FRI query round
+    /// contributes `rate_bits` bits and the proof of work grinding `proof_of_work_bits`, capped by
+    /// the size of the extension field the challenges are drawn from.
+    pub fn security_bits(&self) -> u32 {
+        let fri_config = &self.circuit_data.common.config.fri_config;
+        let fri_bits = fri_config.rate_bits * fri_config.num_query_rounds
+            + fri_config.proof_of_work_bits as usize;
+        fri_bits.min(F::BITS * D) as u32
     }
 
     /// Verify a [`ProofWithPublicInputs`].
@@ -89,6 +298,256 @@ impl WormholeVerifier {
     ///
     /// Returns an error if the proof is not valid.
     pub fn verify(&self, proof: ProofWithPublicInputs<F, C, D>) -> anyhow::Result<()> {
-        self.circuit_data.verify(proof)
+        record_verification(self.circuit_data.verify(proof))
+    }
+
+    /// Verifies `proof` and checks that its leaf funded `expected`, i.e. that the to_account
+    /// revealed in its public inputs is the account id of `expected`. The proof must come from a
+    /// circuit built with [`WormholeCircuit::with_public_to_account`], see
+    /// [`WormholeVerifier::with_public_to_account`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proof doesn't reveal its to_account, if it is not valid, or if its
+    /// to_account isn't the one of `expected`.
+    pub fn verify_for_account(
+        &self,
+        proof: ProofWithPublicInputs<F, C, D>,
+        expected: &UnspendableAccount,
+    ) -> anyhow::Result<()> {
+        let Some(to_account) = extract_leaf_public(&proof)?.to_account else {
+            bail!("proof doesn't reveal its to_account")
+        };
+        self.verify(proof)?;
+        ensure!(
+            to_account == expected.account_id,
+            "proof funds 0x{}, expected 0x{}",
+            hex::encode(felts_to_bytes(&to_account)),
+            hex::encode(felts_to_bytes(&expected.account_id))
+        );
+        Ok(())
+    }
+
+    /// Cheaply checks that the public inputs of `proof` are well formed, without verifying the
+    /// proof itself. This is meant as a pre-filter to reject malformed proofs before spending
+    /// cycles on [`WormholeVerifier::verify`], and does not imply that the proof is valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ShapeError`] if the number of public inputs is wrong, or if the root hash
+    /// contains non-canonical field elements.
+    pub fn validate_shape(&self, proof: &ProofWithPublicInputs<F, C, D>) -> Result<(), ShapeError> {
+        let public_inputs = &proof.public_inputs;
+        if public_inputs.len() != PUBLIC_INPUTS_FELTS_LEN {
+            return Err(ShapeError::PublicInputsLen {
+                expected: PUBLIC_INPUTS_FELTS_LEN,
+                got: public_inputs.len(),
+            });
+        }
+
+        for index in ROOT_HASH_START_INDEX..ROOT_HASH_END_INDEX {
+            if public_inputs[index].0 >= F::ORDER {
+                return Err(ShapeError::NonCanonicalRootHash { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify a proof serialized with [`header::serialize_proof_with_header`]. The proof is routed
+    /// to the current circuit, or to one of the previous circuit versions, based on the circuit
+    /// digest in its header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is malformed, if no known circuit version matches its
+    /// digest, or if the proof is not valid.
+    pub fn verify_with_header(&self, bytes: &[u8]) -> anyhow::Result<()> {
+        let digest = header::proof_circuit_digest(bytes)?;
+        let Some(circuit_data) = core::iter::once(&self.circuit_data)
+            .chain(&self.previous_circuit_data)
+            .find(|circuit_data| header::circuit_digest(circuit_data) == digest)
+        else {
+            bail!("no known circuit version matches the proof circuit digest")
+        };
+
+        let proof = header::deserialize_proof_with_header(bytes, circuit_data)?;
+        record_verification(circuit_data.verify(proof))
+    }
+
+    /// Deserializes and verifies a proof, returning the outcome as a JSON object of the form:
+    ///
+    /// ```json
+    /// { "valid": true, "root_hash": "0x...", "error": null }
+    /// ```
+    ///
+    /// On failure `valid` is `false`, `root_hash` is `null` and `error` holds the error message.
+    pub fn verify_to_json(&self, proof_bytes: &[u8]) -> String {
+        let result =
+            ProofWithPublicInputs::from_bytes(proof_bytes.to_vec(), &self.circuit_data.common)
+                .and_then(|proof| {
+                    self.verify(proof.clone())?;
+                    PublicCircuitInputs::try_from(proof)
+                });
+
+        let output = match result {
+            Ok(public_inputs) => json!({
+                "valid": true,
+                "root_hash": format!("0x{}", hex::encode(public_inputs.root_hash)),
+                "error": null,
+            }),
+            Err(e) => json!({
+                "valid": false,
+                "root_hash": null,
+                "error": e.to_string(),
+            }),
+        };
+        output.to_string()
+    }
+
+    /// Verify an aggregated [`ProofWithPublicInputs`]. The inner proofs are verified inside the
+    /// aggregation circuit, so only the aggregate proof itself is checked here.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no aggregate circuit data was set, or if the proof is not valid.
+    pub fn verify_aggregate(&self, proof: ProofWithPublicInputs<F, C, D>) -> anyhow::Result<()> {
+        let Some(aggregate_circuit_data) = self.aggregate_circuit_data.as_ref() else {
+            bail!("verifier has no aggregate circuit data")
+        };
+        record_verification(aggregate_circuit_data.verify(proof))
     }
+
+    /// Checks whether `proof` has the shape of an aggregate proof, without verifying it: the
+    /// number of public inputs of the aggregation circuit, and merkle proofs as deep as its
+    /// low-degree extension. Always false if no aggregate circuit data was set.
+    pub fn is_aggregate_proof(&self, proof: &ProofWithPublicInputs<F, C, D>) -> bool {
+        self.aggregate_circuit_data
+            .as_ref()
+            .is_some_and(|data| has_shape_of(proof, &data.common))
+    }
+
+    /// Same as [`WormholeVerifier::verify`] or [`WormholeVerifier::verify_aggregate`], depending
+    /// on `kind`, but first rejects a proof of the other kind with a clear error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proof is not of the given kind, or if it is not valid.
+    pub fn verify_kind(
+        &self,
+        proof: ProofWithPublicInputs<F, C, D>,
+        kind: ProofKind,
+    ) -> anyhow::Result<()> {
+        let is_aggregate = self.is_aggregate_proof(&proof);
+        match kind {
+            ProofKind::Single => {
+                ensure!(
+                    !is_aggregate,
+                    "expected a single proof, got an aggregate proof"
+                );
+                self.verify(proof)
+            }
+            ProofKind::Aggregate => {
+                ensure!(
+                    is_aggregate || self.aggregate_circuit_data.is_none(),
+                    "expected an aggregate proof, got a single proof"
+                );
+                self.verify_aggregate(proof)
+            }
+        }
+    }
+
+    /// Verify a proof produced by aggregation layer `layer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verifier has no data for `layer`, or if the proof is not valid.
+    pub fn verify_layer(
+        &self,
+        layer: usize,
+        proof: ProofWithPublicInputs<F, C, D>,
+    ) -> anyhow::Result<()> {
+        let Some(layer_circuit_data) = self.layer_circuit_data(layer) else {
+            bail!(
+                "verifier has no circuit data for aggregation layer {}, only {} layers",
+                layer,
+                self.layer_circuit_data.len()
+            )
+        };
+        record_verification(layer_circuit_data.verify(proof))
+    }
+}
+
+/// Whether `proof` has the shape of a proof of the circuit described by `common`. The depth of the
+/// first merkle proof of the FRI queries is the size of the circuit's low-degree extension, minus
+/// the cap height, so circuits of different sizes are told apart.
+fn has_shape_of(proof: &ProofWithPublicInputs<F, C, D>, common: &CommonCircuitData<F, D>) -> bool {
+    let fri_config = &common.config.fri_config;
+    let merkle_depth = common.degree_bits() + fri_config.rate_bits - fri_config.cap_height;
+    let first_merkle_proof = proof
+        .proof
+        .opening_proof
+        .query_round_proofs
+        .first()
+        .and_then(|round| round.initial_trees_proof.evals_proofs.first());
+
+    proof.public_inputs.len() == common.num_public_inputs
+        && first_merkle_proof
+            .is_some_and(|(_, merkle_proof)| merkle_proof.siblings.len() == merkle_depth)
+}
+
+/// Counts the outcome of a verification when the `metrics` feature is enabled, and passes it
+/// through.
+fn record_verification(result: anyhow::Result<()>) -> anyhow::Result<()> {
+    #[cfg(feature = "metrics")]
+    match &result {
+        Ok(()) => metrics::counter!(PROOFS_VERIFIED_COUNTER).increment(1),
+        Err(_) => metrics::counter!(VERIFICATION_FAILURES_COUNTER).increment(1),
+    }
+    result
+}
+
+/// Reads the root hash from a proof serialized with [`ProofWithPublicInputs::to_bytes`], without
+/// deserializing the proof. The public inputs are serialized last, as their count followed by one
+/// little-endian `u64` per element, so only the tail of `bytes` is read. This is meant for routing
+/// proofs cheaply, and doesn't imply that the proof is valid.
+///
+/// # Errors
+///
+/// Returns an error if `bytes` is too short, or doesn't end with the number of public inputs
+/// expected by `common`.
+pub fn peek_root_hash(bytes: &[u8], common: &CommonCircuitData<F, D>) -> anyhow::Result<[u8; 32]> {
+    const FELT_BYTES: usize = 8;
+
+    let num_public_inputs = common.num_public_inputs;
+    ensure!(
+        num_public_inputs >= ROOT_HASH_END_INDEX,
+        "circuit has {} public inputs, too few to contain a root hash",
+        num_public_inputs
+    );
+
+    let tail_len = FELT_BYTES * (num_public_inputs + 1);
+    ensure!(
+        bytes.len() >= tail_len,
+        "proof is too short to contain {} public inputs: {} bytes",
+        num_public_inputs,
+        bytes.len()
+    );
+    let (len_bytes, public_inputs) = bytes[bytes.len() - tail_len..].split_at(FELT_BYTES);
+
+    let mut len = [0u8; FELT_BYTES];
+    len.copy_from_slice(len_bytes);
+    let len = u64::from_le_bytes(len);
+    ensure!(
+        len == num_public_inputs as u64,
+        "proof has {} public inputs, expected {}",
+        len,
+        num_public_inputs
+    );
+
+    let mut root_hash = [0u8; 32];
+    root_hash.copy_from_slice(
+        &public_inputs[ROOT_HASH_START_INDEX * FELT_BYTES..ROOT_HASH_END_INDEX * FELT_BYTES],
+    );
+    Ok(root_hash)
 }